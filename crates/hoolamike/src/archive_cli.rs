@@ -1,8 +1,9 @@
 use {
-    crate::{compression::ProcessArchive, utils::PathReadWrite},
+    crate::{compression::ProcessArchive, path_resolver, utils::PathReadWrite},
     anyhow::{Context, Result},
     itertools::Itertools,
-    std::path::PathBuf,
+    std::path::{Path, PathBuf},
+    tap::prelude::*,
     tracing::info,
 };
 
@@ -31,8 +32,14 @@ impl ArchiveCliCommand {
                     .and_then(|paths| archive.get_many_handles(paths.iter().map(|p| p.as_path()).collect_vec().as_slice()))
                     .and_then(|handles| {
                         handles.into_iter().try_for_each(|(path, mut handle)| {
-                            path.open_file_write()
-                                .and_then(|(_, mut file)| std::io::copy(&mut handle, &mut file).context("writing extracted file"))
+                            // entries are extracted relative to the current directory, and have to stay there
+                            path_resolver::output_path(Path::new("."), &path)
+                                .and_then(|output| output.open_file_write())
+                                .and_then(|(output, mut file)| {
+                                    std::io::copy(&mut handle, &mut file)
+                                        .context("writing extracted file")
+                                        .tap_ok(|_| path_resolver::resolver().written(&output))
+                                })
                                 .map(|size| info!(%size, "{path:?}"))
                         })
                     })
@@ -1,6 +1,7 @@
 use {
     super::ProcessArchive,
     crate::{
+        path_resolver,
         progress_bars_v2::IndicatifWrapIoExt,
         utils::{MaybeWindowsPath, PathReadWrite, ReadableCatchUnwindExt},
     },
@@ -86,13 +87,14 @@ impl Tes4Archive<'_> {
     }
 }
 
+/// archive entries are matched the same way the path resolver matches files on disk
 fn make_case_insensitive(path: &Path) -> PathBuf {
     path.display()
         .to_string()
-        .to_lowercase()
         .pipe(MaybeWindowsPath)
         .pipe(MaybeWindowsPath::into_path)
         .normalize()
+        .pipe_ref(|path| path_resolver::case_insensitive_path(path))
 }
 
 impl super::ProcessArchive for Fallout4Archive<'_> {
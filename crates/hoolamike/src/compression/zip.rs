@@ -35,6 +35,23 @@ impl ZipArchive {
                 .and_then(|mut archive| with(&mut archive))
         })
     }
+    /// uncompressed sizes, nothing gets extracted
+    pub fn list_paths_with_sizes(&mut self) -> Result<Vec<(PathBuf, u64)>> {
+        self.with_archive(|this| {
+            (0..this.len())
+                .filter_map(|idx| {
+                    this.by_index(idx)
+                        .with_context(|| format!("reading file idx [{idx}]"))
+                        .map(|file| {
+                            file.is_file()
+                                .then(|| (MaybeWindowsPath(file.name().to_string()).into_path(), file.size()))
+                        })
+                        .transpose()
+                })
+                .collect::<Result<_>>()
+                .context("listing archive contents")
+        })
+    }
     fn list_paths_with_originals(&mut self) -> Result<Vec<(String, PathBuf)>> {
         self.with_archive(|this| {
             (0..this.len())
@@ -1,5 +1,8 @@
 use {
-    crate::{modlist_json::GameName, post_install_fixup::common::Resolution},
+    crate::{
+        modlist_json::{ArchiveDescriptor, GameName, HumanUrl},
+        post_install_fixup::common::Resolution,
+    },
     anyhow::{Context, Result},
     indexmap::IndexMap,
     serde::{Deserialize, Serialize},
@@ -17,6 +20,79 @@ pub struct NexusConfig {
     pub api_key: Option<String>,
 }
 
+/// user-supplied replacement for an archive whose modlist source is no longer available
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArchiveOverride {
+    LocalPath { local_path: PathBuf },
+    Url { url: HumanUrl },
+}
+
+/// keyed by archive hash (preferred) or archive name
+pub type ArchiveOverrides = IndexMap<String, ArchiveOverride>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, derivative::Derivative)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct ArchiveUpgradesConfig {
+    /// when the original archive cannot be fetched, download its newer version and rebuild the original using a wabbajack patch
+    #[derivative(Default(value = "true"))]
+    pub enabled: bool,
+    #[derivative(Default(value = "crate::downloaders::wabbajack_upgrades::DEFAULT_UPGRADED_ARCHIVES_URL.parse().expect(\"bad default url\")"))]
+    pub upgraded_archives_url: HumanUrl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, derivative::Derivative)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct GitHubConfig {
+    /// can be pointed at a github-compatible api (or a mirror)
+    #[derivative(Default(value = "crate::downloaders::github::DEFAULT_API_BASE_URL.parse().expect(\"bad default url\")"))]
+    pub api_base_url: HumanUrl,
+    /// optional, raises the api rate limit
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ips4SiteConfig {
+    pub base_url: HumanUrl,
+    /// oauth client registered on the site, required for logging in
+    pub client_id: Option<String>,
+    /// must match the redirect uri of the oauth client, the browser does not need to be able to open it
+    pub redirect_uri: HumanUrl,
+}
+
+fn default_ips4_sites() -> IndexMap<String, Ips4SiteConfig> {
+    [("loverslab", "https://www.loverslab.com"), ("vectorplexus", "https://vectorplexus.com")]
+        .into_iter()
+        .map(|(name, base_url)| {
+            (
+                name.to_string(),
+                Ips4SiteConfig {
+                    base_url: base_url.parse().expect("bad default url"),
+                    client_id: None,
+                    redirect_uri: "http://127.0.0.1/hoolamike-oauth"
+                        .parse()
+                        .expect("bad default url"),
+                },
+            )
+        })
+        .collect()
+}
+
+/// invision community (IPS4) sites, logged in with `hoolamike ips4-login <site>`
+#[derive(Debug, Clone, Serialize, Deserialize, derivative::Derivative)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct Ips4Config {
+    /// oauth tokens are stored here, one file per site. relative to the config file
+    #[derivative(Default(value = "PathBuf::from(\".hoolamike/oauth\")"))]
+    pub token_directory: PathBuf,
+    #[derivative(Default(value = "default_ips4_sites()"))]
+    pub sites: IndexMap<String, Ips4SiteConfig>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, derivative::Derivative)]
 #[derivative(Default)]
 #[serde(deny_unknown_fields)]
@@ -24,6 +100,23 @@ pub struct DownloadersConfig {
     #[derivative(Default(value = "std::env::current_dir().unwrap().join(\"downloads\")"))]
     pub downloads_directory: PathBuf,
     pub nexus: NexusConfig,
+    /// used instead of the modlist-provided source, the result is still validated against the archive hash and size
+    #[serde(default)]
+    pub archive_overrides: ArchiveOverrides,
+    #[serde(default)]
+    pub archive_upgrades: ArchiveUpgradesConfig,
+    #[serde(default)]
+    pub github: GitHubConfig,
+    #[serde(default)]
+    pub ips4: Ips4Config,
+}
+
+impl DownloadersConfig {
+    pub fn archive_override(&self, ArchiveDescriptor { hash, name, .. }: &ArchiveDescriptor) -> Option<&ArchiveOverride> {
+        self.archive_overrides
+            .get(hash)
+            .or_else(|| self.archive_overrides.get(name))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, derivative::Derivative)]
@@ -109,6 +202,11 @@ impl HoolamikeConfig {
                 std::fs::read_to_string(&config_path)
                     .context("reading file")
                     .and_then(|config| serde_yaml::from_str::<Self>(&config).context("parsing config file"))
+                    .map(|config| {
+                        config.tap_mut(|config| {
+                            config.downloaders.ips4.token_directory = relative_to_config_file(&config_path, &config.downloaders.ips4.token_directory)
+                        })
+                    })
                     .map(|config| (config_path, config))
             })
             .with_context(|| format!("getting [{CONFIG_FILE_NAME}]"))
@@ -117,3 +215,48 @@ impl HoolamikeConfig {
             })
     }
 }
+
+/// so that it doesn't matter which directory hoolamike is started from
+fn relative_to_config_file(config_path: &Path, path: &Path) -> PathBuf {
+    match path.is_relative() {
+        true => config_path
+            .parent()
+            .map(|config_directory| config_directory.join(path))
+            .unwrap_or_else(|| path.to_owned()),
+        false => path.to_owned(),
+    }
+}
+
+#[test]
+fn test_relative_to_config_file() {
+    assert_eq!(
+        relative_to_config_file(Path::new("/home/user/modlists/hoolamike.yaml"), Path::new(".hoolamike/oauth")),
+        Path::new("/home/user/modlists/.hoolamike/oauth")
+    );
+    assert_eq!(
+        relative_to_config_file(Path::new("/home/user/hoolamike.yaml"), Path::new("/tokens")),
+        Path::new("/tokens")
+    );
+}
+
+#[test]
+fn test_archive_overrides_parse() -> Result<()> {
+    const EXAMPLE: &str = r#"
+"Sk5VaZ6XOiw=":
+  local_path: /mnt/backup/SomeMod-1234-1-0.7z
+SomeOtherMod.zip:
+  url: https://example.com/SomeOtherMod.zip
+"#;
+    serde_yaml::from_str::<ArchiveOverrides>(EXAMPLE)
+        .context("parsing example overrides")
+        .and_then(|overrides| {
+            matches!(overrides.get("Sk5VaZ6XOiw="), Some(ArchiveOverride::LocalPath { .. }))
+                .then_some(overrides)
+                .context("expected a local path override")
+        })
+        .and_then(|overrides| {
+            matches!(overrides.get("SomeOtherMod.zip"), Some(ArchiveOverride::Url { .. }))
+                .then_some(())
+                .context("expected an url override")
+        })
+}
@@ -0,0 +1,195 @@
+//! peeking inside of a .wabbajack file without installing it
+use {
+    crate::{
+        compression::{zip::ZipArchive, ArchiveFileHandle, ProcessArchive},
+        helpers::human_readable_size,
+        install_modlist::directives::wabbajack_file_handle::WabbajackFileHandle,
+        modlist_json::Modlist,
+        octadiff_reader::{CommandSummary, OctodiffMetadata},
+        utils::{MaybeWindowsPath, PathReadWrite},
+        wabbajack_file::{WabbajackFile, MODLIST_JSON_FILENAME},
+    },
+    anyhow::{Context, Result},
+    itertools::Itertools,
+    std::{
+        collections::HashMap,
+        io::{BufReader, Read, Write},
+        path::{Path, PathBuf},
+    },
+    tracing::info,
+};
+
+#[derive(clap::Subcommand)]
+pub enum WabbajackFileCommand {
+    /// lists the files stored inside of the .wabbajack file, along with what refers to them
+    ListEntries { modlist_file: PathBuf },
+    /// extracts a single file stored inside of the .wabbajack file
+    ExtractEntry {
+        modlist_file: PathBuf,
+        /// path inside of the archive, or the id directives refer to it by (`SourceDataID`, `PatchID`)
+        entry: String,
+        /// defaults to the entry name, in the current directory
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// prints the whole modlist json as it is stored, pretty printed - works for modlists this version can't read
+    DumpModlist { modlist_file: PathBuf },
+    /// lists the commands of a binary patch used by `PatchedFromArchive` directives
+    ExplainPatch { modlist_file: PathBuf, patch_id: uuid::Uuid },
+}
+
+/// what in the modlist refers to each entry
+fn entry_references(modlist: &Modlist) -> HashMap<PathBuf, Vec<String>> {
+    modlist
+        .directives
+        .iter()
+        .enumerate()
+        .filter_map(|(index, directive)| {
+            directive.wabbajack_entry_id().map(|id| {
+                (
+                    WabbajackFileHandle::source_data_path(id),
+                    format!(
+                        "#{index} {} -> {}",
+                        directive.directive_kind(),
+                        directive.to().map(|to| to.0.as_str()).unwrap_or_default()
+                    ),
+                )
+            })
+        })
+        .chain(
+            [("modlist image", &modlist.image), ("readme", &modlist.readme)]
+                .into_iter()
+                .filter(|(_, path)| !path.is_empty())
+                .map(|(what, path)| (MaybeWindowsPath(path.clone()).into_path(), what.to_string())),
+        )
+        .into_group_map()
+}
+
+fn list_entries(modlist_file: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    ZipArchive::new(modlist_file).and_then(|mut archive| archive.list_paths_with_sizes())
+}
+
+/// by path first, ids are matched no matter how they're spelled
+fn resolve_entry(entries: &[(PathBuf, u64)], entry: &str) -> Result<PathBuf> {
+    let by_path = MaybeWindowsPath(entry.to_string()).into_path();
+    entries
+        .iter()
+        .map(|(path, _)| path)
+        .find(|path| **path == by_path)
+        .cloned()
+        .or_else(|| {
+            uuid::Uuid::parse_str(entry)
+                .ok()
+                .map(WabbajackFileHandle::source_data_path)
+                .filter(|path| entries.iter().any(|(entry, _)| entry == path))
+        })
+        .with_context(|| format!("no entry [{entry}] in the .wabbajack file, see `list-entries`"))
+}
+
+/// goes through [serde_json::Value] instead of [Modlist], so that nothing is dropped or required
+fn pretty_print_json(reader: impl Read, writer: impl Write) -> Result<()> {
+    serde_json::from_reader::<_, serde_json::Value>(BufReader::new(reader))
+        .context("reading json")
+        .and_then(|value| serde_json::to_writer_pretty(writer, &value).context("writing json"))
+}
+
+fn extract_entry(modlist_file: &Path, entry: &Path) -> Result<tempfile::NamedTempFile> {
+    ZipArchive::new(modlist_file)
+        .and_then(|mut archive| archive.get_handle(entry))
+        .and_then(|handle| match handle {
+            ArchiveFileHandle::Zip(file) => Ok(file),
+            _ => anyhow::bail!("expected a zip entry"),
+        })
+        .with_context(|| format!("extracting [{}]", entry.display()))
+}
+
+impl WabbajackFileCommand {
+    pub fn run(self) -> Result<()> {
+        match self {
+            WabbajackFileCommand::ListEntries { modlist_file } => WabbajackFile::read_modlist(&modlist_file)
+                .map(|(_, modlist)| entry_references(&modlist))
+                .and_then(|references| list_entries(&modlist_file).map(|entries| (references, entries)))
+                .map(|(references, entries)| {
+                    entries
+                        .into_iter()
+                        .sorted()
+                        .for_each(|(path, size)| match references.get(&path) {
+                            Some(references) => println!("{:>10}  {}  <- {}", human_readable_size(size), path.display(), references.join(", ")),
+                            None => println!("{:>10}  {}", human_readable_size(size), path.display()),
+                        })
+                }),
+            WabbajackFileCommand::ExtractEntry { modlist_file, entry, output } => list_entries(&modlist_file)
+                .and_then(|entries| resolve_entry(&entries, &entry))
+                .and_then(|entry| {
+                    let output = output.unwrap_or_else(|| {
+                        entry
+                            .file_name()
+                            .map(PathBuf::from)
+                            .unwrap_or_else(|| entry.clone())
+                    });
+                    extract_entry(&modlist_file, &entry)
+                        .and_then(|mut extracted| {
+                            output
+                                .open_file_write()
+                                .and_then(|(_, mut file)| std::io::copy(&mut extracted, &mut file).context("writing extracted entry"))
+                        })
+                        .map(|size| info!("extracted [{}] ({}) to [{}]", entry.display(), human_readable_size(size), output.display()))
+                }),
+            WabbajackFileCommand::DumpModlist { modlist_file } => extract_entry(&modlist_file, Path::new(MODLIST_JSON_FILENAME))
+                .and_then(|modlist| pretty_print_json(modlist, std::io::stdout().lock()))
+                .map(|()| println!()),
+            WabbajackFileCommand::ExplainPatch { modlist_file, patch_id } => list_entries(&modlist_file)
+                .and_then(|entries| resolve_entry(&entries, &patch_id.to_string()))
+                .and_then(|entry| extract_entry(&modlist_file, &entry))
+                .and_then(|patch| OctodiffMetadata::explain(BufReader::new(patch)).context("reading patch"))
+                .map(|(metadata, commands)| {
+                    let (copied, written) = commands
+                        .iter()
+                        .fold((0, 0), |(copied, written), command| match command {
+                            CommandSummary::Copy { length, .. } => (copied + length, written),
+                            CommandSummary::Write(bytes) => (copied, written + bytes.len()),
+                        });
+                    println!("{metadata:#?}");
+                    commands.iter().for_each(|command| println!("{command}"));
+                    println!(
+                        "[{}] commands: copies {} from the source, writes {} from the patch",
+                        commands.len(),
+                        human_readable_size(copied as u64),
+                        human_readable_size(written as u64)
+                    );
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_resolve_by_path_and_id() -> Result<()> {
+        let id = uuid::Uuid::parse_str("3f2504e0-4f89-11d3-9a0c-0305e82c3301")?;
+        let entries = [(PathBuf::from("modlist-image.png"), 1), (WabbajackFileHandle::source_data_path(id), 2)];
+        assert_eq!(resolve_entry(&entries, "modlist-image.png")?, PathBuf::from("modlist-image.png"));
+        assert_eq!(
+            resolve_entry(&entries, "3F2504E0-4F89-11D3-9A0C-0305E82C3301")?,
+            WabbajackFileHandle::source_data_path(id)
+        );
+        assert!(resolve_entry(&entries, "readme.md").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_is_printed_as_it_was_stored() -> Result<()> {
+        let mut printed = vec![];
+        pretty_print_json(
+            r#"{"Name": "test", "Archives": [], "FromTheFuture": {"b": 1, "a": 2}}"#.as_bytes(),
+            &mut printed,
+        )?;
+        assert_eq!(
+            String::from_utf8(printed)?,
+            "{\n  \"Name\": \"test\",\n  \"Archives\": [],\n  \"FromTheFuture\": {\n    \"b\": 1,\n    \"a\": 2\n  }\n}"
+        );
+        Ok(())
+    }
+}
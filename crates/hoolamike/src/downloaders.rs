@@ -4,7 +4,9 @@ use {
 };
 
 pub mod gamefile_source_downloader;
+pub mod github;
 pub mod google_drive;
+pub mod ips4;
 pub mod mega;
 pub mod http {
     pub struct HttpDownloader {}
@@ -13,8 +15,14 @@ pub mod manual {
     pub struct ManualDownloader {}
 }
 pub mod mediafire;
+pub mod moddb;
 pub mod nexus;
+pub mod response_sniffing;
 pub mod wabbajack_cdn;
+pub mod wabbajack_upgrades;
+
+#[cfg(test)]
+pub mod mock_server;
 
 pub mod helpers;
 
@@ -26,11 +34,33 @@ pub struct WithArchiveDescriptor<T> {
 
 pub type MergeDownloadTask = WithArchiveDescriptor<(Vec<HumanUrl>, PathBuf)>;
 pub type DownloadTask = WithArchiveDescriptor<(HumanUrl, PathBuf)>;
+/// mirrors of the same file, tried in order until one of them succeeds
+pub type MirroredDownloadTask = WithArchiveDescriptor<(Vec<HumanUrl>, PathBuf)>;
+/// (url, ips4 site name, output path) - downloaded with the access token of the site
+pub type Ips4DownloadTask = WithArchiveDescriptor<(HumanUrl, String, PathBuf)>;
 pub type CopyFileTask = WithArchiveDescriptor<(PathBuf, PathBuf)>;
+/// (task fetching the replacement archive, patch url, output path)
+pub type UpgradeTask = WithArchiveDescriptor<(Box<SyncTask>, HumanUrl, PathBuf)>;
 
 #[derive(Debug, Clone, derive_more::From)]
 pub enum SyncTask {
     MergeDownload(MergeDownloadTask),
     Download(DownloadTask),
+    MirroredDownload(MirroredDownloadTask),
+    Ips4Download(Ips4DownloadTask),
     Copy(CopyFileTask),
+    Upgrade(UpgradeTask),
+}
+
+impl SyncTask {
+    pub fn descriptor(&self) -> &ArchiveDescriptor {
+        match self {
+            SyncTask::MergeDownload(task) => &task.descriptor,
+            SyncTask::Download(task) => &task.descriptor,
+            SyncTask::MirroredDownload(task) => &task.descriptor,
+            SyncTask::Ips4Download(task) => &task.descriptor,
+            SyncTask::Copy(task) => &task.descriptor,
+            SyncTask::Upgrade(task) => &task.descriptor,
+        }
+    }
 }
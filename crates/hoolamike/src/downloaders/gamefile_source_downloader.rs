@@ -4,27 +4,71 @@ use {
         config_file::{GameConfig, GamesConfig},
         install_modlist::download_cache::validate_hash,
         modlist_json::{GameFileSourceState, GameName},
+        path_resolver,
+        utils::spawn_rayon,
     },
     anyhow::{Context, Result},
     futures::TryFutureExt,
+    hash_index::GameHashIndex,
     indexmap::IndexMap,
-    std::{future::ready, path::PathBuf},
+    itertools::Itertools,
+    std::{
+        future::ready,
+        path::{Path, PathBuf},
+    },
     tap::prelude::*,
+    tokio::sync::OnceCell,
 };
 
+pub mod hash_index;
+
+/// hash indexes of game directories are cached here, relative to the downloads directory
+pub const HASH_INDEX_DIRECTORY: &str = ".game_hash_index";
+
 pub struct GameFileSourceDownloader {
     game_name: GameName,
     source_directory: PathBuf,
+    index_file: PathBuf,
+    /// only built once a file can't be found at its expected path
+    index: OnceCell<GameHashIndex>,
 }
 
 impl GameFileSourceDownloader {
-    pub fn new(game_name: GameName, GameConfig { root_directory }: GameConfig) -> Result<Self> {
+    pub fn new(game_name: GameName, GameConfig { root_directory }: GameConfig, index_directory: &Path) -> Result<Self> {
         root_directory
             .exists()
             .then_some(root_directory.clone())
             .with_context(|| format!("[{}] does not exist", root_directory.display()))
-            .map(|source_directory| Self { source_directory, game_name })
+            .map(|source_directory| Self {
+                source_directory,
+                index_file: index_directory.join(format!("{game_name}.json")),
+                game_name,
+                index: OnceCell::new(),
+            })
     }
+
+    async fn hash_index(&self) -> Result<&GameHashIndex> {
+        self.index
+            .get_or_try_init(|| {
+                let (root, index_file) = (self.source_directory.clone(), self.index_file.clone());
+                spawn_rayon(move || GameHashIndex::load_or_build(&root, &index_file))
+            })
+            .await
+    }
+
+    /// localized or repackaged games keep identical files under different names
+    async fn find_by_content(&self, hash: &str) -> Result<PathBuf> {
+        self.hash_index()
+            .await?
+            .find(hash)
+            .next()
+            .map(|relative| self.source_directory.join(relative))
+            .with_context(|| format!("no file with hash [{hash}] in [{}]", self.source_directory.display()))
+            .pipe(ready)
+            .and_then(|found| validate_hash(found, hash.to_string()))
+            .await
+    }
+
     pub async fn prepare_copy(
         &self,
         GameFileSourceState {
@@ -32,16 +76,18 @@ impl GameFileSourceDownloader {
             hash,
             game_file,
             game,
+            extra: _,
         }: GameFileSourceState,
     ) -> Result<PathBuf> {
-        self.game_name
-            .eq(&game)
+        let at_expected_path = self
+            .game_name
+            .is_same_game(&game)
             .then_some(())
             .with_context(|| format!("expected downloader for [{game}], but this is a downloader for [{}]", self.game_name))
             .map(|_| game_file.into_path())
             .pipe(ready)
             .and_then(|game_file| {
-                self.source_directory.join(game_file).pipe(|game_file| {
+                path_resolver::existing_path(&self.source_directory, &game_file).pipe(|game_file| {
                     game_file
                         .clone()
                         .pipe(tokio::fs::try_exists)
@@ -53,18 +99,43 @@ impl GameFileSourceDownloader {
                         })
                 })
             })
-            .and_then(|source| validate_hash(source, hash))
-            .await
+            .and_then(|source| validate_hash(source, hash.clone()))
+            .await;
+        match at_expected_path {
+            Ok(source) => Ok(source),
+            Err(reason) if !self.game_name.is_same_game(&game) => Err(reason),
+            Err(reason) => self
+                .find_by_content(&hash)
+                .await
+                .tap_ok(|found| tracing::warn!("{reason:#}, using [{}] which has the same contents", found.display()))
+                .map_err(|by_content| reason.context(format!("{by_content:#}"))),
+        }
     }
 }
 
 pub type GameFileSourceSynchronizers = IndexMap<GameName, GameFileSourceDownloader>;
 
-pub fn get_game_file_source_synchronizers(config: GamesConfig) -> Result<GameFileSourceSynchronizers> {
+/// games are matched through the registry, so the config key does not need to match the modlist spelling exactly
+pub fn find_game_file_source_synchronizer<'a>(synchronizers: &'a GameFileSourceSynchronizers, game: &GameName) -> Option<&'a GameFileSourceDownloader> {
+    synchronizers.get(game).or_else(|| {
+        synchronizers
+            .iter()
+            .find(|(configured, _)| configured.is_same_game(game))
+            .map(|(_, synchronizer)| synchronizer)
+    })
+}
+
+pub fn get_game_file_source_synchronizers(config: GamesConfig, downloads_directory: &Path) -> Result<GameFileSourceSynchronizers> {
+    let index_directory = downloads_directory.join(HASH_INDEX_DIRECTORY);
     config
         .into_iter()
+        .inspect(|(game, _)| {
+            if let Err(reason) = game.game() {
+                tracing::warn!("{reason:#}, known games are: {}", crate::games::Game::all().join(", "));
+            }
+        })
         .map(|(game, config)| {
-            GameFileSourceDownloader::new(game.clone(), config)
+            GameFileSourceDownloader::new(game.clone(), config, &index_directory)
                 .with_context(|| format!("creating copy manager for [{game}]"))
                 .map(|downloader| (game, downloader))
         })
@@ -0,0 +1,205 @@
+//! content index of a game directory, so that game files can be found by hash when they were renamed or moved
+use {
+    crate::{
+        install_modlist::download_cache::{to_base_64_from_u64, to_u64_from_base_64},
+        utils::PathReadWrite,
+    },
+    anyhow::{Context, Result},
+    rayon::prelude::*,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, HashMap},
+        hash::Hasher,
+        io::Read,
+        path::{Path, PathBuf},
+        time::UNIX_EPOCH,
+    },
+    tap::prelude::*,
+    tracing::{debug, info, instrument, warn},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub size: u64,
+    /// nanoseconds since unix epoch, an unchanged size and mtime means the cached hash can be reused
+    pub modified: u128,
+    pub hash: String,
+}
+
+/// keyed by path relative to the game root
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameHashIndex {
+    pub files: BTreeMap<PathBuf, IndexedFile>,
+    /// built from [Self::files] whenever the index is built or loaded
+    #[serde(skip)]
+    by_hash: HashMap<u64, Vec<PathBuf>>,
+}
+
+fn xxhash_file(path: &Path) -> Result<String> {
+    path.open_file_read().and_then(|(_, mut file)| {
+        let mut buffer = vec![0; crate::BUFFER_SIZE];
+        let mut hasher = xxhash_rust::xxh64::Xxh64::new(0);
+        loop {
+            match file
+                .read(&mut buffer)
+                .context("reading chunk into a hasher")?
+            {
+                0 => break,
+                read => hasher.update(&buffer[..read]),
+            }
+        }
+        Ok(to_base_64_from_u64(hasher.finish()))
+    })
+}
+
+fn size_and_modified(path: &Path) -> Result<(u64, u128)> {
+    path.metadata()
+        .with_context(|| format!("reading metadata of [{}]", path.display()))
+        .and_then(|metadata| {
+            metadata
+                .modified()
+                .context("reading modification time")
+                .and_then(|modified| {
+                    modified
+                        .duration_since(UNIX_EPOCH)
+                        .context("modified before unix epoch")
+                })
+                .map(|modified| (metadata.len(), modified.as_nanos()))
+        })
+}
+
+impl GameHashIndex {
+    fn from_files(files: BTreeMap<PathBuf, IndexedFile>) -> Self {
+        let by_hash = files
+            .iter()
+            .filter_map(|(path, file)| {
+                to_u64_from_base_64(file.hash.clone())
+                    .tap_err(|reason| debug!("not indexing [{}]: {reason:?}", path.display()))
+                    .ok()
+                    .map(|hash| (hash, path.clone()))
+            })
+            .fold(HashMap::<u64, Vec<PathBuf>>::new(), |acc, (hash, path)| {
+                acc.tap_mut(|acc| acc.entry(hash).or_default().push(path))
+            });
+        Self { files, by_hash }
+    }
+
+    /// only files that changed since `previous` was built are hashed again
+    #[instrument(skip(previous))]
+    pub fn build(root: &Path, previous: &GameHashIndex) -> Result<Self> {
+        walkdir::WalkDir::new(root)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|entry| {
+                entry
+                    .tap_err(|reason| warn!(?reason, "could not read entry"))
+                    .ok()
+            })
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(root)
+                    .ok()
+                    .map(|relative| (relative.to_owned(), entry.path().to_owned()))
+            })
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|(relative, path)| {
+                size_and_modified(&path).and_then(|(size, modified)| match previous.files.get(&relative) {
+                    Some(cached) if cached.size == size && cached.modified == modified => Ok((relative, cached.clone())),
+                    _ => xxhash_file(&path)
+                        .with_context(|| format!("hashing [{}]", path.display()))
+                        .map(|hash| (relative, IndexedFile { size, modified, hash })),
+                })
+            })
+            .filter_map(|file| {
+                file.tap_err(|reason| warn!("skipping file: {reason:?}"))
+                    .ok()
+            })
+            .collect::<BTreeMap<_, _>>()
+            .pipe(Self::from_files)
+            .pipe(Ok)
+    }
+
+    fn load(cache_file: &Path) -> Result<Self> {
+        std::fs::read_to_string(cache_file)
+            .context("reading file")
+            .and_then(|contents| serde_json::from_str::<Self>(&contents).context("parsing index"))
+            .map(|Self { files, by_hash: _ }| Self::from_files(files))
+            .with_context(|| format!("loading game hash index from [{}]", cache_file.display()))
+    }
+
+    fn save(&self, cache_file: &Path) -> Result<()> {
+        cache_file
+            .parent()
+            .map(|parent| std::fs::create_dir_all(parent).context("creating index directory"))
+            .transpose()
+            .and_then(|_| serde_json::to_string(self).context("serializing index"))
+            .and_then(|contents| std::fs::write(cache_file, contents).context("writing file"))
+            .with_context(|| format!("saving game hash index to [{}]", cache_file.display()))
+    }
+
+    /// reuses the hashes stored in `cache_file` and stores the refreshed index there
+    #[instrument]
+    pub fn load_or_build(root: &Path, cache_file: &Path) -> Result<Self> {
+        let previous = Self::load(cache_file)
+            .tap_err(|reason| debug!("building index from scratch: {reason:?}"))
+            .unwrap_or_default();
+        Self::build(root, &previous).tap_ok(|index| {
+            info!(files = index.files.len(), "indexed game directory");
+            if index != &previous {
+                if let Err(reason) = index.save(cache_file) {
+                    warn!("{reason:?}");
+                }
+            }
+        })
+    }
+
+    /// relative paths of files with this content
+    pub fn find(&self, hash: &str) -> impl Iterator<Item = &Path> {
+        to_u64_from_base_64(hash.to_string())
+            .ok()
+            .and_then(|hash| self.by_hash.get(&hash))
+            .into_iter()
+            .flatten()
+            .map(PathBuf::as_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_finds_moved_files_and_reuses_cache() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let cache = tempfile::tempdir()?;
+        let cache_file = cache.path().join("game.json");
+        std::fs::create_dir_all(root.path().join("Data/Localized"))?;
+        std::fs::write(root.path().join("Data/Localized/Skyrim_Deutsch.esm"), b"same contents")?;
+        std::fs::write(root.path().join("Data/Update.esm"), b"other contents")?;
+
+        let index = GameHashIndex::load_or_build(root.path(), &cache_file)?;
+        let hash = xxhash_file(&root.path().join("Data/Localized/Skyrim_Deutsch.esm"))?;
+        assert_eq!(index.find(&hash).collect::<Vec<_>>(), [Path::new("Data/Localized/Skyrim_Deutsch.esm")]);
+        assert!(cache_file.exists());
+
+        // a poisoned cache entry is trusted as long as size and mtime did not change
+        let cached = to_base_64_from_u64(42);
+        let poisoned = GameHashIndex::load(&cache_file)?.tap_mut(|index| {
+            index
+                .files
+                .values_mut()
+                .for_each(|file| file.hash = cached.clone())
+        });
+        assert_eq!(
+            GameHashIndex::build(root.path(), &poisoned)?
+                .find(&cached)
+                .count(),
+            2
+        );
+        assert_eq!(GameHashIndex::load(&cache_file)?.find(&hash).count(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use {
+    super::helpers::{FutureAnyhowExt, ReqwestPrettyJsonResponse},
+    crate::{
+        config_file::GitHubConfig,
+        modlist_json::{GitHubState, HumanUrl},
+    },
+    anyhow::{Context, Result},
+    reqwest::{
+        header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION},
+        Client,
+        ClientBuilder,
+        StatusCode,
+    },
+    serde::{Deserialize, Serialize},
+    tap::prelude::*,
+    tracing::instrument,
+};
+
+pub const DEFAULT_API_BASE_URL: &str = "https://api.github.com";
+
+pub struct GitHubDownloader {
+    client: Client,
+    api_base_url: HumanUrl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: HumanUrl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+fn manual_action_required(
+    GitHubState {
+        user,
+        repository,
+        tag,
+        asset_name,
+        extra: _,
+    }: &GitHubState,
+    reason: &str,
+) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Manual action is required:\n\nURL: https://github.com/{user}/{repository}/releases\n{reason}, please download [{asset_name}] ({tag}) manually"
+    )
+}
+
+impl GitHubDownloader {
+    pub fn new(GitHubConfig { api_base_url, token }: GitHubConfig) -> Result<Self> {
+        [
+            (ACCEPT, Some("application/vnd.github+json".to_string())),
+            (AUTHORIZATION, token.map(|token| format!("Bearer {token}"))),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key, value)))
+        .map(|(key, value)| {
+            HeaderValue::from_str(&value)
+                .with_context(|| format!("invalid header value for {key}"))
+                .map(|value| (key, value))
+        })
+        .try_fold(HeaderMap::new(), |map, header| {
+            header.map(|(key, value)| map.tap_mut(|map| map.insert(key, value).pipe(|_| ())))
+        })
+        .and_then(|headers| {
+            ClientBuilder::new()
+                .user_agent(concat!("hoolamike/", env!("CARGO_PKG_VERSION")))
+                .default_headers(headers)
+                .build()
+                .context("building http client")
+        })
+        .map(|client| Self { client, api_base_url })
+        .context("building GitHubDownloader")
+    }
+
+    #[instrument(skip(self))]
+    pub async fn release(&self, state: &GitHubState) -> Result<Release> {
+        let GitHubState { user, repository, tag, .. } = state;
+        let url = format!(
+            "{}/repos/{user}/{repository}/releases/tags/{tag}",
+            self.api_base_url.to_string().trim_end_matches('/')
+        );
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .map_with_context(|| format!("fetching [{url}]"))
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Err(manual_action_required(state, &format!("release [{tag}] does not exist (anymore?)"))),
+            _ => response
+                .json_response_ok(|_| Ok(()))
+                .await
+                .context("reading release"),
+        }
+    }
+
+    /// resolves the download url of the asset, the release is looked up by its tag
+    #[instrument(skip(self))]
+    pub async fn prepare_download(&self, state: &GitHubState) -> Result<HumanUrl> {
+        self.release(state)
+            .await
+            .and_then(|Release { assets, tag_name }| {
+                assets
+                    .into_iter()
+                    .find(|asset| asset.name == state.asset_name)
+                    .map(|asset| asset.browser_download_url)
+                    .ok_or_else(|| manual_action_required(state, &format!("release [{tag_name}] has no asset named [{}]", state.asset_name)))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,77 @@
+use {
+    super::*,
+    crate::downloaders::mock_server::{MockResponse, MockServer, Routes},
+};
+
+fn example_state(tag: &str, asset_name: &str) -> GitHubState {
+    GitHubState {
+        user: "TES5Edit".to_string(),
+        repository: "TES5Edit".to_string(),
+        tag: tag.to_string(),
+        asset_name: asset_name.to_string(),
+        extra: Default::default(),
+    }
+}
+
+async fn example_server() -> Result<MockServer> {
+    MockServer::start(|base_url| {
+        Routes::new().tap_mut(|routes| {
+            Release {
+                tag_name: "xedit-4.1.5".to_string(),
+                assets: vec![ReleaseAsset {
+                    name: "xEdit_4.1.5.7z".to_string(),
+                    size: 1337,
+                    browser_download_url: base_url
+                        .clone()
+                        .tap_mut(|url| url.as_mut().set_path("/download/xEdit_4.1.5.7z")),
+                }],
+            }
+            .pipe_ref(serde_json::to_vec)
+            .map(|release| routes.insert("/repos/TES5Edit/TES5Edit/releases/tags/xedit-4.1.5".to_string(), MockResponse::json(release)))
+            .expect("serializing release");
+        })
+    })
+    .await
+}
+
+fn downloader(server: &MockServer) -> Result<GitHubDownloader> {
+    GitHubDownloader::new(GitHubConfig {
+        api_base_url: server.base_url.clone(),
+        token: None,
+    })
+}
+
+#[tokio::test]
+async fn test_resolves_asset_url() -> Result<()> {
+    let server = example_server().await?;
+    downloader(&server)?
+        .prepare_download(&example_state("xedit-4.1.5", "xEdit_4.1.5.7z"))
+        .await
+        .map(|url| assert_eq!(url, server.url("/download/xEdit_4.1.5.7z")))
+}
+
+#[tokio::test]
+async fn test_missing_tag_requires_manual_action() -> Result<()> {
+    let server = example_server().await?;
+    downloader(&server)?
+        .prepare_download(&example_state("xedit-4.0.0", "xEdit_4.0.0.7z"))
+        .await
+        .err()
+        .context("missing tag should be an error")
+        .map(|error| {
+            let message = format!("{error:?}");
+            assert!(message.contains("Manual action is required"), "{message}");
+            assert!(message.contains("https://github.com/TES5Edit/TES5Edit/releases"), "{message}");
+        })
+}
+
+#[tokio::test]
+async fn test_missing_asset_requires_manual_action() -> Result<()> {
+    let server = example_server().await?;
+    downloader(&server)?
+        .prepare_download(&example_state("xedit-4.1.5", "SSEEdit_4.1.5.7z"))
+        .await
+        .err()
+        .context("missing asset should be an error")
+        .map(|error| assert!(format!("{error:?}").contains("has no asset named [SSEEdit_4.1.5.7z]")))
+}
@@ -3,12 +3,17 @@ use {
     crate::modlist_json::HumanUrl,
     anyhow::{Context, Result},
     futures::TryFutureExt,
+    reqwest::{header::CONTENT_TYPE, StatusCode},
+    response_parsing::GoogleDriveError,
     std::{future::ready, str::FromStr},
     tap::prelude::*,
 };
 
 pub struct GoogleDriveDownloader {}
 
+#[cfg(test)]
+mod test_responses;
+
 pub mod response_parsing {
     use {
         crate::modlist_json::HumanUrl,
@@ -19,6 +24,41 @@ pub mod response_parsing {
         url::{form_urlencoded, Url},
     };
 
+    /// pages google drive serves instead of the file
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+    pub enum GoogleDriveError {
+        #[error("google drive download quota for this file is exceeded, try again in 24 hours")]
+        QuotaExceeded,
+        #[error("access to the file is denied, it was either removed or made private")]
+        PermissionDenied,
+        #[error("google drive is rate limiting the requests, try again later")]
+        RateLimited,
+    }
+
+    /// matched on the elements google drive puts its errors in, so a file name on a confirmation page can't look like one
+    pub fn detect_error_page(contents: &str) -> Option<GoogleDriveError> {
+        let document = Html::parse_document(contents);
+        let starts_with = |selector: &str, prefixes: &[&str]| {
+            document
+                .select(&Selector::parse(selector).unwrap())
+                .map(|element| element.text().collect::<String>().trim().to_lowercase())
+                .any(|text| prefixes.iter().any(|prefix| text.starts_with(prefix)))
+        };
+        if starts_with("p.uc-error-subcaption", &["too many users have viewed or downloaded this file recently"])
+            || starts_with("title", &["google drive - quota exceeded"])
+        {
+            Some(GoogleDriveError::QuotaExceeded)
+        } else if starts_with("title", &["sorry..."]) {
+            Some(GoogleDriveError::RateLimited)
+        } else if starts_with("div.title", &["you need access", "you need permission"])
+            || starts_with("p.uc-error-subcaption", &["the file you have requested does not exist"])
+        {
+            Some(GoogleDriveError::PermissionDenied)
+        } else {
+            None
+        }
+    }
+
     /// BASED ON https://github.com/wkentaro/gdown/blob/main/gdown/download.py
     pub fn get_url_from_gdrive_confirmation(contents: &str) -> Result<HumanUrl> {
         let mut url = String::new();
@@ -62,8 +102,17 @@ pub mod response_parsing {
                 url = url.replace("\\u003d", "=").replace("\\u0026", "&");
                 break;
             }
+        }
 
-            if let Some(captures) = error_caption_re.captures(line) {
+        // only a page without a way to the file is an error page
+        if url.is_empty() {
+            if let Some(error) = detect_error_page(contents) {
+                return Err(error.into());
+            }
+            if let Some(captures) = contents
+                .lines()
+                .find_map(|line| error_caption_re.captures(line))
+            {
                 anyhow::bail!("{}", (captures.get(1).unwrap().as_str()))
             }
         }
@@ -74,22 +123,42 @@ pub mod response_parsing {
 
 impl GoogleDriveDownloader {
     /// wget --no-check-certificate 'https://docs.google.com/uc?export=download&id=1WmGuPCblM-L22O38qs939FRRs9ehnLsU' -O your_file_name
+    /// fails with [GoogleDriveError] when google serves an error page instead of the file
     pub async fn download(id: String, expected_size: u64) -> Result<HumanUrl> {
         let original_url = format!("https://docs.google.com/uc?export=download&id={id}&export=download&confirm=t")
             .pipe_deref(HumanUrl::from_str)
             .context("invalid url")?;
 
-        let response = {
-            reqwest::Client::new()
-                .get(original_url.to_string())
-                .send()
+        let response = reqwest::Client::new()
+            .get(original_url.to_string())
+            .send()
+            .await
+            .context("fetching google drive warning page")?;
+        let status_check = response.error_for_status_ref().map(|_| ());
+        if let Err(bad_status) = status_check {
+            let status = response.status();
+            return response
+                .text()
                 .await
-                .context("fetching google drive warning page")?
-                .error_for_status()
-                .context("bad status")?
-        };
+                .ok()
+                .and_then(|text| response_parsing::detect_error_page(&text))
+                .or_else(|| match status {
+                    StatusCode::TOO_MANY_REQUESTS => Some(GoogleDriveError::RateLimited),
+                    StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => Some(GoogleDriveError::PermissionDenied),
+                    _ => None,
+                })
+                .map(anyhow::Error::from)
+                .unwrap_or_else(|| anyhow::Error::from(bad_status).context("bad status"))
+                .pipe(Err);
+        }
+        let is_html = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(|content_type| content_type.starts_with("text/html"))
+            .unwrap_or(false);
         match response.content_length() {
-            Some(size) if expected_size == size => Ok(original_url),
+            Some(size) if expected_size == size && !is_html => Ok(original_url),
             _ => {
                 response
                     .text()
@@ -104,3 +173,8 @@ impl GoogleDriveDownloader {
         }
     }
 }
+
+/// the file can always be downloaded manually from here
+pub fn manual_download_url(id: &str) -> String {
+    format!("https://drive.google.com/file/d/{id}/view")
+}
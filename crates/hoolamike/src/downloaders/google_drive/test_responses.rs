@@ -0,0 +1,62 @@
+use {
+    super::{response_parsing::*, *},
+    std::collections::BTreeMap,
+};
+
+fn expect_error(contents: &str, expected: GoogleDriveError) -> Result<()> {
+    get_url_from_gdrive_confirmation(contents)
+        .err()
+        .context("error page was parsed as a confirmation")
+        .and_then(|error| {
+            error
+                .downcast::<GoogleDriveError>()
+                .map_err(|error| anyhow::anyhow!("untyped error: {error:?}"))
+        })
+        .map(|error| assert_eq!(error, expected))
+}
+
+#[test]
+fn test_quota_exceeded() -> Result<()> {
+    expect_error(include_str!("test_raw_responses/quota-exceeded.html"), GoogleDriveError::QuotaExceeded)
+}
+
+#[test]
+fn test_access_denied() -> Result<()> {
+    expect_error(include_str!("test_raw_responses/access-denied.html"), GoogleDriveError::PermissionDenied)
+}
+
+#[test]
+fn test_rate_limited() -> Result<()> {
+    expect_error(include_str!("test_raw_responses/rate-limited.html"), GoogleDriveError::RateLimited)
+}
+
+fn expect_virus_scan_warning_url(contents: &str) -> Result<()> {
+    get_url_from_gdrive_confirmation(contents).map(|url| {
+        let url = url.as_ref();
+        assert_eq!(url.as_str().split('?').next(), Some("https://drive.usercontent.google.com/download"));
+        assert_eq!(
+            url.query_pairs().into_owned().collect::<BTreeMap<_, _>>(),
+            [
+                ("confirm", "t"),
+                ("export", "download"),
+                ("id", "1WmGuPCblM-L22O38qs939FRRs9ehnLsU"),
+                ("uuid", "0d0b5f40-2c52-4c7a-8b1f-3d5a9e3c1f00"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>()
+        );
+    })
+}
+
+#[test]
+fn test_virus_scan_warning() -> Result<()> {
+    expect_virus_scan_warning_url(include_str!("test_raw_responses/virus-scan-warning.html"))
+}
+
+#[test]
+fn test_error_phrases_in_the_file_name_are_not_errors() -> Result<()> {
+    include_str!("test_raw_responses/virus-scan-warning.html")
+        .replace("SomeMod.7z", "Unusual Traffic - Download Quota Exceeded - You need access.7z")
+        .pipe_deref(expect_virus_scan_warning_url)
+}
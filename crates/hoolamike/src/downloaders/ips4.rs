@@ -0,0 +1,320 @@
+//! invision community (IPS4) sites, such as loverslab and vectorplexus, authenticated through oauth
+use {
+    super::helpers::{FutureAnyhowExt, ReqwestPrettyJsonResponse},
+    crate::{
+        config_file::{Ips4Config, Ips4SiteConfig},
+        modlist_json::{HumanUrl, Ips4OAuth2State},
+    },
+    anyhow::{Context, Result},
+    futures::TryFutureExt,
+    itertools::Itertools,
+    reqwest::Client,
+    serde::{Deserialize, Serialize},
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    tap::prelude::*,
+    tracing::{info, instrument},
+};
+
+pub const OAUTH_SCOPE: &str = "profile get_downloads";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub token_type: Option<String>,
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+    /// unix timestamp of when the token was issued, `expires_in` counts from there
+    #[serde(default)]
+    pub issued_at: Option<u64>,
+}
+
+/// a token this close to expiring is refreshed up front, so that it does not expire mid-download
+const EXPIRY_MARGIN_SECONDS: u64 = 60;
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn token_path(token_directory: &Path, site: &str) -> PathBuf {
+    token_directory.join(format!("{site}.json"))
+}
+
+impl OAuthToken {
+    fn issued_now(self) -> Self {
+        Self {
+            issued_at: Some(unix_now()),
+            ..self
+        }
+    }
+
+    /// tokens saved without an expiry never expire as far as hoolamike knows
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.issued_at
+            .zip(self.expires_in)
+            .is_some_and(|(issued_at, expires_in)| issued_at + expires_in <= now + EXPIRY_MARGIN_SECONDS)
+    }
+
+    pub fn load(token_directory: &Path, site: &str) -> Result<Option<Self>> {
+        let path = token_path(token_directory, site);
+        match path.exists() {
+            false => Ok(None),
+            true => std::fs::read_to_string(&path)
+                .context("reading token")
+                .and_then(|token| serde_json::from_str(&token).context("parsing token"))
+                .map(Some)
+                .with_context(|| format!("loading oauth token from [{}]", path.display())),
+        }
+    }
+
+    pub fn save(&self, token_directory: &Path, site: &str) -> Result<PathBuf> {
+        let path = token_path(token_directory, site);
+        std::fs::create_dir_all(token_directory)
+            .context("creating token directory")
+            .and_then(|_| serde_json::to_string_pretty(self).context("serializing token"))
+            .and_then(|token| {
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).create(true).truncate(true);
+                // the token is as good as a password
+                #[cfg(unix)]
+                std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+                options
+                    .open(&path)
+                    .and_then(|mut file| {
+                        #[cfg(unix)]
+                        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+                        file.write_all(token.as_bytes())
+                    })
+                    .context("writing token")
+            })
+            .map(|_| path.clone())
+            .with_context(|| format!("saving oauth token to [{}]", path.display()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ips4File {
+    pub name: String,
+    pub url: HumanUrl,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// only the fields needed for downloading are read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ips4DownloadsFile {
+    pub files: Vec<Ips4File>,
+}
+
+fn endpoint(base_url: &HumanUrl, path: &str) -> Result<HumanUrl> {
+    format!("{}/{path}", base_url.to_string().trim_end_matches('/'))
+        .parse()
+        .with_context(|| format!("bad url for [{path}]"))
+}
+
+fn host(url: &HumanUrl) -> Option<String> {
+    AsRef::<url::Url>::as_ref(url)
+        .host_str()
+        .map(str::to_lowercase)
+}
+
+pub struct Ips4Downloader {
+    site: String,
+    site_config: Ips4SiteConfig,
+    /// refreshed tokens are saved back here
+    token_directory: PathBuf,
+    client: Client,
+    token: tokio::sync::Mutex<Option<OAuthToken>>,
+}
+
+impl Ips4Downloader {
+    pub fn new(site: String, site_config: Ips4SiteConfig, token_directory: PathBuf, token: Option<OAuthToken>) -> Self {
+        Self {
+            site,
+            site_config,
+            token_directory,
+            client: Client::new(),
+            token: tokio::sync::Mutex::new(token),
+        }
+    }
+
+    /// expired tokens are refreshed (and saved) first
+    pub async fn access_token(&self) -> Result<String> {
+        let site = &self.site;
+        let mut token = self.token.lock().await;
+        let current = token
+            .clone()
+            .with_context(|| format!("not logged in to [{site}], run `hoolamike ips4-login {site}` first"))?;
+        if !current.is_expired(unix_now()) {
+            return Ok(current.access_token);
+        }
+        let refresh_token = current
+            .refresh_token
+            .with_context(|| format!("token for [{site}] has expired, run `hoolamike ips4-login {site}` again"))?;
+        let refreshed = refresh_token_grant(&self.site_config, &refresh_token)
+            .await
+            // not every site hands out a new refresh token
+            .map(|refreshed| OAuthToken {
+                refresh_token: refreshed.refresh_token.or(Some(refresh_token)),
+                ..refreshed
+            })
+            .with_context(|| format!("token for [{site}] has expired and could not be refreshed, run `hoolamike ips4-login {site}` again"))?;
+        refreshed
+            .save(&self.token_directory, site)
+            .tap_ok(|path| info!("refreshed token for [{site}], saved to [{}]", path.display()))?;
+        *token = Some(refreshed.clone());
+        Ok(refreshed.access_token)
+    }
+
+    /// the token only ever goes to the site itself, files can be served from elsewhere (eg. a CDN)
+    pub async fn authorization_for(&self, url: &HumanUrl) -> Result<Option<String>> {
+        match host(url).is_some_and(|file_host| Some(file_host) == host(&self.site_config.base_url)) {
+            true => self.access_token().await.map(Some),
+            false => Ok(None),
+        }
+    }
+
+    /// url of the archive, it has to be downloaded with the access token as well
+    #[instrument(skip(self), fields(site=%self.site))]
+    pub async fn prepare_download(&self, state: &Ips4OAuth2State) -> Result<HumanUrl> {
+        let access_token = self.access_token().await?;
+        let Ips4OAuth2State {
+            ips4_mod,
+            ips4_file,
+            is_attachment,
+            ips4_url: _,
+            extra: _,
+        } = state;
+        match is_attachment {
+            true => endpoint(
+                &self.site_config.base_url,
+                &format!("applications/core/interface/file/attachment.php?id={ips4_mod}"),
+            ),
+            false => {
+                let url = endpoint(&self.site_config.base_url, &format!("api/downloads/files/{ips4_mod}"))?;
+                self.client
+                    .get(url.to_string())
+                    .bearer_auth(access_token)
+                    .send()
+                    .map_with_context(|| format!("fetching [{url}]"))
+                    .and_then(|response| response.json_response_ok(|_| Ok(())))
+                    .await
+                    .and_then(|Ips4DownloadsFile { files }| {
+                        files
+                            .into_iter()
+                            .find(|file| &file.name == ips4_file)
+                            .map(|file| file.url)
+                            .with_context(|| format!("no file named [{ips4_file}] in [{url}]"))
+                    })
+            }
+        }
+        .with_context(|| format!("resolving [{ips4_file}] on [{}]", self.site))
+    }
+}
+
+/// sites don't share their clients, it has to be registered by the user
+fn client_id(Ips4SiteConfig { client_id, redirect_uri, .. }: &Ips4SiteConfig) -> Result<&str> {
+    client_id.as_deref().with_context(|| {
+        format!(
+            "no client_id configured for this site, register an oauth client on the site (with [{redirect_uri}] as its redirect uri) and set its id in \
+             `downloaders.ips4.sites`"
+        )
+    })
+}
+
+pub fn authorize_url(site_config: &Ips4SiteConfig, state: &str) -> Result<HumanUrl> {
+    let Ips4SiteConfig { base_url, redirect_uri, .. } = site_config;
+    let client_id = client_id(site_config)?;
+    endpoint(base_url, "oauth/authorize/").map(|url| {
+        url.tap_mut(|url| {
+            url.as_mut()
+                .query_pairs_mut()
+                .append_pair("client_id", client_id)
+                .append_pair("response_type", "code")
+                .append_pair("redirect_uri", &redirect_uri.to_string())
+                .append_pair("scope", OAUTH_SCOPE)
+                .append_pair("state", state);
+        })
+    })
+}
+
+/// accepts either the bare code or the whole url the browser was redirected to
+pub fn extract_code(input: &str, expected_state: &str) -> Result<String> {
+    let input = input.trim();
+    match url::Url::parse(input) {
+        Err(_) => Ok(input.to_string()),
+        Ok(redirected) => redirected
+            .query_pairs()
+            .find(|(key, _)| key == "state")
+            .map(|(_, state)| state == expected_state)
+            .unwrap_or(false)
+            .then_some(())
+            .context("state does not match, please try logging in again")
+            .and_then(|_| {
+                redirected
+                    .query_pairs()
+                    .find(|(key, _)| key == "code")
+                    .map(|(_, code)| code.to_string())
+                    .context("no code in the url")
+            }),
+    }
+}
+
+async fn request_token(site_config: &Ips4SiteConfig, grant: &[(&str, &str)]) -> Result<OAuthToken> {
+    let url = endpoint(&site_config.base_url, "oauth/token/")?;
+    let redirect_uri = site_config.redirect_uri.to_string();
+    let form = grant
+        .iter()
+        .copied()
+        .chain([("client_id", client_id(site_config)?), ("redirect_uri", redirect_uri.as_str())])
+        .collect_vec();
+    Client::new()
+        .post(url.to_string())
+        .form(&form)
+        .send()
+        .map_with_context(|| format!("requesting token from [{url}]"))
+        .and_then(|response| response.json_response_ok(|_| Ok(())))
+        .await
+        .map(OAuthToken::issued_now)
+}
+
+#[instrument(skip(code))]
+pub async fn exchange_code(site_config: &Ips4SiteConfig, code: &str) -> Result<OAuthToken> {
+    request_token(site_config, &[("grant_type", "authorization_code"), ("code", code)]).await
+}
+
+#[instrument(skip(refresh_token))]
+pub async fn refresh_token_grant(site_config: &Ips4SiteConfig, refresh_token: &str) -> Result<OAuthToken> {
+    request_token(site_config, &[("grant_type", "refresh_token"), ("refresh_token", refresh_token)]).await
+}
+
+/// browser login flow - the user opens the authorize url and pastes back where they were redirected
+pub async fn login(Ips4Config { token_directory, sites }: &Ips4Config, site: &str) -> Result<PathBuf> {
+    let site_config = sites
+        .get(site)
+        .with_context(|| format!("no such site configured: [{site}], available sites: {:?}", sites.keys().collect::<Vec<_>>()))?;
+    let state = uuid::Uuid::new_v4().to_string();
+    let authorize_url = authorize_url(site_config, &state)?;
+    println!("open this url in your browser and log in:\n\n{authorize_url}\n");
+    let code = inquire::Text::new("paste the url you were redirected to (or just the code):")
+        .prompt()
+        .context("reading code")
+        .and_then(|input| extract_code(&input, &state))?;
+    exchange_code(site_config, &code)
+        .await
+        .and_then(|token| token.save(token_directory, site))
+        .tap_ok(|path| info!("logged in to [{site}], token saved to [{}]", path.display()))
+        .with_context(|| format!("logging in to [{site}]"))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,244 @@
+use {
+    super::*,
+    crate::{
+        config_file::{ArchiveUpgradesConfig, DownloadersConfig, GamesConfig, NexusConfig},
+        downloaders::mock_server::{MockResponse, MockServer, Routes},
+        install_modlist::{download_cache::to_base_64_from_u64, downloads::Synchronizers},
+        modlist_json::{Archive, ArchiveDescriptor, State},
+    },
+    indexmap::IndexMap,
+};
+
+const ARCHIVE: &[u8] = b"some very lewd mod";
+
+fn site_config(server: &MockServer) -> Ips4SiteConfig {
+    Ips4SiteConfig {
+        base_url: server.base_url.clone(),
+        client_id: Some("hoolamike-test".to_string()),
+        redirect_uri: "http://127.0.0.1/hoolamike-oauth".parse().unwrap(),
+    }
+}
+
+fn example_state(ips4_mod: u64, ips4_file: &str, is_attachment: bool) -> Ips4OAuth2State {
+    Ips4OAuth2State {
+        ips4_mod,
+        ips4_file: ips4_file.to_string(),
+        is_attachment,
+        ips4_url: format!("https://www.loverslab.com/files/file/{ips4_mod}"),
+        extra: Default::default(),
+    }
+}
+
+async fn example_server() -> Result<MockServer> {
+    MockServer::start(|base_url| {
+        Routes::new().tap_mut(|routes| {
+            let url = |path: &str| base_url.clone().tap_mut(|url| url.as_mut().set_path(path));
+            Ips4DownloadsFile {
+                files: vec![Ips4File {
+                    name: "SomeMod-1.0.7z".to_string(),
+                    url: url("/files/SomeMod-1.0.7z"),
+                    size: Some(ARCHIVE.len() as u64),
+                }],
+            }
+            .pipe_ref(serde_json::to_vec)
+            .map(|files| routes.insert("/api/downloads/files/1234".to_string(), MockResponse::json(files)))
+            .expect("serializing files");
+            routes.insert("/files/SomeMod-1.0.7z".to_string(), MockResponse::bytes(ARCHIVE));
+            routes.insert(
+                "/oauth/token/".to_string(),
+                MockResponse::json(r#"{"access_token": "secret", "token_type": "bearer", "expires_in": 3600}"#),
+            );
+        })
+    })
+    .await
+}
+
+fn token(access_token: &str, refresh_token: Option<&str>, issued_at: Option<u64>) -> OAuthToken {
+    OAuthToken {
+        access_token: access_token.to_string(),
+        refresh_token: refresh_token.map(str::to_string),
+        token_type: None,
+        expires_in: Some(3600),
+        issued_at,
+    }
+}
+
+fn logged_in(server: &MockServer) -> Ips4Downloader {
+    Ips4Downloader::new(
+        "loverslab".to_string(),
+        site_config(server),
+        std::env::temp_dir(),
+        Some(token("secret", None, None)),
+    )
+}
+
+#[test]
+fn test_parse_wabbajack_state() -> Result<()> {
+    serde_json::from_str::<State>(
+        r#"{
+            "$type": "LoversLabOAuthDownloader, Wabbajack.Lib",
+            "IPS4Mod": 1234, "IPS4File": "SomeMod-1.0.7z", "IsAttachment": false, "IPS4Url": "https://www.loverslab.com/files/file/1234"
+        }"#,
+    )
+    .context("parsing state")
+    .map(|state| assert!(matches!(state, State::LoversLab(Ips4OAuth2State { ips4_mod: 1234, .. }))))
+}
+
+#[test]
+fn test_extract_code() -> Result<()> {
+    assert_eq!(extract_code("  abcd  ", "state")?, "abcd");
+    assert_eq!(extract_code("http://127.0.0.1/hoolamike-oauth?code=abcd&state=state", "state")?, "abcd");
+    assert!(extract_code("http://127.0.0.1/hoolamike-oauth?code=abcd&state=other", "state").is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_login_code_exchange() -> Result<()> {
+    let server = example_server().await?;
+    let token_directory = tempfile::tempdir()?;
+    exchange_code(&site_config(&server), "abcd")
+        .await
+        .and_then(|token| token.save(token_directory.path(), "loverslab"))
+        .and_then(|_| OAuthToken::load(token_directory.path(), "loverslab"))
+        .map(|token| assert_eq!(token.map(|token| token.access_token).as_deref(), Some("secret")))
+}
+
+#[tokio::test]
+async fn test_not_logged_in() -> Result<()> {
+    let server = example_server().await?;
+    Ips4Downloader::new("loverslab".to_string(), site_config(&server), std::env::temp_dir(), None)
+        .prepare_download(&example_state(1234, "SomeMod-1.0.7z", false))
+        .await
+        .err()
+        .context("downloading without a token should fail")
+        .map(|error| assert!(format!("{error:?}").contains("hoolamike ips4-login loverslab")))
+}
+
+#[tokio::test]
+async fn test_expired_tokens_are_refreshed() -> Result<()> {
+    let server = example_server().await?;
+    let token_directory = tempfile::tempdir()?;
+    let expired = |refresh_token| {
+        Ips4Downloader::new(
+            "loverslab".to_string(),
+            site_config(&server),
+            token_directory.path().to_owned(),
+            Some(token("stale", refresh_token, Some(0))),
+        )
+    };
+    assert_eq!(expired(Some("refresh")).access_token().await?, "secret");
+    assert_eq!(
+        OAuthToken::load(token_directory.path(), "loverslab")?.and_then(|token| token.refresh_token),
+        Some("refresh".to_string()),
+        "the refresh token is kept when the site does not hand out a new one"
+    );
+    expired(None)
+        .access_token()
+        .await
+        .err()
+        .context("expired token without a refresh token should fail")
+        .map(|error| assert!(format!("{error:?}").contains("expired"), "{error:?}"))
+}
+
+#[tokio::test]
+async fn test_token_only_goes_to_the_site() -> Result<()> {
+    let server = example_server().await?;
+    let downloader = logged_in(&server);
+    assert_eq!(
+        downloader
+            .authorization_for(&server.url("/files/SomeMod-1.0.7z"))
+            .await?,
+        Some("secret".to_string())
+    );
+    assert_eq!(
+        downloader
+            .authorization_for(&"https://cdn.example.com/SomeMod-1.0.7z".parse()?)
+            .await?,
+        None
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_token_is_private() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let token_directory = tempfile::tempdir()?;
+    token("secret", None, None)
+        .save(token_directory.path(), "loverslab")
+        .and_then(|path| std::fs::metadata(path).context("reading metadata"))
+        .map(|metadata| assert_eq!(metadata.permissions().mode() & 0o777, 0o600))
+}
+
+#[tokio::test]
+async fn test_resolves_file_and_attachment_urls() -> Result<()> {
+    let server = example_server().await?;
+    let downloader = logged_in(&server);
+    assert_eq!(
+        downloader
+            .prepare_download(&example_state(1234, "SomeMod-1.0.7z", false))
+            .await?,
+        server.url("/files/SomeMod-1.0.7z")
+    );
+    assert_eq!(
+        downloader
+            .prepare_download(&example_state(42, "attachment.7z", true))
+            .await?,
+        server.url("/applications/core/interface/file/attachment.php?id=42")
+    );
+    assert!(downloader
+        .prepare_download(&example_state(1234, "OtherMod.7z", false))
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_through_mock_site() -> Result<()> {
+    let server = example_server().await?;
+    let downloads_directory = tempfile::tempdir()?;
+    let token_directory = tempfile::tempdir()?;
+    exchange_code(&site_config(&server), "abcd")
+        .await
+        .and_then(|token| token.save(token_directory.path(), "loverslab"))?;
+
+    let synchronizers = Synchronizers::new(
+        DownloadersConfig {
+            downloads_directory: downloads_directory.path().to_owned(),
+            nexus: NexusConfig::default(),
+            archive_overrides: Default::default(),
+            archive_upgrades: ArchiveUpgradesConfig {
+                enabled: false,
+                ..Default::default()
+            },
+            github: Default::default(),
+            ips4: Ips4Config {
+                token_directory: token_directory.path().to_owned(),
+                sites: IndexMap::from_iter([("loverslab".to_string(), site_config(&server))]),
+            },
+        },
+        GamesConfig::new(),
+    )?;
+
+    synchronizers
+        .sync_downloads(vec![Archive {
+            descriptor: ArchiveDescriptor {
+                hash: xxhash_rust::xxh64::xxh64(ARCHIVE, 0).pipe(to_base_64_from_u64),
+                meta: String::new(),
+                name: "SomeMod-1.0.7z".to_string(),
+                size: ARCHIVE.len() as u64,
+                extra: Default::default(),
+            },
+            state: State::LoversLab(example_state(1234, "SomeMod-1.0.7z", false)),
+        }])
+        .await
+        .map_err(|errors| anyhow::anyhow!("{errors:#?}"))
+        .and_then(|synced| {
+            synced
+                .into_iter()
+                .next()
+                .context("nothing was synchronized")
+        })
+        .and_then(|synced| std::fs::read(&synced.inner).context("reading downloaded archive"))
+        .map(|downloaded| assert_eq!(downloaded, ARCHIVE))
+}
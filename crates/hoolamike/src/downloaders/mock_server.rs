@@ -0,0 +1,159 @@
+//! minimal http server used to stand in for remote services (CDNs, APIs) in tests
+use {
+    crate::modlist_json::HumanUrl,
+    anyhow::{Context, Result},
+    std::{collections::BTreeMap, str::FromStr, sync::Arc},
+    tap::prelude::*,
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            content_type: content_type.to_string(),
+            body: body.into(),
+        }
+    }
+    pub fn json(body: impl Into<Vec<u8>>) -> Self {
+        Self::ok("application/json", body)
+    }
+    pub fn bytes(body: impl Into<Vec<u8>>) -> Self {
+        Self::ok("application/octet-stream", body)
+    }
+    pub fn not_found() -> Self {
+        Self {
+            status: 404,
+            content_type: "text/plain".to_string(),
+            body: b"not found".to_vec(),
+        }
+    }
+}
+
+/// routes are matched against the request path including the query string first, then against the bare path
+pub type Routes = BTreeMap<String, MockResponse>;
+
+pub struct MockServer {
+    pub base_url: HumanUrl,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        302 => "Found",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        _ => "Unknown",
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, routes: Arc<Routes>) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut buffer).await.context("reading request")?;
+        if read == 0 {
+            anyhow::bail!("connection closed before request was complete");
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if let Some(position) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while request.len() < header_end + content_length {
+        let read = stream
+            .read(&mut buffer)
+            .await
+            .context("reading request body")?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let target = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .context("no request target")?
+        .to_string();
+    let response = routes
+        .get(&target)
+        .or_else(|| {
+            target
+                .split_once('?')
+                .and_then(|(path, _)| routes.get(path))
+        })
+        .cloned()
+        .unwrap_or_else(MockResponse::not_found);
+    format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        status_text(response.status),
+        response.content_type,
+        response.body.len()
+    )
+    .into_bytes()
+    .tap_mut(|out| out.extend_from_slice(&response.body))
+    .pipe(|out| async move { stream.write_all(&out).await.context("writing response") })
+    .await
+}
+
+impl MockServer {
+    /// `routes` is called with the base url of the server, so responses can link back to it
+    pub async fn start(routes: impl FnOnce(&HumanUrl) -> Routes) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("binding mock server")?;
+        let base_url = listener
+            .local_addr()
+            .context("reading mock server address")
+            .and_then(|address| HumanUrl::from_str(&format!("http://{address}")).context("building base url"))?;
+        let routes = Arc::new(routes(&base_url));
+        let task = tokio::task::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let routes = routes.clone();
+                tokio::task::spawn(async move {
+                    if let Err(message) = handle_connection(stream, routes).await {
+                        tracing::warn!(?message, "mock server could not handle connection");
+                    }
+                });
+            }
+        });
+        Ok(Self { base_url, task })
+    }
+
+    pub fn url(&self, path: &str) -> HumanUrl {
+        self.base_url.clone().tap_mut(|url| {
+            let (path, query) = path.split_once('?').unwrap_or((path, ""));
+            url.as_mut().set_path(path);
+            url.as_mut()
+                .set_query(Some(query).filter(|query| !query.is_empty()));
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use {
+    super::helpers::FutureAnyhowExt,
+    crate::modlist_json::HumanUrl,
+    anyhow::{Context, Result},
+    futures::TryFutureExt,
+    std::future::ready,
+    tap::prelude::*,
+    tracing::instrument,
+    url::Url,
+};
+
+pub struct ModDBDownloader {}
+
+const BASE_URL: &str = "https://www.moddb.com";
+
+#[cfg(test)]
+mod test_responses;
+
+pub mod response_parsing {
+    use {
+        crate::modlist_json::HumanUrl,
+        anyhow::{Context, Result},
+        itertools::Itertools,
+        scraper::{Html, Selector},
+        url::Url,
+    };
+
+    /// "Germany, 37% capacity" -> 37.0
+    fn parse_load(subheading: &str) -> Option<f64> {
+        subheading
+            .rsplit(',')
+            .next()
+            .and_then(|last| last.split('%').next())
+            .and_then(|load| load.trim().parse().ok())
+    }
+
+    /// BASED ON https://github.com/wabbajack-tools/wabbajack/blob/main/Wabbajack.Downloaders.ModDB/ModDBDownloader.cs
+    /// mirrors are returned least loaded first, mirrors with unknown load go last
+    pub fn get_mirrors_from_moddb_mirror_list(contents: &str, base_url: &Url) -> Result<Vec<HumanUrl>> {
+        let selector = |selector: &str| {
+            Selector::parse(selector)
+                .map_err(|e| anyhow::anyhow!("{e:?}"))
+                .with_context(|| format!("parsing selector [{selector}]"))
+        };
+        let (row, download_link, subheading) = (selector("div.row")?, selector("a#downloadon")?, selector(".subheading")?);
+        Html::parse_document(contents)
+            .select(&row)
+            .filter_map(|row| {
+                row.select(&download_link)
+                    .next()
+                    .and_then(|link| link.attr("href"))
+                    .map(|href| {
+                        (
+                            href.to_string(),
+                            row.select(&subheading)
+                                .next()
+                                .map(|subheading| subheading.text().collect::<String>())
+                                .and_then(|subheading| parse_load(&subheading)),
+                        )
+                    })
+            })
+            .map(|(href, load)| {
+                base_url
+                    .join(&href)
+                    .with_context(|| format!("bad mirror url: [{href}]"))
+                    .map(|url| (HumanUrl::from(url), load))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(|mirrors| {
+                mirrors
+                    .into_iter()
+                    .sorted_by(|(_, left), (_, right)| {
+                        left.unwrap_or(f64::MAX)
+                            .total_cmp(&right.unwrap_or(f64::MAX))
+                    })
+                    .map(|(url, _)| url)
+                    .unique()
+                    .collect_vec()
+            })
+            .and_then(|mirrors| {
+                (!mirrors.is_empty())
+                    .then_some(mirrors)
+                    .context("no download mirrors found on the page")
+            })
+    }
+}
+
+/// https://www.moddb.com/downloads/start/74237 -> https://www.moddb.com/downloads/start/74237/all
+fn mirror_list_url(url: &HumanUrl) -> Result<Url> {
+    url.as_ref()
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+        .with_context(|| format!("no file id in [{url}]"))
+        .and_then(|id| {
+            format!("{BASE_URL}/downloads/start/{id}/all")
+                .pipe_deref(Url::parse)
+                .context("bad mirror list url")
+        })
+}
+
+impl ModDBDownloader {
+    #[instrument]
+    pub async fn download(url: HumanUrl) -> Result<Vec<HumanUrl>> {
+        let mirror_list_url = mirror_list_url(&url)?;
+        reqwest::Client::builder()
+            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/109.0.0.0 Safari/537.36")
+            .build()
+            .context("bad http client")?
+            .get(mirror_list_url.clone())
+            .send()
+            .map_context("fetching the moddb mirror list")
+            .and_then(|res| {
+                res.error_for_status()
+                    .context("bad status code")
+                    .pipe(ready)
+            })
+            .and_then(|res| res.text().map_context("extracting text"))
+            .and_then(|text| {
+                tokio::task::spawn_blocking(move || {
+                    response_parsing::get_mirrors_from_moddb_mirror_list(&text, &mirror_list_url)
+                        .tap_ok(|mirrors| tracing::debug!(?mirrors, "parsed moddb mirrors"))
+                })
+                .map_context("thread crashed")
+                .and_then(ready)
+            })
+            .await
+            .with_context(|| format!("preparing ModDB download for [{url}]"))
+    }
+}
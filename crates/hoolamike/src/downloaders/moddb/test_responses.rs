@@ -0,0 +1,43 @@
+use {super::*, std::str::FromStr};
+
+#[test]
+fn test_mirror_list_url() -> Result<()> {
+    ["https://www.moddb.com/downloads/start/74237", "https://www.moddb.com/downloads/start/74237/"]
+        .into_iter()
+        .try_for_each(|url| {
+            HumanUrl::from_str(url)
+                .context("bad example")
+                .and_then(|url| mirror_list_url(&url))
+                .map(|mirror_list| assert_eq!(mirror_list.as_str(), "https://www.moddb.com/downloads/start/74237/all"))
+        })
+}
+
+#[test]
+fn test_example_mirror_list() -> Result<()> {
+    response_parsing::get_mirrors_from_moddb_mirror_list(
+        include_str!("test_raw_responses/mirror-list-1.html"),
+        &Url::parse("https://www.moddb.com/downloads/start/74237/all")?,
+    )
+    .map(|mirrors| {
+        assert_eq!(
+            mirrors
+                .iter()
+                .map(|mirror| mirror.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "https://www.moddb.com/downloads/mirror/74237/121/9f8e7d6c5b4a39281706f5e4d3c2b1a0/?referer=https%3A%2F%2Fwww.moddb.com%2F",
+                "https://www.moddb.com/downloads/mirror/74237/124/0b8a5a1b2e6f3c9d4e7a8b9c0d1e2f3a/?referer=https%3A%2F%2Fwww.moddb.com%2F",
+                "https://www.moddb.com/downloads/mirror/74237/130/aa11bb22cc33dd44ee55ff6677889900/?referer=https%3A%2F%2Fwww.moddb.com%2F",
+            ]
+        )
+    })
+}
+
+#[test]
+fn test_page_without_mirrors_is_an_error() {
+    assert!(response_parsing::get_mirrors_from_moddb_mirror_list(
+        "<html><body><p>This file is no longer available</p></body></html>",
+        &Url::parse("https://www.moddb.com/downloads/start/74237/all").unwrap(),
+    )
+    .is_err())
+}
@@ -0,0 +1,180 @@
+//! servers (CDNs, file hosts) like to respond with a 200 html error page instead of the file,
+//! these checks catch it before anything is written to disk
+use {
+    anyhow::Context,
+    futures::{Stream, StreamExt},
+    reqwest::header::{HeaderMap, CONTENT_TYPE},
+    std::path::Path,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UnexpectedResponse {
+    #[error("server responded with [{content_type}] instead of the archive")]
+    ContentType { content_type: String },
+    #[error("server responded with a {kind} document instead of the archive")]
+    Document { kind: &'static str },
+    #[error("server announced [{announced}] bytes, but the archive should have [{expected}] bytes")]
+    ContentLength { announced: u64, expected: u64 },
+}
+
+/// how many bytes of the response are needed for [sniff_first_bytes]
+pub const SNIFF_LENGTH: usize = 512;
+
+/// some archives are legitimately html/json/text files, those are never rejected
+fn expects_document(output: &Path) -> bool {
+    output
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .map(|extension| ["html", "htm", "xhtml", "json", "xml", "txt", "md"].contains(&extension.as_str()))
+        .unwrap_or(false)
+}
+
+/// `expected_size` is [None] when the response is only a part of the archive
+pub fn check_headers(headers: &HeaderMap, content_length: Option<u64>, expected_size: Option<u64>, output: &Path) -> Result<(), UnexpectedResponse> {
+    if let Some((announced, expected)) = content_length.zip(expected_size) {
+        if announced != expected {
+            return Err(UnexpectedResponse::ContentLength { announced, expected });
+        }
+    }
+    if expects_document(output) {
+        return Ok(());
+    }
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.to_lowercase())
+        .filter(|content_type| {
+            ["text/html", "application/xhtml+xml", "application/json"]
+                .iter()
+                .any(|document| content_type.starts_with(document))
+        })
+        .map(|content_type| Err(UnexpectedResponse::ContentType { content_type }))
+        .unwrap_or(Ok(()))
+}
+
+/// servers do not always send a (correct) content type, so the beginning of the body is checked as well
+pub fn sniff_first_bytes(bytes: &[u8], output: &Path) -> Result<(), UnexpectedResponse> {
+    if expects_document(output) {
+        return Ok(());
+    }
+    let start = bytes
+        .strip_prefix(b"\xEF\xBB\xBF")
+        .unwrap_or(bytes)
+        .trim_ascii_start()
+        .iter()
+        .take(SNIFF_LENGTH)
+        .map(|byte| byte.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let starts_with_any = |prefixes: &[&[u8]]| prefixes.iter().any(|prefix| start.starts_with(prefix));
+    if starts_with_any(&[b"<!doctype html", b"<html", b"<head", b"<body"]) {
+        return Err(UnexpectedResponse::Document { kind: "html" });
+    }
+    let looks_like_json = match start.split_first() {
+        Some((b'{', rest)) => rest
+            .trim_ascii_start()
+            .first()
+            .is_none_or(|next| *next == b'"' || *next == b'}'),
+        _ => false,
+    };
+    match looks_like_json {
+        true => Err(UnexpectedResponse::Document { kind: "json" }),
+        false => Ok(()),
+    }
+}
+
+/// buffers the beginning of the body (servers split it however they like) and checks it with [sniff_first_bytes].
+/// the returned bytes were taken out of the stream, they have to be written before the rest of it
+pub async fn sniff_stream<S, B, E>(byte_stream: &mut S, output: &Path) -> anyhow::Result<Vec<u8>>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let mut head = Vec::with_capacity(SNIFF_LENGTH);
+    while head.len() < SNIFF_LENGTH {
+        match byte_stream.next().await {
+            Some(chunk) => head.extend_from_slice(chunk.context("reading response")?.as_ref()),
+            None => break,
+        }
+    }
+    sniff_first_bytes(&head, output)?;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            downloaders::mock_server::{MockResponse, MockServer, Routes},
+            install_modlist::downloads::stream_file,
+        },
+        anyhow::Context,
+        reqwest::header::HeaderValue,
+        tap::prelude::*,
+    };
+
+    fn headers(content_type: &str) -> HeaderMap {
+        HeaderMap::new().tap_mut(|headers| {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+        })
+    }
+
+    #[test]
+    fn test_check_headers() {
+        let archive = Path::new("SomeMod.7z");
+        assert_eq!(check_headers(&headers("application/x-7z-compressed"), Some(10), Some(10), archive), Ok(()));
+        assert_eq!(check_headers(&headers("application/octet-stream"), None, Some(10), archive), Ok(()));
+        assert_eq!(
+            check_headers(&headers("application/octet-stream"), Some(9), Some(10), archive),
+            Err(UnexpectedResponse::ContentLength { announced: 9, expected: 10 })
+        );
+        assert!(check_headers(&headers("text/html; charset=utf-8"), Some(10), Some(10), archive).is_err());
+        assert!(check_headers(&headers("application/json"), None, None, archive).is_err());
+        assert_eq!(check_headers(&headers("text/html"), None, None, Path::new("readme.html")), Ok(()));
+    }
+
+    #[test]
+    fn test_sniff_first_bytes() {
+        let archive = Path::new("SomeMod.7z");
+        assert_eq!(sniff_first_bytes(b"7z\xBC\xAF\x27\x1C\x00\x04", archive), Ok(()));
+        assert_eq!(sniff_first_bytes(b"PK\x03\x04", archive), Ok(()));
+        assert_eq!(sniff_first_bytes(b"", archive), Ok(()));
+        assert_eq!(
+            sniff_first_bytes(b"\xEF\xBB\xBF\r\n  <!DOCTYPE html><html>", archive),
+            Err(UnexpectedResponse::Document { kind: "html" })
+        );
+        assert_eq!(
+            sniff_first_bytes(b"{\n  \"error\": \"not found\"}", archive),
+            Err(UnexpectedResponse::Document { kind: "json" })
+        );
+        assert_eq!(sniff_first_bytes(b"<html>", Path::new("changelog.htm")), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_sniff_stream_buffers_split_chunks() -> anyhow::Result<()> {
+        let chunks = |chunks: &'static [&'static [u8]]| futures::stream::iter(chunks.iter().map(Ok::<_, std::io::Error>));
+        let archive = Path::new("SomeMod.7z");
+        assert!(sniff_stream(&mut chunks(&[b"\r\n  ", b"<!DOC", b"TYPE html>"]), archive)
+            .await
+            .is_err());
+        let head = sniff_stream(&mut chunks(&[b"7z\xBC", b"\xAF\x27\x1C"]), archive).await?;
+        assert_eq!(head, b"7z\xBC\xAF\x27\x1C");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_file_rejects_html_page() -> anyhow::Result<()> {
+        const PAGE: &[u8] = b"<!DOCTYPE html><html><body>this file is no longer available</body></html>";
+        let server = MockServer::start(|_| Routes::from_iter([("/SomeMod.7z".to_string(), MockResponse::bytes(PAGE))])).await?;
+        let output = tempfile::tempdir()?;
+        stream_file(server.url("/SomeMod.7z"), output.path().join("SomeMod.7z"), PAGE.len() as u64)
+            .await
+            .err()
+            .context("html page should be rejected")
+            .map(|error| assert!(format!("{error:?}").contains("html document"), "{error:?}"))?;
+        assert!(!output.path().join("SomeMod.7z").exists(), "nothing should be written for a rejected response");
+        Ok(())
+    }
+}
@@ -98,7 +98,7 @@ fn parse_wabbajack_cdn_file_response(contents: &str) -> Result<WabbajackCdnFile>
 }
 
 impl WabbajackCDNDownloader {
-    pub async fn prepare_download(WabbajackCDNDownloaderState { url }: WabbajackCDNDownloaderState) -> Result<Vec<HumanUrl>> {
+    pub async fn prepare_download(WabbajackCDNDownloaderState { url, .. }: WabbajackCDNDownloaderState) -> Result<Vec<HumanUrl>> {
         let url = url
             .clone()
             .conv::<url::Url>()
@@ -0,0 +1,168 @@
+use {
+    super::helpers::{FutureAnyhowExt, ReqwestPrettyJsonResponse},
+    crate::{
+        downloaders::wabbajack_cdn::remap_wabbajack_cdn_url,
+        modlist_json::{Archive, ArchiveDescriptor, HumanUrl},
+        octadiff_reader::ApplyDetla,
+        utils::{scoped_temp_path, PathReadWrite, ResultZipExt},
+    },
+    anyhow::{Context, Result},
+    futures::{StreamExt, TryFutureExt},
+    itertools::Itertools,
+    reqwest::Client,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        io::BufReader,
+        path::{Path, PathBuf},
+    },
+    tap::prelude::*,
+    tempfile::TempPath,
+    tokio::{io::AsyncWriteExt, sync::OnceCell},
+    tracing::{info, instrument},
+};
+
+pub const DEFAULT_UPGRADED_ARCHIVES_URL: &str = "https://raw.githubusercontent.com/wabbajack-tools/mod-lists/master/reports/upgraded.json";
+
+/// entry of the upgraded archives report published by wabbajack, only the fields needed for upgrading are read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ValidatedArchive {
+    pub original: Archive,
+    pub patched_from: Option<Archive>,
+    pub patch_url: Option<HumanUrl>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveUpgrade {
+    /// newer version of the archive, downloaded like any other archive
+    pub replacement: Archive,
+    /// octodiff patch rebuilding the original archive out of the replacement
+    pub patch_url: HumanUrl,
+}
+
+pub struct WabbajackUpgradesDownloader {
+    client: Client,
+    upgraded_archives_url: HumanUrl,
+    index: OnceCell<BTreeMap<String, ArchiveUpgrade>>,
+}
+
+impl WabbajackUpgradesDownloader {
+    pub fn new(upgraded_archives_url: HumanUrl) -> Self {
+        Self {
+            client: Client::new(),
+            upgraded_archives_url,
+            index: OnceCell::new(),
+        }
+    }
+
+    async fn index(&self) -> Result<&BTreeMap<String, ArchiveUpgrade>> {
+        self.index
+            .get_or_try_init(|| {
+                self.client
+                    .get(self.upgraded_archives_url.to_string())
+                    .send()
+                    .map_context("fetching upgraded archives report")
+                    .and_then(|response| response.json_response_ok(|_| Ok(())))
+                    .map_ok(|validated: Vec<serde_json::Value>| {
+                        let total = validated.len();
+                        // the report covers every modlist out there, one entry this version can't read is no reason to give up on the rest
+                        validated
+                            .into_iter()
+                            .filter_map(|entry| {
+                                serde_json::from_value::<ValidatedArchive>(entry)
+                                    .tap_err(|reason| tracing::debug!(%reason, "skipping unreadable upgrade entry"))
+                                    .ok()
+                            })
+                            .collect_vec()
+                            .tap(|readable| {
+                                if readable.len() < total {
+                                    tracing::warn!("skipped [{}] upgrade entries that could not be read", total - readable.len())
+                                }
+                            })
+                            .into_iter()
+                            .filter_map(
+                                |ValidatedArchive {
+                                     original,
+                                     patched_from,
+                                     patch_url,
+                                 }| {
+                                    patched_from
+                                        .zip(patch_url)
+                                        .filter(|(replacement, _)| replacement.descriptor.hash != original.descriptor.hash)
+                                        .map(|(replacement, patch_url)| (original.descriptor.hash, ArchiveUpgrade { replacement, patch_url }))
+                                },
+                            )
+                            .collect::<BTreeMap<_, _>>()
+                            .tap(|index| info!("loaded [{}] known archive upgrades", index.len()))
+                    })
+            })
+            .await
+            .with_context(|| format!("loading archive upgrades from [{}]", self.upgraded_archives_url))
+    }
+
+    #[instrument(skip(self), fields(name=%descriptor.name))]
+    pub async fn find_upgrade(&self, descriptor: &ArchiveDescriptor) -> Result<ArchiveUpgrade> {
+        self.index().await.and_then(|index| {
+            index
+                .get(&descriptor.hash)
+                .cloned()
+                .with_context(|| format!("no known upgrade path for [{}] ([{}])", descriptor.name, descriptor.hash))
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn fetch_patch(&self, patch_url: HumanUrl) -> Result<TempPath> {
+        let patch_url = patch_url
+            .conv::<url::Url>()
+            .pipe(remap_wabbajack_cdn_url)?
+            .conv::<HumanUrl>();
+        let patch_path = scoped_temp_path()?;
+        let mut output = tokio::fs::File::create(&patch_path)
+            .map_with_context(|| format!("creating [{}]", patch_path.display()))
+            .await?;
+        let mut byte_stream = self
+            .client
+            .get(patch_url.to_string())
+            .send()
+            .await
+            .with_context(|| format!("making request to {patch_url}"))?
+            .error_for_status()
+            .context("bad status")?
+            .bytes_stream();
+        while let Some(chunk) = byte_stream.next().await {
+            output
+                .write_all(&chunk.context("reading patch")?)
+                .await
+                .context("writing patch")?;
+        }
+        output.flush().await.context("flushing patch")?;
+        Ok(patch_path)
+    }
+}
+
+/// rebuilds the original archive out of its replacement and an octodiff patch
+#[instrument]
+pub fn apply_patch(replacement: &Path, patch: &Path, to: &Path, expected_size: u64) -> Result<PathBuf> {
+    replacement
+        .open_file_read()
+        .zip(patch.open_file_read())
+        .and_then(|((_, replacement), (_, patch))| ApplyDetla::new_from_readers(BufReader::new(replacement), BufReader::new(patch)))
+        .and_then(|delta| {
+            to.open_file_write()
+                .and_then(|(_, mut output)| match delta {
+                    Some(mut delta) => std::io::copy(&mut delta, &mut output).context("applying patch"),
+                    None => Ok(0),
+                })
+        })
+        .and_then(|written| {
+            written
+                .eq(&expected_size)
+                .then(|| to.to_owned())
+                .with_context(|| format!("patched archive has unexpected size (expected [{expected_size}] bytes, found [{written}] bytes)"))
+        })
+        .with_context(|| format!("patching [{}] with [{}] into [{}]", replacement.display(), patch.display(), to.display()))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,214 @@
+use {
+    super::*,
+    crate::{
+        config_file::{ArchiveUpgradesConfig, DownloadersConfig, GamesConfig, NexusConfig},
+        downloaders::mock_server::{MockResponse, MockServer, Routes},
+        install_modlist::{download_cache::to_base_64_from_u64, downloads::Synchronizers},
+        modlist_json::{HttpState, ManualState, State},
+    },
+    std::io::Write,
+};
+
+const REPLACEMENT: &[u8] = b"hello brave new world";
+const ORIGINAL: &[u8] = b"hello old world!";
+
+enum Command<'a> {
+    Copy { start: i64, length: i64 },
+    Write(&'a [u8]),
+}
+
+fn octodiff_delta(commands: &[Command]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    delta.extend_from_slice(b"OCTODELTA");
+    delta.push(0x01);
+    delta.push(4);
+    delta.extend_from_slice(b"SHA1");
+    delta.extend_from_slice(&20i32.to_le_bytes());
+    delta.extend_from_slice(&[0; 20]);
+    delta.extend_from_slice(b">>>");
+    commands.iter().for_each(|command| match command {
+        Command::Copy { start, length } => {
+            delta.push(0x60);
+            delta.extend_from_slice(&start.to_le_bytes());
+            delta.extend_from_slice(&length.to_le_bytes());
+        }
+        Command::Write(bytes) => {
+            delta.push(0x80);
+            delta.extend_from_slice(&(bytes.len() as i64).to_le_bytes());
+            delta.extend_from_slice(bytes);
+        }
+    });
+    delta
+}
+
+fn example_patch() -> Vec<u8> {
+    octodiff_delta(&[
+        Command::Copy { start: 0, length: 6 },
+        Command::Write(b"old "),
+        Command::Copy { start: 16, length: 5 },
+        Command::Write(b"!"),
+    ])
+}
+
+fn hash_of(bytes: &[u8]) -> String {
+    xxhash_rust::xxh64::xxh64(bytes, 0).pipe(to_base_64_from_u64)
+}
+
+fn http_archive(name: &str, contents: &[u8], url: HumanUrl) -> Archive {
+    Archive {
+        descriptor: ArchiveDescriptor {
+            hash: hash_of(contents),
+            meta: String::new(),
+            name: name.to_string(),
+            size: contents.len() as u64,
+            extra: Default::default(),
+        },
+        state: State::Http(HttpState {
+            headers: vec![],
+            url,
+            extra: Default::default(),
+        }),
+    }
+}
+
+fn write_temp(contents: &[u8]) -> Result<tempfile::NamedTempFile> {
+    tempfile::NamedTempFile::new()
+        .context("creating temp file")
+        .and_then(|mut file| file.write_all(contents).map(|_| file).context("writing"))
+}
+
+#[test]
+fn test_apply_patch_rebuilds_original() -> Result<()> {
+    let replacement = write_temp(REPLACEMENT)?;
+    let patch = write_temp(&example_patch())?;
+    let output = tempfile::tempdir()?.pipe(|dir| dir.path().join("original.7z").pipe(|path| (dir, path)));
+    apply_patch(replacement.path(), patch.path(), &output.1, ORIGINAL.len() as u64)
+        .and_then(|path| std::fs::read(path).context("reading output"))
+        .map(|rebuilt| assert_eq!(rebuilt, ORIGINAL))
+}
+
+#[test]
+fn test_apply_patch_rejects_wrong_size() -> Result<()> {
+    let replacement = write_temp(REPLACEMENT)?;
+    let patch = write_temp(&example_patch())?;
+    let output = tempfile::tempdir()?;
+    apply_patch(replacement.path(), patch.path(), &output.path().join("original.7z"), 1337)
+        .err()
+        .context("size mismatch should be reported")
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn test_upgrade_through_mock_cdn() -> Result<()> {
+    let server = MockServer::start(|base_url| {
+        let url = |path: &str| base_url.clone().tap_mut(|url| url.as_mut().set_path(path));
+        Routes::new().tap_mut(|routes| {
+            ValidatedArchive {
+                original: http_archive("original.7z", ORIGINAL, url("/deleted.7z")),
+                patched_from: Some(http_archive("replacement.7z", REPLACEMENT, url("/replacement.7z"))),
+                patch_url: Some(url("/patch")),
+            }
+            .pipe_ref(serde_json::to_value)
+            // entries that can't be read don't take the rest of the report down with them
+            .map(|readable| vec![serde_json::json!({"Original": 42}), readable])
+            .and_then(|report| serde_json::to_vec(&report))
+            .map(|report| routes.insert("/upgraded.json".to_string(), MockResponse::json(report)))
+            .expect("serializing report");
+            routes.insert("/replacement.7z".to_string(), MockResponse::bytes(REPLACEMENT));
+            routes.insert("/patch".to_string(), MockResponse::bytes(example_patch()));
+        })
+    })
+    .await?;
+
+    let downloads_directory = tempfile::tempdir()?;
+    let synchronizers = Synchronizers::new(
+        DownloadersConfig {
+            downloads_directory: downloads_directory.path().to_owned(),
+            nexus: NexusConfig::default(),
+            archive_overrides: Default::default(),
+            archive_upgrades: ArchiveUpgradesConfig {
+                enabled: true,
+                upgraded_archives_url: server.url("/upgraded.json"),
+            },
+            github: Default::default(),
+            ips4: Default::default(),
+        },
+        GamesConfig::new(),
+    )?;
+
+    synchronizers
+        .sync_downloads(vec![http_archive("original.7z", ORIGINAL, server.url("/deleted.7z"))])
+        .await
+        .map_err(|errors| anyhow::anyhow!("{errors:#?}"))
+        .and_then(|synced| {
+            synced
+                .into_iter()
+                .next()
+                .context("nothing was synchronized")
+        })
+        .and_then(|synced| std::fs::read(&synced.inner).context("reading rebuilt archive"))
+        .map(|rebuilt| assert_eq!(rebuilt, ORIGINAL))
+}
+
+#[test]
+fn test_only_missing_originals_are_upgraded() {
+    let mismatch = anyhow::Error::new(crate::install_modlist::download_cache::HashMismatch {
+        expected: "a".into(),
+        found: "b".into(),
+    })
+    .context("synchronized file does not match the modlist");
+    assert!(Synchronizers::original_is_gone(&mismatch));
+    assert!(!Synchronizers::original_is_gone(&anyhow::anyhow!("connection reset by peer")));
+}
+
+#[tokio::test]
+async fn test_originals_that_fail_for_other_reasons_are_not_upgraded() -> Result<()> {
+    let server = MockServer::start(|base_url| {
+        let url = |path: &str| base_url.clone().tap_mut(|url| url.as_mut().set_path(path));
+        Routes::new().tap_mut(|routes| {
+            ValidatedArchive {
+                original: http_archive("original.7z", ORIGINAL, url("/deleted.7z")),
+                patched_from: Some(http_archive("replacement.7z", REPLACEMENT, url("/replacement.7z"))),
+                patch_url: Some(url("/patch")),
+            }
+            .pipe_ref(|upgrade| serde_json::to_vec(&[upgrade]))
+            .map(|report| routes.insert("/upgraded.json".to_string(), MockResponse::json(report)))
+            .expect("serializing report");
+            routes.insert("/replacement.7z".to_string(), MockResponse::bytes(REPLACEMENT));
+            routes.insert("/patch".to_string(), MockResponse::bytes(example_patch()));
+        })
+    })
+    .await?;
+
+    let downloads_directory = tempfile::tempdir()?;
+    let synchronizers = Synchronizers::new(
+        DownloadersConfig {
+            downloads_directory: downloads_directory.path().to_owned(),
+            nexus: NexusConfig::default(),
+            archive_overrides: Default::default(),
+            archive_upgrades: ArchiveUpgradesConfig {
+                enabled: true,
+                upgraded_archives_url: server.url("/upgraded.json"),
+            },
+            github: Default::default(),
+            ips4: Default::default(),
+        },
+        GamesConfig::new(),
+    )?;
+    // an upgrade is available, but the original only needs a manual download
+    let original = http_archive("original.7z", ORIGINAL, server.url("/deleted.7z")).tap_mut(|archive| {
+        archive.state = State::Manual(ManualState {
+            prompt: "download it from the author's website".to_string(),
+            url: server.url("/original.7z"),
+            extra: Default::default(),
+        })
+    });
+    let reason = synchronizers
+        .prepare_sync_task(original)
+        .await
+        .err()
+        .context("a manual download should not be replaced by its upgrade")?;
+    assert!(format!("{reason:?}").contains("Manual action is required"), "{reason:?}");
+    assert!(!format!("{reason:?}").contains("upgrade"), "{reason:?}");
+    Ok(())
+}
@@ -9,17 +9,17 @@ use {
     },
     tracing::{info, instrument},
 };
-type Sha1Hash = [u8; 20];
+pub(crate) type Sha1Hash = [u8; 20];
 
 static RELEASE_IDS: &[&str] = &["US", "Unknown (DE?)", "RU"];
 
-static PATCHED_HASHES: &[Sha1Hash] = &[
+pub(crate) static PATCHED_HASHES: &[Sha1Hash] = &[
     hex!("0021023e37b1af143305a61b7b29a1811cc7c5fb"),
     hex!("37cae4e713b6b182311f66e31668d5005d1b9f5b"),
     hex!("600cd576cde7746fb2cd152fdd24db97453ed135"),
     hex!("34b65096caef9374dd6aa39af855e43308b417f2"),
 ];
-static UNPATCHED_HASHES: &[Sha1Hash] = &[
+pub(crate) static UNPATCHED_HASHES: &[Sha1Hash] = &[
     hex!("d068f394521a67c6e74fe572f59bd1be71e855f3"),
     hex!("07affda66c89f09b0876a50c77759640bc416673"),
     hex!("3980940522f0264ed9af14aea1773bb19f5160ab"),
@@ -32,7 +32,7 @@ static UNPATCHED_HASHES: &[Sha1Hash] = &[
 static NVVERPATCH1: &[&[u8]] = &[b"\x3E\xF9\xFC".as_slice(), b"\x88\xC4\xFC".as_slice(), b"\x15\x43\xFD".as_slice()];
 static NVVERPATCH2: &[&[u8]] = &[b"\x32\x32\x33\x38".as_slice(), b"\x32\x32\x33\x38".as_slice(), b"\x32\x32\x34\x39".as_slice()];
 
-fn sha1_hash_file(file: &Path) -> Result<Sha1Hash> {
+pub(crate) fn sha1_hash_file(file: &Path) -> Result<Sha1Hash> {
     use sha1::Digest;
     file.open_file_read()
         .map(|(_, file)| std::io::BufReader::new(file))
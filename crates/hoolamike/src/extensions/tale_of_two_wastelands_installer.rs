@@ -2,9 +2,10 @@ use {
     crate::{
         compression::{preheated_archive::PreheatedArchive, ProcessArchive, SeekWithTempFileExt},
         config_file::HoolamikeConfig,
-        modlist_json::GameName,
+        games::Game,
+        path_resolver,
         progress_bars_v2::{count_progress_style, IndicatifWrapIoExt},
-        utils::{scoped_temp_file, MaybeWindowsPath, PathReadWrite, ReadableCatchUnwindExt},
+        utils::{scoped_temp_file, AtomicFile, MaybeWindowsPath, PathReadWrite, ReadableCatchUnwindExt},
     },
     anyhow::{Context, Result},
     handle_asset::AssetContext,
@@ -114,7 +115,7 @@ impl VariablesContext {
                     "FO3ROOT" => self
                         .hoolamike_installation_config
                         .games
-                        .get(&GameName::new("Fallout3".to_string()))
+                        .get(&Game::Fallout3.game_name())
                         .context("'Fallout3' is not found in hoolamike defined games")
                         .map(|p| p.root_directory.display().to_string().pipe(Cow::Owned))
                         .tap_ok(|value| info!(%variable_name, %value, "⭐⭐⭐ MAGICALLY ⭐⭐⭐ filling the variable using hoolamike derived context")),
@@ -122,7 +123,7 @@ impl VariablesContext {
                     "FNVROOT" => self
                         .hoolamike_installation_config
                         .games
-                        .get(&GameName::new("FalloutNewVegas".to_string()))
+                        .get(&Game::FalloutNewVegas.game_name())
                         .context("'FalloutNewVegas' is not found in hoolamike defined games")
                         .map(|p| p.root_directory.display().to_string().pipe(Cow::Owned))
                         .tap_ok(|value| info!(%variable_name, %value, "⭐⭐⭐ MAGICALLY ⭐⭐⭐ filling the variable using hoolamike derived context")),
@@ -199,11 +200,15 @@ impl FullLocation {
                     .clone()
                     .pipe(MaybeWindowsPath)
                     .pipe(MaybeWindowsPath::into_path)
-                    .pipe(|folder| folder.join(self.path.0.into_path()).normalize())
-                    .open_file_write()
-                    .and_then(|(target_path, mut target_file)| {
-                        std::io::copy(from_reader, &mut target_file)
-                            .with_context(|| format!("copying into [{target_path:#?}]"))
+                    // not normalized here, a leading `..` has to reach the sanitizer to be rejected
+                    .pipe(|folder| path_resolver::output_path(&folder, &self.path.0.into_path()))
+                    .and_then(|target_path| {
+                        AtomicFile::create(&target_path)
+                            .and_then(|mut target_file| {
+                                std::io::copy(from_reader, &mut target_file)
+                                    .with_context(|| format!("copying into [{target_path:#?}]"))
+                                    .and_then(|wrote| target_file.commit().map(|_| wrote))
+                            })
                             .map(|wrote| tracing::info!(?target_path, "wrote [{wrote}bytes]"))
                     })
                     .map(|_| None),
@@ -252,7 +257,7 @@ impl FullLocation {
                             .clone()
                             .pipe(MaybeWindowsPath)
                             .pipe(MaybeWindowsPath::into_path)
-                            .pipe(|path| path.join(self.path.0.into_path()).normalize())
+                            .pipe(|path| path_resolver::existing_path(&path, &self.path.0.into_path().normalize()))
                             .pipe(|source| {
                                 source
                                     .open_file_read()
@@ -262,7 +267,10 @@ impl FullLocation {
                             inner: ReadArchiveLocation { name: _, value },
                             ..
                         }) => {
-                            let value = MaybeWindowsPath(value.clone()).into_path().normalize();
+                            let value = MaybeWindowsPath(value.clone())
+                                .into_path()
+                                .normalize()
+                                .pipe_ref(|value| path_resolver::existing_absolute_path(value));
                             crate::compression::ArchiveHandle::with_guessed(value.as_path(), value.extension(), |mut archive| {
                                 archive.get_handle(&self.path.clone().0.into_path())
                             })
@@ -288,9 +296,13 @@ pub fn install(CliConfig { contains }: CliConfig, hoolamike_config: HoolamikeCon
         .context("no tale of two wastelands configured in hoolamike.yaml")?;
     let fallout_new_vegas_exe_path = hoolamike_config
         .games
-        .get(&GameName::new("FalloutNewVegas".to_string()))
+        .get(&Game::FalloutNewVegas.game_name())
         .context("new vegas not configured")
-        .map(|game| game.root_directory.join("FalloutNV.exe"))
+        .and_then(|game| {
+            Game::FalloutNewVegas
+                .main_executable(&game.root_directory)
+                .context("no main executable registered")
+        })
         .and_then(|path| {
             path.try_exists()
                 .context("checking for file existence")
@@ -447,7 +459,10 @@ pub fn install(CliConfig { contains }: CliConfig, hoolamike_config: HoolamikeCon
                                         .into_iter()
                                         .flatten()
                                         .map(|(source, ReadArchiveLocation { name: _, value })| {
-                                            let archive_path = MaybeWindowsPath(value).into_path().normalize();
+                                            let archive_path = MaybeWindowsPath(value)
+                                                .into_path()
+                                                .normalize()
+                                                .pipe_ref(|value| path_resolver::existing_absolute_path(value));
                                             PreheatedArchive::from_archive_concurrent(&archive_path, 128).map(|preheated| (source, preheated))
                                         })
                                         .collect::<Result<BTreeMap<_, _>>>()
@@ -514,12 +529,14 @@ pub fn install(CliConfig { contains }: CliConfig, hoolamike_config: HoolamikeCon
                                                             output_path
                                                                 .into_path()
                                                                 .normalize()
-                                                                .open_file_write()
-                                                                .and_then(|(output_path, output)| {
+                                                                .pipe_ref(|output_path| path_resolver::output_absolute_path(output_path))
+                                                                .and_then(|output_path| AtomicFile::create(&output_path).map(|output| (output_path, output)))
+                                                                .and_then(|(output_path, mut output)| {
                                                                     archive
-                                                                        .write(&mut tracing::Span::current().wrap_write(0, output), &options)
+                                                                        .write(&mut tracing::Span::current().wrap_write(0, &mut output), &options)
                                                                         .with_context(|| format!("writing built bsa file to {output_path:?}"))
-                                                                        .tap_ok(|_| info!(?output_path, "[OK]"))
+                                                                        .and_then(|_| output.commit())
+                                                                        .map(|output_path| info!(?output_path, "[OK]"))
                                                                 })
                                                         })
                                                     })
@@ -54,6 +54,7 @@ pub fn build_bsa<F: FnOnce(&Archive<'_>, ArchiveOptions, MaybeWindowsPath) -> Re
                                         flip_compression: true,
                                         index: 0,
                                         path: archive_path.clone(),
+                                        extra: Default::default(),
                                     },
                                 )
                                 .with_context(|| format!("loading file at [{path:?}]"))
@@ -1,8 +1,13 @@
 use {
     super::manifest_file::PostCommand,
+    crate::{
+        path_resolver,
+        safe_path::{sanitize_relative_path, sanitized_join, UnsafePath},
+        utils::MaybeWindowsPath,
+    },
     anyhow::{Context, Result},
     futures::TryFutureExt,
-    std::path::PathBuf,
+    std::path::{Component, Path, PathBuf},
     tap::prelude::*,
     tracing::{debug, info, instrument},
     typed_path::Utf8TypedPath,
@@ -18,6 +23,13 @@ fn normalize_windows_shell_path(path: String) -> Result<PathBuf> {
     #[cfg(unix)]
     let path = path.replace(r#"\"#, "/");
     let path = snailquote::unescape(&path).context("unescaping")?;
+    // normalizing would quietly resolve these, so they're rejected before that
+    if Path::new(&path)
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        anyhow::bail!(UnsafePath::ParentEscape(path.into()));
+    }
     match Utf8TypedPath::derive(&path) {
         Utf8TypedPath::Unix(utf8_path) => utf8_path
             .with_platform_encoding_checked()
@@ -80,6 +92,14 @@ impl ParsedPostCommand {
                     let to = next(&mut parser, "to")
                         .await
                         .and_then(|s| snailquote::unescape(&s).context("unescaping"))?;
+                    // renaming can't move the file into another directory
+                    sanitize_relative_path(&MaybeWindowsPath(to.clone()).into_path())
+                        .context("invalid new name")
+                        .and_then(|sanitized| {
+                            (sanitized.components().count() == 1 && sanitized == Path::new(&to))
+                                .then_some(())
+                                .with_context(|| format!("[{to}] is not a plain file name"))
+                        })?;
 
                     Ok(ParsedPostCommand::Rename(from, to))
                 }
@@ -99,8 +119,18 @@ pub fn handle_post_commands(post_commands: Vec<PostCommand>) -> Result<()> {
             .and_then(|command| {
                 match &command {
                     ParsedPostCommand::Rename(from, new_file_name) => {
-                        let (from, to) = (&from, from.with_file_name(new_file_name));
-                        std::fs::rename(from, &to).with_context(|| format!("renaming [{from:?}] -> [{to:?}]"))
+                        let from = path_resolver::existing_absolute_path(from);
+                        let to = from
+                            .parent()
+                            .with_context(|| format!("[{from:?}] has no parent"))
+                            .and_then(|parent| sanitized_join(parent, Path::new(new_file_name)))?;
+                        std::fs::rename(&from, &to)
+                            .with_context(|| format!("renaming [{from:?}] -> [{to:?}]"))
+                            .tap_ok(|_| {
+                                from.parent()
+                                    .into_iter()
+                                    .for_each(|parent| path_resolver::resolver().forget(parent))
+                            })
                     }
                     ParsedPostCommand::Delete(_path_buf) => {
                         info!("skipping {command:?}");
@@ -170,6 +200,14 @@ mod tests {
         Ok(())
     }
 
+    #[test_log::test]
+    fn test_traversal_is_rejected() {
+        assert!(ParsedPostCommand::parse("cmd.exe /C del \"%DESTINATION%\\..\\..\\.bashrc\"").is_err());
+        assert!(ParsedPostCommand::parse("cmd.exe /C ren \"%DESTINATION%\\a.bsa\" \"..\\b.bsa\"").is_err());
+        assert!(ParsedPostCommand::parse("cmd.exe /C ren \"%DESTINATION%\\a.bsa\" \"C:\\b.bsa\"").is_err());
+        assert!(ParsedPostCommand::parse("cmd.exe /C ren \"%DESTINATION%\\a.bsa\" \"/tmp/b.bsa\"").is_err());
+    }
+
     #[test_log::test]
     fn test_example_6() -> Result<()> {
         assert_eq!(
@@ -0,0 +1,95 @@
+//! finds games installed through launchers, so that they don't have to be configured by hand
+use {
+    crate::{
+        config_file::{GameConfig, GamesConfig},
+        game_locator::editions::Edition,
+        games::Game,
+        modlist_json::GameName,
+    },
+    std::path::PathBuf,
+    tap::prelude::*,
+    tracing::{info, instrument, warn},
+};
+
+pub mod editions;
+pub mod heroic;
+pub mod lutris;
+pub mod steam;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedGame {
+    pub game: Game,
+    pub root_directory: PathBuf,
+    /// launcher the game was found through
+    pub source: &'static str,
+    /// only known for games with known executable hashes
+    pub edition: Option<Edition>,
+}
+
+/// steam installs are preferred over GOG ones, as that's what most modlists are made for
+pub fn locate_installed_games() -> Vec<LocatedGame> {
+    match std::env::var_os("HOME").map(PathBuf::from) {
+        Some(home) => [steam::locate_games, heroic::locate_games, lutris::locate_games]
+            .into_iter()
+            .flat_map(|locate| locate(&home))
+            .map(|located| LocatedGame {
+                edition: editions::detect_edition(located.game, &located.root_directory)
+                    .tap_err(|reason| warn!("could not detect edition of [{}]: {reason:?}", located.game))
+                    .ok()
+                    .flatten(),
+                ..located
+            })
+            .collect(),
+        None => {
+            warn!("HOME is not set, games will not be detected");
+            vec![]
+        }
+    }
+}
+
+/// explicitly configured games always win, only games the modlist needs are added
+#[instrument(skip(games, located))]
+pub fn fill_missing_games(
+    games: GamesConfig,
+    needed: impl IntoIterator<Item = GameName> + std::fmt::Debug,
+    located: impl FnOnce() -> Vec<LocatedGame>,
+) -> GamesConfig {
+    let missing = needed
+        .into_iter()
+        .filter(|needed| {
+            !games
+                .keys()
+                .any(|configured| configured.is_same_game(needed))
+        })
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return games;
+    }
+    let located = located();
+    missing.into_iter().fold(games, |games, needed| {
+        match needed
+            .game()
+            .ok()
+            .and_then(|game| located.iter().find(|located| located.game == game))
+        {
+            Some(LocatedGame {
+                root_directory,
+                source,
+                edition,
+                ..
+            }) => games.tap_mut(|games| {
+                match edition {
+                    Some(edition) => info!("found [{needed}] at [{}] ({source}, {edition} edition)", root_directory.display()),
+                    None => info!("found [{needed}] at [{}] ({source})", root_directory.display()),
+                }
+                games.insert(
+                    needed,
+                    GameConfig {
+                        root_directory: root_directory.clone(),
+                    },
+                );
+            }),
+            None => games.tap(|_| warn!("[{needed}] is not configured and could not be found, add it to the 'games' section of the config")),
+        }
+    })
+}
@@ -0,0 +1,78 @@
+//! tells store editions apart by hashing the main executable
+use {
+    crate::{
+        extensions::fallout_new_vegas_4gb_patch::{sha1_hash_file, Sha1Hash, PATCHED_HASHES, UNPATCHED_HASHES},
+        games::Game,
+    },
+    anyhow::{Context, Result},
+    std::path::Path,
+    tracing::instrument,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum Edition {
+    Steam,
+    #[display("GOG")]
+    Gog,
+}
+
+/// executables known to belong to a specific edition, 4gb-patched ones included
+pub fn known_executables() -> impl Iterator<Item = (Game, Edition, &'static Sha1Hash)> {
+    // the 4gb patcher lists two unpatched hashes per release, the last release being GOG
+    let unpatched = UNPATCHED_HASHES
+        .iter()
+        .enumerate()
+        .map(|(idx, hash)| (idx / 2, hash));
+    let patched = PATCHED_HASHES.iter().enumerate();
+    unpatched.chain(patched).map(|(release, hash)| {
+        (
+            Game::FalloutNewVegas,
+            match release {
+                3 => Edition::Gog,
+                _ => Edition::Steam,
+            },
+            hash,
+        )
+    })
+}
+
+pub fn edition_of_hash(game: Game, hash: &Sha1Hash) -> Option<Edition> {
+    known_executables()
+        .find(|(known_game, _, known_hash)| *known_game == game && *known_hash == hash)
+        .map(|(_, edition, _)| edition)
+}
+
+/// [None] when there are no known hashes for this game or the executable is not recognized
+#[instrument]
+pub fn detect_edition(game: Game, root_directory: &Path) -> Result<Option<Edition>> {
+    if !known_executables().any(|(known, ..)| known == game) {
+        return Ok(None);
+    }
+    match game.main_executable(root_directory) {
+        Some(executable) if executable.exists() => sha1_hash_file(&executable)
+            .with_context(|| format!("hashing [{}]", executable.display()))
+            .map(|hash| edition_of_hash(game, &hash)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallout_new_vegas_editions() {
+        assert_eq!(
+            edition_of_hash(Game::FalloutNewVegas, &hex_literal::hex!("946d2eaba04a75ff361b8617c7632b49f1ede9d3")),
+            Some(Edition::Gog)
+        );
+        assert_eq!(
+            edition_of_hash(Game::FalloutNewVegas, &hex_literal::hex!("d068f394521a67c6e74fe572f59bd1be71e855f3")),
+            Some(Edition::Steam)
+        );
+        assert_eq!(
+            edition_of_hash(Game::SkyrimSpecialEdition, &hex_literal::hex!("946d2eaba04a75ff361b8617c7632b49f1ede9d3")),
+            None
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! GOG games installed through the heroic games launcher
+use {
+    super::LocatedGame,
+    crate::games::Game,
+    anyhow::{Context, Result},
+    serde::Deserialize,
+    std::path::{Path, PathBuf},
+    tap::prelude::*,
+    tracing::{debug, instrument},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// native and flatpak installations, relative to home
+const HEROIC_CONFIG_ROOTS: &[&str] = &[".config/heroic", ".var/app/com.heroicgameslauncher.hgl/config/heroic"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct InstalledGame {
+    /// GOG product id
+    #[serde(rename = "appName")]
+    pub app_name: String,
+    pub install_path: PathBuf,
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub is_dlc: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstalledFile {
+    installed: Vec<InstalledGame>,
+}
+
+pub fn parse_installed(contents: &str) -> Result<Vec<InstalledGame>> {
+    serde_json::from_str::<InstalledFile>(contents)
+        .context("parsing heroic installed.json")
+        .map(|file| file.installed)
+}
+
+fn installed_games(config_root: &Path) -> Result<Vec<InstalledGame>> {
+    let path = config_root.join("gog_store").join("installed.json");
+    std::fs::read_to_string(&path)
+        .context("reading file")
+        .and_then(|contents| parse_installed(&contents))
+        .with_context(|| format!("reading [{}]", path.display()))
+}
+
+/// games from the registry installed through heroic's GOG integration
+#[instrument]
+pub fn locate_games(home: &Path) -> Vec<LocatedGame> {
+    HEROIC_CONFIG_ROOTS
+        .iter()
+        .map(|root| home.join(root))
+        .filter(|root| root.is_dir())
+        .flat_map(|root| {
+            installed_games(&root)
+                .tap_err(|reason| debug!("{reason:?}"))
+                .unwrap_or_default()
+        })
+        .filter(|installed| !installed.is_dlc && installed.install_path.is_dir())
+        .flat_map(|installed| {
+            let gog_id = installed.app_name.parse::<u64>().ok();
+            Game::all()
+                .filter(move |game| gog_id.is_some_and(|gog_id| game.meta().gog_ids.contains(&gog_id)))
+                .map(move |game| LocatedGame {
+                    game,
+                    root_directory: installed.install_path.clone(),
+                    source: "heroic (GOG)",
+                    edition: None,
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use super::*;
+
+#[test]
+fn test_parse_installed() -> Result<()> {
+    parse_installed(include_str!("test_data/installed.json")).map(|installed| {
+        assert_eq!(
+            installed.first(),
+            Some(&InstalledGame {
+                app_name: "1454587428".to_string(),
+                install_path: "/home/deck/Games/Heroic/Fallout New Vegas".into(),
+                platform: Some("windows".to_string()),
+                is_dlc: false,
+            })
+        );
+        assert_eq!(installed.len(), 2);
+    })
+}
+
+#[test]
+fn test_locate_games_in_flatpak_heroic() -> Result<()> {
+    let home = tempfile::tempdir()?;
+    let config = home
+        .path()
+        .join(".var/app/com.heroicgameslauncher.hgl/config/heroic/gog_store");
+    let install_path = home.path().join("Games/Heroic/Fallout New Vegas");
+    std::fs::create_dir_all(&config)?;
+    std::fs::create_dir_all(&install_path)?;
+    std::fs::write(
+        config.join("installed.json"),
+        include_str!("test_data/installed.json").replace("/home/deck/Games/Heroic/Fallout New Vegas", &install_path.display().to_string()),
+    )?;
+
+    assert_eq!(
+        locate_games(home.path()),
+        [LocatedGame {
+            game: Game::FalloutNewVegas,
+            root_directory: install_path,
+            source: "heroic (GOG)",
+            edition: None,
+        }]
+    );
+    Ok(())
+}
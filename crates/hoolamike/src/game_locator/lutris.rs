@@ -0,0 +1,149 @@
+//! games installed through lutris, recognized by the executable the lutris config points at
+use {
+    super::LocatedGame,
+    crate::games::Game,
+    anyhow::{Context, Result},
+    itertools::Itertools,
+    serde::Deserialize,
+    std::path::{Path, PathBuf},
+    tap::prelude::*,
+    tracing::{debug, instrument},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// older and newer lutris versions, relative to home
+const LUTRIS_GAME_DIRECTORIES: &[&str] = &[".config/lutris/games", ".local/share/lutris/games"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LutrisGameSection {
+    /// absent for games run through the steam runner
+    #[serde(default)]
+    pub exe: Option<PathBuf>,
+    #[serde(default)]
+    pub prefix: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LutrisGameConfig {
+    pub game: LutrisGameSection,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub game_slug: Option<String>,
+}
+
+pub fn parse_game_config(contents: &str) -> Result<LutrisGameConfig> {
+    serde_yaml::from_str(contents).context("parsing lutris game config")
+}
+
+/// root directory of the game, if `exe` ends with the (relative) main executable of the registry
+pub fn strip_main_executable(exe: &Path, main_executable: &str) -> Option<PathBuf> {
+    let relative = main_executable.split(['/', '\\']).collect_vec();
+    let components = exe
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect_vec();
+    components
+        .len()
+        .checked_sub(relative.len())
+        .filter(|&start| {
+            components[start..]
+                .iter()
+                .zip(&relative)
+                .all(|(component, expected)| component.eq_ignore_ascii_case(expected))
+        })
+        .and_then(|_| exe.ancestors().nth(relative.len()))
+        .map(Path::to_path_buf)
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// some games share an executable (skyrim and enderal), the lutris name then has to settle it
+fn recognize(config: &LutrisGameConfig, hint: &str) -> Option<(Game, PathBuf)> {
+    let exe = config.game.exe.as_deref()?;
+    let candidates = Game::all()
+        .filter_map(|game| {
+            game.meta()
+                .main_executable
+                .and_then(|main_executable| strip_main_executable(exe, main_executable))
+                .map(|root| (game, root))
+        })
+        .collect_vec();
+    match candidates.as_slice() {
+        [] => None,
+        [single] => Some(single.clone()),
+        ambiguous => {
+            let hint = [Some(hint), config.name.as_deref(), config.game_slug.as_deref()]
+                .into_iter()
+                .flatten()
+                .map(normalize)
+                .join(" ");
+            ambiguous
+                .iter()
+                .filter(|(game, _)| hint.contains(&normalize(game.meta().wabbajack_name)))
+                // the most specific name wins, so "enderalspecialedition" beats "enderal"
+                .max_by_key(|(game, _)| game.meta().wabbajack_name.len())
+                .cloned()
+                .tap(|recognized| {
+                    if recognized.is_none() {
+                        debug!(?ambiguous, %hint, "could not tell which game this is");
+                    }
+                })
+        }
+    }
+}
+
+fn game_configs(directory: &Path) -> Result<Vec<(PathBuf, LutrisGameConfig)>> {
+    std::fs::read_dir(directory)
+        .with_context(|| format!("reading [{}]", directory.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "yml"))
+        .filter_map(|path| {
+            std::fs::read_to_string(&path)
+                .context("reading file")
+                .and_then(|contents| parse_game_config(&contents))
+                .with_context(|| format!("reading [{}]", path.display()))
+                .tap_err(|reason| debug!("skipping lutris config: {reason:?}"))
+                .ok()
+                .map(|config| (path, config))
+        })
+        .collect_vec()
+        .pipe(Ok)
+}
+
+/// games from the registry whose lutris config points at a known main executable
+#[instrument]
+pub fn locate_games(home: &Path) -> Vec<LocatedGame> {
+    LUTRIS_GAME_DIRECTORIES
+        .iter()
+        .map(|directory| home.join(directory))
+        .filter(|directory| directory.is_dir())
+        .flat_map(|directory| {
+            game_configs(&directory)
+                .tap_err(|reason| debug!("{reason:?}"))
+                .unwrap_or_default()
+        })
+        .filter_map(|(path, config)| {
+            // config files are named after the game slug
+            let hint = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            recognize(&config, &hint)
+        })
+        .filter(|(_, root_directory)| root_directory.is_dir())
+        .map(|(game, root_directory)| LocatedGame {
+            game,
+            root_directory,
+            source: "lutris",
+            edition: None,
+        })
+        .collect()
+}
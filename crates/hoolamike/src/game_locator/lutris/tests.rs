@@ -0,0 +1,64 @@
+use super::*;
+
+#[test]
+fn test_parse_game_config() -> Result<()> {
+    parse_game_config(include_str!("test_data/enderal-special-edition-1700000000.yml")).map(|config| {
+        assert_eq!(
+            config.game.exe.as_deref(),
+            Some(Path::new(
+                "/home/deck/Games/enderal-special-edition/drive_c/GOG Games/Enderal Special Edition/SkyrimSE.exe"
+            ))
+        );
+        assert_eq!(config.game_slug.as_deref(), Some("enderal-special-edition"));
+    })
+}
+
+#[test]
+fn test_strip_main_executable() {
+    assert_eq!(
+        strip_main_executable(Path::new("/games/witcher/bin/x64/WITCHER3.exe"), "bin/x64/witcher3.exe"),
+        Some(PathBuf::from("/games/witcher"))
+    );
+    assert_eq!(strip_main_executable(Path::new("/games/witcher/witcher3.exe"), "bin/x64/witcher3.exe"), None);
+    assert_eq!(strip_main_executable(Path::new("witcher3.exe"), "bin/x64/witcher3.exe"), None);
+}
+
+#[test]
+fn test_shared_executable_is_settled_by_name() -> Result<()> {
+    let config = parse_game_config(include_str!("test_data/enderal-special-edition-1700000000.yml"))?;
+    assert_eq!(recognize(&config, "").map(|(game, _)| game), Some(Game::EnderalSpecialEdition));
+    let anonymous = LutrisGameConfig {
+        name: None,
+        game_slug: None,
+        ..config
+    };
+    assert_eq!(recognize(&anonymous, "my-game").map(|(game, _)| game), None);
+    Ok(())
+}
+
+#[test]
+fn test_locate_games_in_lutris() -> Result<()> {
+    let home = tempfile::tempdir()?;
+    let games = home.path().join(".local/share/lutris/games");
+    let root = home
+        .path()
+        .join("Games/enderal-special-edition/drive_c/GOG Games/Enderal Special Edition");
+    std::fs::create_dir_all(&games)?;
+    std::fs::create_dir_all(&root)?;
+    std::fs::write(
+        games.join("enderal-special-edition-1700000000.yml"),
+        include_str!("test_data/enderal-special-edition-1700000000.yml").replace("/home/deck", &home.path().display().to_string()),
+    )?;
+    std::fs::write(games.join("broken.yml"), "game: [")?;
+
+    assert_eq!(
+        locate_games(home.path()),
+        [LocatedGame {
+            game: Game::EnderalSpecialEdition,
+            root_directory: root,
+            source: "lutris",
+            edition: None,
+        }]
+    );
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use {
+    super::LocatedGame,
+    crate::games::Game,
+    anyhow::{Context, Result},
+    itertools::Itertools,
+    std::path::{Path, PathBuf},
+    tap::prelude::*,
+    tracing::{debug, instrument},
+    vdf::VdfValue,
+};
+
+pub mod vdf;
+
+#[cfg(test)]
+mod tests;
+
+/// native, flatpak and snap installations, relative to home
+const STEAM_ROOTS: &[&str] = &[
+    ".steam/steam",
+    ".steam/root",
+    ".local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/data/Steam",
+    "snap/steam/common/.local/share/Steam",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryFolder {
+    pub path: PathBuf,
+    pub apps: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppManifest {
+    pub app_id: u32,
+    pub name: String,
+    pub install_dir: String,
+}
+
+pub fn parse_library_folders(contents: &str) -> Result<Vec<LibraryFolder>> {
+    vdf::parse(contents).and_then(|root| {
+        root.get("libraryfolders")
+            .context("no [libraryfolders]")?
+            .entries()
+            .iter()
+            .filter_map(|(_, folder)| match folder {
+                VdfValue::Object(_) => Some(folder),
+                // very old format: "1" "/path/to/library"
+                VdfValue::String(_) => None,
+            })
+            .map(|folder| {
+                folder
+                    .get("path")
+                    .and_then(VdfValue::as_str)
+                    .context("no [path] in library folder")
+                    .map(|path| LibraryFolder {
+                        path: PathBuf::from(path),
+                        apps: folder
+                            .get("apps")
+                            .map(|apps| {
+                                apps.entries()
+                                    .iter()
+                                    .filter_map(|(app_id, _)| app_id.parse().ok())
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                    })
+            })
+            .collect()
+    })
+}
+
+pub fn parse_app_manifest(contents: &str) -> Result<AppManifest> {
+    vdf::parse(contents).and_then(|root| {
+        let app_state = root.get("AppState").context("no [AppState]")?;
+        let field = |name: &str| {
+            app_state
+                .get(name)
+                .and_then(VdfValue::as_str)
+                .with_context(|| format!("no [{name}] in app manifest"))
+        };
+        Ok(AppManifest {
+            app_id: field("appid")?.parse().context("bad [appid]")?,
+            name: field("name")?.to_string(),
+            install_dir: field("installdir")?.to_string(),
+        })
+    })
+}
+
+/// existing steam installations, symlinked ones (~/.steam/steam) are reported once
+pub fn steam_roots(home: &Path) -> Vec<PathBuf> {
+    STEAM_ROOTS
+        .iter()
+        .map(|root| home.join(root))
+        .filter(|root| root.join("steamapps").is_dir())
+        .unique_by(|root| root.canonicalize().unwrap_or_else(|_| root.clone()))
+        .collect()
+}
+
+fn library_folders(steam_root: &Path) -> Result<Vec<PathBuf>> {
+    let path = steam_root.join("steamapps").join("libraryfolders.vdf");
+    std::fs::read_to_string(&path)
+        .context("reading file")
+        .and_then(|contents| parse_library_folders(&contents))
+        .map(|folders| folders.into_iter().map(|folder| folder.path).collect_vec())
+        // the library inside of the steam installation is not always listed
+        .map(|folders| folders.tap_mut(|folders| folders.insert(0, steam_root.to_owned())))
+        .with_context(|| format!("reading [{}]", path.display()))
+}
+
+fn installed_apps(library: &Path) -> Result<Vec<(AppManifest, PathBuf)>> {
+    let steamapps = library.join("steamapps");
+    std::fs::read_dir(&steamapps)
+        .with_context(|| format!("reading [{}]", steamapps.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy())
+                .is_some_and(|name| name.starts_with("appmanifest_") && name.ends_with(".acf"))
+        })
+        .filter_map(|manifest| {
+            std::fs::read_to_string(&manifest)
+                .context("reading file")
+                .and_then(|contents| parse_app_manifest(&contents))
+                .with_context(|| format!("reading [{}]", manifest.display()))
+                .tap_err(|reason| debug!("skipping app manifest: {reason:?}"))
+                .ok()
+        })
+        .map(|manifest| {
+            steamapps
+                .join("common")
+                .join(&manifest.install_dir)
+                .pipe(|install_dir| (manifest, install_dir))
+        })
+        .collect_vec()
+        .pipe(Ok)
+}
+
+/// games from the registry installed through any of the steam installations found in `home`
+#[instrument]
+pub fn locate_games(home: &Path) -> Vec<LocatedGame> {
+    steam_roots(home)
+        .into_iter()
+        .flat_map(|steam_root| {
+            library_folders(&steam_root)
+                .tap_err(|reason| debug!("{reason:?}"))
+                .unwrap_or_else(|_| vec![steam_root])
+        })
+        .unique_by(|library| library.canonicalize().unwrap_or_else(|_| library.clone()))
+        .flat_map(|library| {
+            installed_apps(&library)
+                .tap_err(|reason| debug!("{reason:?}"))
+                .unwrap_or_default()
+        })
+        .filter(|(_, install_dir)| install_dir.is_dir())
+        .flat_map(|(manifest, install_dir)| {
+            Game::all()
+                .filter(move |game| game.meta().steam_ids.contains(&manifest.app_id))
+                .map(move |game| LocatedGame {
+                    game,
+                    root_directory: install_dir.clone(),
+                    source: "steam",
+                    edition: None,
+                })
+        })
+        .collect()
+}
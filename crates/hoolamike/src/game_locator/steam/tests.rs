@@ -0,0 +1,108 @@
+use {
+    super::*,
+    crate::{config_file::GamesConfig, game_locator::fill_missing_games},
+};
+
+#[test]
+fn test_parse_library_folders() -> Result<()> {
+    parse_library_folders(include_str!("test_data/libraryfolders.vdf")).map(|folders| {
+        assert_eq!(
+            folders,
+            [
+                LibraryFolder {
+                    path: "/home/deck/.local/share/Steam".into(),
+                    apps: vec![228980, 1070560, 1391110],
+                },
+                LibraryFolder {
+                    path: "/run/media/mmcblk0p1/SteamLibrary".into(),
+                    apps: vec![22380, 489830],
+                },
+            ]
+        )
+    })
+}
+
+#[test]
+fn test_parse_app_manifest() -> Result<()> {
+    parse_app_manifest(include_str!("test_data/appmanifest_489830.acf")).map(|manifest| {
+        assert_eq!(
+            manifest,
+            AppManifest {
+                app_id: 489830,
+                name: "The Elder Scrolls V: Skyrim Special Edition".to_string(),
+                install_dir: "Skyrim Special Edition".to_string(),
+            }
+        )
+    })
+}
+
+#[test]
+fn test_vdf_escapes_and_comments() -> Result<()> {
+    vdf::parse("// comment\n\"root\" { \"path\" \"C:\\\\Games\\\\Steam\" \"quote\" \"say \\\"hi\\\"\" }").map(|root| {
+        let root = root.get("root").unwrap();
+        assert_eq!(root.get("path").and_then(VdfValue::as_str), Some("C:\\Games\\Steam"));
+        assert_eq!(root.get("quote").and_then(VdfValue::as_str), Some("say \"hi\""));
+    })
+}
+
+#[test]
+fn test_unterminated_vdf_is_an_error() {
+    assert!(vdf::parse("\"libraryfolders\" { \"0\" { \"path\" \"/\" }").is_err());
+}
+
+/// builds a flatpak steam installation with an additional library in a temporary home directory
+#[test]
+fn test_locate_games_in_flatpak_steam() -> Result<()> {
+    let home = tempfile::tempdir()?;
+    let steam_root = home
+        .path()
+        .join(".var/app/com.valvesoftware.Steam/.local/share/Steam");
+    let library = home.path().join("SteamLibrary");
+    let write = |path: PathBuf, contents: String| {
+        path.parent()
+            .context("no parent")
+            .and_then(|parent| std::fs::create_dir_all(parent).context("creating directories"))
+            .and_then(|_| std::fs::write(&path, contents).context("writing"))
+    };
+    write(
+        steam_root.join("steamapps/libraryfolders.vdf"),
+        include_str!("test_data/libraryfolders.vdf")
+            .replace("/home/deck/.local/share/Steam", &steam_root.display().to_string())
+            .replace("/run/media/mmcblk0p1/SteamLibrary", &library.display().to_string()),
+    )?;
+    write(
+        library.join("steamapps/appmanifest_489830.acf"),
+        include_str!("test_data/appmanifest_489830.acf").to_string(),
+    )?;
+    std::fs::create_dir_all(library.join("steamapps/common/Skyrim Special Edition"))?;
+
+    assert_eq!(
+        locate_games(home.path()),
+        [LocatedGame {
+            game: Game::SkyrimSpecialEdition,
+            root_directory: library.join("steamapps/common/Skyrim Special Edition"),
+            source: "steam",
+            edition: None,
+        }]
+    );
+
+    let explicit = home.path().join("my/own/skyrim");
+    let located = || locate_games(home.path());
+    fill_missing_games(GamesConfig::new(), [Game::SkyrimSpecialEdition.game_name()], located)
+        .get(&Game::SkyrimSpecialEdition.game_name())
+        .context("game should be detected")
+        .map(|game| assert_eq!(game.root_directory, library.join("steamapps/common/Skyrim Special Edition")))?;
+    fill_missing_games(
+        GamesConfig::from_iter([(
+            Game::SkyrimSpecialEdition.game_name(),
+            crate::config_file::GameConfig {
+                root_directory: explicit.clone(),
+            },
+        )]),
+        [Game::SkyrimSpecialEdition.game_name()],
+        located,
+    )
+    .get(&Game::SkyrimSpecialEdition.game_name())
+    .context("game should stay configured")
+    .map(|game| assert_eq!(game.root_directory, explicit))
+}
@@ -0,0 +1,119 @@
+//! valve KeyValues text format, used by libraryfolders.vdf and appmanifest_*.acf
+use {
+    anyhow::{Context, Result},
+    std::{iter::Peekable, str::Chars},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfValue {
+    String(String),
+    /// keys are not guaranteed to be unique, so the order is preserved
+    Object(Vec<(String, VdfValue)>),
+}
+
+impl VdfValue {
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        match self {
+            VdfValue::String(_) => None,
+            VdfValue::Object(entries) => entries
+                .iter()
+                .find(|(entry, _)| entry.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(value) => Some(value),
+            VdfValue::Object(_) => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, VdfValue)] {
+        match self {
+            VdfValue::String(_) => &[],
+            VdfValue::Object(entries) => entries,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    String(String),
+    Open,
+    Close,
+}
+
+fn quoted(chars: &mut Peekable<Chars>) -> Result<String> {
+    let mut value = String::new();
+    loop {
+        match chars.next().context("unterminated string")? {
+            '"' => return Ok(value),
+            '\\' => match chars.next().context("unterminated escape sequence")? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                other => value.push(other),
+            },
+            other => value.push(other),
+        }
+    }
+}
+
+fn tokenize(contents: &str) -> Result<Vec<Token>> {
+    let mut chars = contents.chars().peekable();
+    let mut tokens = vec![];
+    while let Some(next) = chars.next() {
+        match next {
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '"' => tokens.push(quoted(&mut chars).map(Token::String)?),
+            '/' if chars.peek() == Some(&'/') => {
+                chars
+                    .by_ref()
+                    .take_while(|next| *next != '\n')
+                    .for_each(drop);
+            }
+            whitespace if whitespace.is_whitespace() => {}
+            // unquoted tokens are allowed as well
+            other => {
+                let mut value = other.to_string();
+                while let Some(next) = chars.next_if(|next| !next.is_whitespace() && !['{', '}', '"'].contains(next)) {
+                    value.push(next);
+                }
+                tokens.push(Token::String(value));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn object(tokens: &mut std::vec::IntoIter<Token>, nested: bool) -> Result<Vec<(String, VdfValue)>> {
+    let mut entries = vec![];
+    loop {
+        match tokens.next() {
+            None if nested => anyhow::bail!("unexpected end of file, expected '}}'"),
+            None => return Ok(entries),
+            Some(Token::Close) if nested => return Ok(entries),
+            Some(Token::Close) => anyhow::bail!("unexpected '}}'"),
+            Some(Token::Open) => anyhow::bail!("unexpected '{{', expected a key"),
+            Some(Token::String(key)) => match tokens
+                .next()
+                .with_context(|| format!("no value for [{key}]"))?
+            {
+                Token::String(value) => entries.push((key, VdfValue::String(value))),
+                Token::Open => object(tokens, true)
+                    .with_context(|| format!("parsing [{key}]"))
+                    .map(|value| entries.push((key, VdfValue::Object(value))))?,
+                Token::Close => anyhow::bail!("unexpected '}}', expected a value for [{key}]"),
+            },
+        }
+    }
+}
+
+/// the whole file is treated as an object, so the root key ("libraryfolders", "AppState") has to be looked up
+pub fn parse(contents: &str) -> Result<VdfValue> {
+    tokenize(contents)
+        .and_then(|tokens| object(&mut tokens.into_iter(), false))
+        .map(VdfValue::Object)
+        .context("parsing vdf")
+}
@@ -0,0 +1,286 @@
+//! every game wabbajack knows about
+//! BASED ON https://github.com/wabbajack-tools/wabbajack/blob/main/Wabbajack.DTOs/Game/GameRegistry.cs
+use {
+    crate::modlist_json::{GameName, NexusGameName, SpecialGameName},
+    anyhow::{Context, Result},
+    std::path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Game {
+    Morrowind,
+    Oblivion,
+    Fallout3,
+    FalloutNewVegas,
+    Skyrim,
+    Enderal,
+    SkyrimSpecialEdition,
+    EnderalSpecialEdition,
+    SkyrimVR,
+    Fallout4,
+    Fallout4VR,
+    Starfield,
+    DarkestDungeon,
+    Dishonored,
+    Witcher3,
+    StardewValley,
+    KingdomComeDeliverance,
+    MechWarrior5Mercenaries,
+    NoMansSky,
+    DragonAgeOrigins,
+    DragonAge2,
+    DragonAgeInquisition,
+    KerbalSpaceProgram,
+    Terraria,
+    Cyberpunk2077,
+    Sims4,
+    DragonsDogma,
+    KarrynsPrison,
+    Valheim,
+    BaldursGate3,
+    /// not a game - nexus hosts general modding tools under the "site" domain
+    ModdingTools,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GameMeta {
+    pub game: Game,
+    /// name used in modlists
+    pub wabbajack_name: &'static str,
+    pub human_name: &'static str,
+    pub nexus_domain: &'static str,
+    pub steam_ids: &'static [u32],
+    pub gog_ids: &'static [u64],
+    /// relative to the game root
+    pub main_executable: Option<&'static str>,
+    /// relative to the game root
+    pub data_folder: Option<&'static str>,
+    /// ini files kept in the profile (or "My Games") directory
+    pub ini_files: &'static [&'static str],
+    /// the ini holding display settings
+    pub prefs_ini: Option<&'static str>,
+}
+
+macro_rules! game {
+    (
+        $game:ident,
+        $human_name:literal,
+        nexus: $nexus_domain:literal,
+        steam: [$($steam_id:literal),*],
+        gog: [$($gog_id:literal),*],
+        exe: $main_executable:expr,
+        data: $data_folder:expr,
+        ini: [$($ini_file:literal),*],
+        prefs: $prefs_ini:expr $(,)?
+    ) => {
+        GameMeta {
+            game: Game::$game,
+            wabbajack_name: stringify!($game),
+            human_name: $human_name,
+            nexus_domain: $nexus_domain,
+            steam_ids: &[$($steam_id),*],
+            gog_ids: &[$($gog_id),*],
+            main_executable: $main_executable,
+            data_folder: $data_folder,
+            ini_files: &[$($ini_file),*],
+            prefs_ini: $prefs_ini,
+        }
+    };
+}
+
+pub static GAMES: &[GameMeta] = &[
+    game!(Morrowind, "Morrowind", nexus: "morrowind", steam: [22320], gog: [1440163901, 1435828767],
+        exe: Some("Morrowind.exe"), data: Some("Data Files"), ini: ["Morrowind.ini"], prefs: None),
+    game!(Oblivion, "Oblivion", nexus: "oblivion", steam: [22330], gog: [1458058109],
+        exe: Some("Oblivion.exe"), data: Some("Data"), ini: ["Oblivion.ini"], prefs: Some("Oblivion.ini")),
+    game!(Fallout3, "Fallout 3", nexus: "fallout3", steam: [22300, 22370], gog: [1454315831],
+        exe: Some("Fallout3.exe"), data: Some("Data"), ini: ["Fallout.ini", "FalloutPrefs.ini"], prefs: Some("FalloutPrefs.ini")),
+    game!(FalloutNewVegas, "Fallout New Vegas", nexus: "newvegas", steam: [22380, 22490], gog: [1454587428],
+        exe: Some("FalloutNV.exe"), data: Some("Data"), ini: ["Fallout.ini", "FalloutPrefs.ini"], prefs: Some("FalloutPrefs.ini")),
+    game!(Skyrim, "Skyrim Legendary Edition", nexus: "skyrim", steam: [72850], gog: [],
+        exe: Some("TESV.exe"), data: Some("Data"), ini: ["Skyrim.ini", "SkyrimPrefs.ini"], prefs: Some("SkyrimPrefs.ini")),
+    game!(Enderal, "Enderal", nexus: "enderal", steam: [933480], gog: [],
+        exe: Some("TESV.exe"), data: Some("Data"), ini: ["Enderal.ini", "EnderalPrefs.ini"], prefs: Some("EnderalPrefs.ini")),
+    game!(SkyrimSpecialEdition, "Skyrim Special Edition", nexus: "skyrimspecialedition", steam: [489830], gog: [1711230643],
+        exe: Some("SkyrimSE.exe"), data: Some("Data"), ini: ["Skyrim.ini", "SkyrimPrefs.ini", "SkyrimCustom.ini"], prefs: Some("SkyrimPrefs.ini")),
+    game!(EnderalSpecialEdition, "Enderal Special Edition", nexus: "enderalspecialedition", steam: [976620], gog: [],
+        exe: Some("SkyrimSE.exe"), data: Some("Data"), ini: ["Enderal.ini", "EnderalPrefs.ini"], prefs: Some("EnderalPrefs.ini")),
+    game!(SkyrimVR, "Skyrim VR", nexus: "skyrimspecialedition", steam: [611670], gog: [],
+        exe: Some("SkyrimVR.exe"), data: Some("Data"), ini: ["SkyrimVR.ini", "SkyrimPrefs.ini"], prefs: Some("SkyrimPrefs.ini")),
+    game!(Fallout4, "Fallout 4", nexus: "fallout4", steam: [377160], gog: [1998527297],
+        exe: Some("Fallout4.exe"), data: Some("Data"), ini: ["Fallout4.ini", "Fallout4Prefs.ini", "Fallout4Custom.ini"], prefs: Some("Fallout4Prefs.ini")),
+    game!(Fallout4VR, "Fallout 4 VR", nexus: "fallout4", steam: [611660], gog: [],
+        exe: Some("Fallout4VR.exe"), data: Some("Data"), ini: ["Fallout4.ini", "Fallout4Prefs.ini", "Fallout4Custom.ini"], prefs: Some("Fallout4Prefs.ini")),
+    game!(Starfield, "Starfield", nexus: "starfield", steam: [1716740], gog: [],
+        exe: Some("Starfield.exe"), data: Some("Data"), ini: ["StarfieldCustom.ini", "StarfieldPrefs.ini"], prefs: Some("StarfieldPrefs.ini")),
+    game!(DarkestDungeon, "Darkest Dungeon", nexus: "darkestdungeon", steam: [262060], gog: [1450711444],
+        exe: Some("_windowsnosteam/Darkest.exe"), data: None, ini: [], prefs: None),
+    game!(Dishonored, "Dishonored", nexus: "dishonored", steam: [205100], gog: [],
+        exe: Some("Binaries/Win32/Dishonored.exe"), data: None, ini: [], prefs: None),
+    game!(Witcher3, "The Witcher 3: Wild Hunt", nexus: "witcher3", steam: [292030, 499450], gog: [1207664643, 1495134320, 1207664663, 1640424747],
+        exe: Some("bin/x64/witcher3.exe"), data: None, ini: [], prefs: None),
+    game!(StardewValley, "Stardew Valley", nexus: "stardewvalley", steam: [413150], gog: [1453375253],
+        exe: Some("Stardew Valley.exe"), data: None, ini: [], prefs: None),
+    game!(KingdomComeDeliverance, "Kingdom Come: Deliverance", nexus: "kingdomcomedeliverance", steam: [379430], gog: [1719198803],
+        exe: Some("bin/Win64/KingdomCome.exe"), data: None, ini: [], prefs: None),
+    game!(MechWarrior5Mercenaries, "MechWarrior 5: Mercenaries", nexus: "mechwarrior5mercenaries", steam: [784080], gog: [],
+        exe: Some("MW5Mercs/Binaries/Win64/MechWarrior-Win64-Shipping.exe"), data: None, ini: [], prefs: None),
+    game!(NoMansSky, "No Man's Sky", nexus: "nomanssky", steam: [275850], gog: [1446213994], exe: Some("Binaries/NMS.exe"), data: None, ini: [], prefs: None),
+    game!(DragonAgeOrigins, "Dragon Age: Origins", nexus: "dragonage", steam: [47810], gog: [1949616134],
+        exe: Some("bin_ship/daorigins.exe"), data: None, ini: [], prefs: None),
+    game!(DragonAge2, "Dragon Age 2", nexus: "dragonage2", steam: [1238040], gog: [], exe: Some("bin_ship/DragonAge2.exe"), data: None, ini: [], prefs: None),
+    game!(DragonAgeInquisition, "Dragon Age: Inquisition", nexus: "dragonageinquisition", steam: [1222690], gog: [],
+        exe: Some("DragonAgeInquisition.exe"), data: None, ini: [], prefs: None),
+    game!(KerbalSpaceProgram, "Kerbal Space Program", nexus: "kerbalspaceprogram", steam: [220200], gog: [1429864849],
+        exe: Some("KSP_x64.exe"), data: None, ini: [], prefs: None),
+    game!(Terraria, "Terraria", nexus: "terraria", steam: [105600], gog: [], exe: Some("Terraria.exe"), data: None, ini: [], prefs: None),
+    game!(Cyberpunk2077, "Cyberpunk 2077", nexus: "cyberpunk2077", steam: [1091500], gog: [2093619782, 1423049311],
+        exe: Some("bin/x64/Cyberpunk2077.exe"), data: None, ini: [], prefs: None),
+    game!(Sims4, "The Sims 4", nexus: "thesims4", steam: [1222670], gog: [], exe: Some("Game/Bin/TS4_x64.exe"), data: None, ini: [], prefs: None),
+    game!(DragonsDogma, "Dragon's Dogma: Dark Arisen", nexus: "dragonsdogma", steam: [367500], gog: [1242384383],
+        exe: Some("DDDA.exe"), data: None, ini: [], prefs: None),
+    game!(KarrynsPrison, "Karryn's Prison", nexus: "karrynsprison", steam: [1619750], gog: [], exe: Some("nw.exe"), data: None, ini: [], prefs: None),
+    game!(Valheim, "Valheim", nexus: "valheim", steam: [892970], gog: [], exe: Some("valheim.exe"), data: None, ini: [], prefs: None),
+    game!(BaldursGate3, "Baldur's Gate 3", nexus: "baldursgate3", steam: [1086940], gog: [1456460669],
+        exe: Some("bin/bg3.exe"), data: None, ini: [], prefs: None),
+    game!(ModdingTools, "Modding Tools", nexus: "site", steam: [], gog: [], exe: None, data: None, ini: [], prefs: None),
+];
+
+impl Game {
+    pub fn meta(self) -> &'static GameMeta {
+        GAMES
+            .iter()
+            .find(|meta| meta.game == self)
+            .expect("every game is registered")
+    }
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        GAMES.iter().map(|meta| meta.game)
+    }
+
+    pub fn from_wabbajack_name(name: &str) -> Option<Self> {
+        GAMES
+            .iter()
+            .find(|meta| meta.wabbajack_name.eq_ignore_ascii_case(name))
+            .map(|meta| meta.game)
+    }
+
+    pub fn game_name(self) -> GameName {
+        GameName::new(self.meta().wabbajack_name.to_string())
+    }
+
+    pub fn main_executable(self, root_directory: &Path) -> Option<PathBuf> {
+        self.meta()
+            .main_executable
+            .map(|executable| root_directory.join(executable))
+    }
+
+    pub fn data_folder(self, root_directory: &Path) -> Option<PathBuf> {
+        self.meta()
+            .data_folder
+            .map(|data_folder| root_directory.join(data_folder))
+    }
+}
+
+impl GameName {
+    pub fn game(&self) -> Result<Game> {
+        Game::from_wabbajack_name(&self.to_string()).with_context(|| format!("[{self}] is not a game known to hoolamike"))
+    }
+
+    /// names are compared through the registry, so that eg. `skyrimspecialedition` matches `SkyrimSpecialEdition`
+    pub fn is_same_game(&self, other: &GameName) -> bool {
+        self == other
+            || self
+                .game()
+                .ok()
+                .zip(other.game().ok())
+                .is_some_and(|(left, right)| left == right)
+    }
+}
+
+impl From<SpecialGameName> for Game {
+    fn from(special: SpecialGameName) -> Self {
+        match special {
+            SpecialGameName::ModdingTools => Game::ModdingTools,
+            SpecialGameName::FalloutNewVegas => Game::FalloutNewVegas,
+        }
+    }
+}
+
+impl NexusGameName {
+    pub fn game(&self) -> Result<Game> {
+        match self {
+            NexusGameName::Special(special) => Ok(special.clone().into()),
+            NexusGameName::GameName(game_name) => game_name.game(),
+        }
+    }
+
+    pub fn nexus_domain(&self) -> Result<&'static str> {
+        self.game().map(|game| game.meta().nexus_domain)
+    }
+
+    /// games missing from the registry are passed through lowercased, that's what most nexus domains look like
+    pub fn nexus_domain_or_guess(&self) -> String {
+        self.nexus_domain()
+            .map(str::to_owned)
+            .unwrap_or_else(|reason| {
+                let guess = self.to_string().to_lowercase();
+                tracing::warn!("{reason:#}, guessing nexus domain [{guess}]");
+                guess
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, itertools::Itertools};
+
+    #[test]
+    fn test_every_game_is_registered_once() {
+        assert_eq!(
+            GAMES
+                .iter()
+                .map(|meta| meta.wabbajack_name)
+                .unique()
+                .count(),
+            GAMES.len()
+        );
+        GAMES
+            .iter()
+            .for_each(|meta| assert_eq!(meta.game.to_string(), meta.wabbajack_name));
+    }
+
+    #[test]
+    fn test_nexus_domains() -> Result<()> {
+        [
+            (NexusGameName::Special(SpecialGameName::ModdingTools), "site"),
+            (NexusGameName::Special(SpecialGameName::FalloutNewVegas), "newvegas"),
+            (NexusGameName::GameName(GameName::new("SkyrimSpecialEdition".into())), "skyrimspecialedition"),
+            (NexusGameName::GameName(GameName::new("Fallout4".into())), "fallout4"),
+        ]
+        .into_iter()
+        .try_for_each(|(game_name, expected)| {
+            game_name
+                .nexus_domain()
+                .map(|domain| assert_eq!(domain, expected))
+        })
+    }
+
+    #[test]
+    fn test_game_name_comparison() {
+        assert!(GameName::new("skyrimspecialedition".into()).is_same_game(&Game::SkyrimSpecialEdition.game_name()));
+        assert!(!Game::Skyrim
+            .game_name()
+            .is_same_game(&Game::SkyrimSpecialEdition.game_name()));
+        assert!(GameName::new("SomeUnknownGame".into()).game().is_err());
+    }
+
+    #[test]
+    fn test_unknown_nexus_games_are_passed_through() {
+        assert_eq!(
+            NexusGameName::GameName(GameName::new("SomeUnknownGame".into())).nexus_domain_or_guess(),
+            "someunknowngame"
+        );
+        assert_eq!(NexusGameName::Special(SpecialGameName::FalloutNewVegas).nexus_domain_or_guess(), "newvegas");
+    }
+}
@@ -3,18 +3,20 @@ use {
         config_file::{HoolamikeConfig, InstallationConfig},
         downloaders::WithArchiveDescriptor,
         error::TotalResult,
-        modlist_json::{Archive, Modlist},
+        game_locator,
+        modlist_json::{unsupported::UnsupportedReport, Archive, GameName, Modlist, State},
         progress_bars_v2::io_progress_style,
-        utils::spawn_rayon,
+        shutdown,
+        utils::{remove_partial_files, spawn_rayon},
         wabbajack_file::WabbajackFile,
         DebugHelpers,
     },
     anyhow::Context,
-    directives::{DirectivesHandler, DirectivesHandlerConfig},
+    directives::{directive_store::DirectiveStore, DirectivesHandler, DirectivesHandlerConfig},
     downloads::Synchronizers,
     futures::{FutureExt, TryFutureExt, TryStreamExt},
     itertools::Itertools,
-    std::{future::ready, sync::Arc},
+    std::{collections::BTreeMap, future::ready, sync::Arc},
     tap::prelude::*,
     tracing::instrument,
     tracing_indicatif::span_ext::IndicatifSpanExt,
@@ -23,6 +25,25 @@ use {
 pub mod directives;
 pub mod download_cache;
 pub mod downloads;
+pub mod game_preflight;
+pub mod install_lock;
+pub mod run_directive;
+
+/// the game the modlist is made for, and games its files are copied from
+fn required_games(modlist: &Modlist) -> Vec<GameName> {
+    std::iter::once(modlist.game_type.clone())
+        .chain(
+            modlist
+                .archives
+                .iter()
+                .filter_map(|archive| match &archive.state {
+                    State::GameFileSource(state) => Some(state.game.clone()),
+                    _ => None,
+                }),
+        )
+        .unique()
+        .collect()
+}
 
 #[allow(clippy::needless_as_bytes)]
 #[instrument(skip_all)]
@@ -42,12 +63,13 @@ pub async fn install_modlist(
         start_from_directive,
         skip_kind,
         contains,
+        allow_unsupported,
     }: DebugHelpers,
 ) -> TotalResult<()> {
-    let synchronizers = Synchronizers::new(downloaders.clone(), games.clone())
-        .context("setting up downloaders")
+    // held until the installation is done, dropping them releases the locks
+    let _locks = install_lock::lock_directories([installation_path.as_path(), downloaders.downloads_directory.as_path()])
+        .context("another installation seems to be running on the same directories")
         .map_err(|e| vec![e])?;
-
     let (
         wabbajack_file_handle,
         WabbajackFile {
@@ -82,6 +104,64 @@ pub async fn install_modlist(
         })
         .map_err(|e| vec![e])?;
 
+    shutdown::listen()
+        .await
+        .context("listening for ctrl-c")
+        .map_err(|e| vec![e])?;
+    // left behind when a previous run was killed before it could clean up
+    remove_partial_files(&installation_path)
+        .context("removing partial files left behind by a previous run")
+        .map_err(|e| vec![e])?
+        .pipe(|removed| {
+            if removed > 0 {
+                tracing::info!(removed, "removed partial files left behind by a previous run");
+            }
+        });
+
+    let unsupported = UnsupportedReport::new(&modlist);
+    if !unsupported.is_empty() {
+        match allow_unsupported || unsupported.directives.is_empty() {
+            // archives only matter if they have to be downloaded, that's reported by the downloader
+            true => tracing::warn!("{unsupported}"),
+            false => {
+                return Err(vec![anyhow::anyhow!(
+                    "{unsupported}\n\nthis modlist was made with a newer version of wabbajack, update hoolamike or pass --allow-unsupported to skip these"
+                )])
+            }
+        }
+    } else if !unsupported.ignored_fields.is_empty() {
+        tracing::warn!("{}", unsupported.ignored_fields);
+    }
+
+    let games = game_locator::fill_missing_games(games, required_games(&modlist), game_locator::locate_installed_games);
+    let synchronizers = Synchronizers::new(downloaders.clone(), games.clone())
+        .context("setting up downloaders")
+        .map_err(|e| vec![e])?;
+
+    // wrong game versions are reported up front, instead of as hash mismatches while downloading.
+    // game files that were already copied into the downloads directory get verified with the rest of the downloads
+    if !skip_verify_and_downloads {
+        game_preflight::preflight(
+            &games,
+            modlist
+                .archives
+                .iter()
+                .filter(|archive| {
+                    synchronizers
+                        .cache
+                        .download_output_path(archive.descriptor.name.clone())
+                        .metadata()
+                        .map(|metadata| metadata.len() != archive.descriptor.size)
+                        .unwrap_or(true)
+                })
+                .filter_map(|archive| match &archive.state {
+                    State::GameFileSource(state) => Some(state.clone()),
+                    _ => None,
+                }),
+        )
+        .await;
+    }
+
     modlist
         .pipe(Ok)
         .pipe(ready)
@@ -99,7 +179,65 @@ pub async fn install_modlist(
                       version: _,
                       wabbajack_version: _,
                       website: _,
+                      extra: _,
                   }| {
+                let (archives, skipped_archives) = match allow_unsupported {
+                    true => archives
+                        .into_iter()
+                        .partition::<Vec<_>, _>(|archive| match &archive.state {
+                            State::Unsupported(_) => synchronizers
+                                .cache
+                                .download_output_path(archive.descriptor.name.clone())
+                                .exists()
+                                .tap(|exists| {
+                                    if !exists {
+                                        tracing::warn!(name = %archive.descriptor.name, "skipping archive with an unsupported download source")
+                                    }
+                                }),
+                            _ => true,
+                        }),
+                    false => (archives, vec![]),
+                };
+                // directives reading from a skipped archive would only fail once the archive can't be found
+                let mut skipped_archives = skipped_archives
+                    .into_iter()
+                    .map(|archive| (archive.descriptor.hash, (archive.descriptor.name, 0usize)))
+                    .collect::<BTreeMap<_, _>>();
+                let directives = directives.into_iter().filter(|directive| {
+                    match directive
+                        .archive_hash_path()
+                        .and_then(|archive_hash_path| skipped_archives.get_mut(&archive_hash_path.source_hash))
+                    {
+                        Some((_, skipped)) => {
+                            *skipped += 1;
+                            false
+                        }
+                        None => true,
+                    }
+                });
+                // matched while the directives are still as they were parsed, the store drops the fields it doesn't need
+                let mut started = start_from_directive.is_none();
+                let (store, selected) = DirectiveStore::from_directives_selecting(directives, |directive| {
+                    started = started
+                        || start_from_directive
+                            .as_ref()
+                            .is_some_and(|start_from_directive| &directive.directive_hash() == start_from_directive);
+                    started
+                        && (contains.is_empty()
+                            || serde_json::to_string(directive)
+                                .tap_err(|e| tracing::error!("{e:#?}"))
+                                .map(|directive| contains.iter().all(|contains| directive.contains(contains)))
+                                .unwrap_or(false))
+                });
+                // the modlist representation is dropped directive by directive while this is built
+                let store = store.pipe(Arc::new);
+                let missing_start = start_from_directive
+                    .filter(|_| !started)
+                    .map(|start_from_directive| anyhow::anyhow!("no directive with hash [{start_from_directive}] in this modlist"));
+                skipped_archives
+                    .values()
+                    .filter(|(_, skipped)| *skipped > 0)
+                    .for_each(|(name, skipped)| tracing::warn!(%name, %skipped, "skipping directives that read from an archive that was skipped"));
                 // let archives: Vec<_> = archives
                 //     .into_iter()
                 //     .filter(|archive| {
@@ -109,8 +247,9 @@ pub async fn install_modlist(
                 //             .unwrap_or(false)
                 //     })
                 //     .collect();
-                match skip_verify_and_downloads {
-                    true => archives
+                match (missing_start, skip_verify_and_downloads) {
+                    (Some(missing_start), _) => Err(vec![missing_start]).pipe(ready).boxed_local(),
+                    (None, true) => archives
                         .into_iter()
                         .map(|Archive { descriptor, state: _ }| WithArchiveDescriptor {
                             inner: synchronizers
@@ -122,8 +261,14 @@ pub async fn install_modlist(
                         .pipe(Ok)
                         .pipe(ready)
                         .boxed_local(),
-                    false => synchronizers.clone().sync_downloads(archives).boxed_local(),
+                    (None, false) => synchronizers.clone().sync_downloads(archives).boxed_local(),
                 }
+                .and_then(|summary| {
+                    shutdown::check()
+                        .map(|_| summary)
+                        .map_err(|e| vec![e.into()])
+                        .pipe(ready)
+                })
                 .and_then({
                     move |summary| {
                         tracing::Span::current().pb_inc(summary.iter().map(|d| d.descriptor.size).sum());
@@ -137,6 +282,7 @@ pub async fn install_modlist(
                                         output_directory: installation_path,
                                         game_directory: game_config.root_directory.clone(),
                                         downloads_directory: downloaders.downloads_directory.clone(),
+                                        keep_abandoned_outputs: false,
                                     },
                                     summary,
                                 )
@@ -147,30 +293,17 @@ pub async fn install_modlist(
                 })
                 .map_ok(Arc::new)
                 .and_then(move |directives_handler| {
+                    let selected = selected
+                        .into_iter()
+                        .filter(|id| !skip_kind.contains(&store.kind(*id)))
+                        .collect_vec();
                     directives_handler
-                        .handle_directives(directives.tap_mut(|directives| {
-                            *directives = directives
-                                .pipe(std::mem::take)
-                                .drain(..)
-                                .skip_while(|d| {
-                                    start_from_directive
-                                        .as_ref()
-                                        .map(|start_from_directive| &d.directive_hash() != start_from_directive)
-                                        .unwrap_or(false)
-                                })
-                                .filter(|directive| !skip_kind.contains(&directive.directive_kind()))
-                                .filter(|directive| {
-                                    serde_json::to_string(&directive)
-                                        .tap_err(|e| tracing::error!("{e:#?}"))
-                                        .map(|directive| contains.iter().all(|contains| directive.contains(contains)))
-                                        .unwrap_or(false)
-                                })
-                                .collect_vec();
-                        }))
+                        .handle_directives(store, selected)
                         .map_ok(|size| tracing::Span::current().pb_inc(size))
                         .try_collect::<Vec<_>>()
                         .map(|res| match res {
-                            Ok(out) => Ok(out),
+                            // directives that were not scheduled yet are just missing, not failed
+                            Ok(out) => shutdown::check().map(|_| out).map_err(|e| vec![e.into()]),
                             Err(e) => Err(vec![e]),
                         })
                 })
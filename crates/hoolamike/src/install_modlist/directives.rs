@@ -3,22 +3,16 @@ use {
         downloaders::{helpers::FutureAnyhowExt, WithArchiveDescriptor},
         install_modlist::{download_cache::validate_hash, io_progress_style},
         modlist_json::{
-            directive::{
-                create_bsa_directive::{CreateBSADirective, CreateBSADirectiveKind},
-                ArchiveHashPath,
-                FromArchiveDirective,
-                InlineFileDirective,
-                PatchedFromArchiveDirective,
-                RemappedInlineFileDirective,
-                TransformedTextureDirective,
-            },
+            directive::{ArchiveHashPath, FromArchiveDirective, PatchedFromArchiveDirective, TransformedTextureDirective},
             DirectiveKind,
         },
+        path_resolver,
         progress_bars_v2::count_progress_style,
-        utils::{MaybeWindowsPath, PathReadWrite},
+        shutdown,
+        utils::{AtomicFile, MaybeWindowsPath, PathReadWrite},
     },
     anyhow::{Context, Result},
-    futures::{FutureExt, Stream, StreamExt, TryStreamExt},
+    futures::{FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt},
     itertools::Itertools,
     nonempty::NonEmpty,
     remapped_inline_file::RemappingContext,
@@ -35,12 +29,9 @@ use {
     wabbajack_file_handle::WabbajackFileHandle,
 };
 
-pub(crate) fn create_file_all(path: &Path) -> Result<std::fs::File> {
-    path.parent()
-        .map(|parent| std::fs::create_dir_all(parent).with_context(|| format!("creating directory for [{}]", parent.display())))
-        .unwrap_or_else(|| Ok(()))
-        .and_then(|_| path.open_file_write())
-        .map(|(_, f)| f)
+/// the output only shows up at `path` after [AtomicFile::commit], which handlers call once the contents were validated
+pub(crate) fn create_file_all(path: &Path, keep_abandoned: bool) -> Result<AtomicFile> {
+    AtomicFile::create(path).map(|file| file.keep_abandoned(keep_abandoned))
 }
 
 pub type DownloadSummary = Arc<BTreeMap<String, WithArchiveDescriptor<PathBuf>>>;
@@ -54,6 +45,9 @@ pub mod transformed_texture;
 
 use crate::modlist_json::Directive;
 
+pub mod directive_store;
+use directive_store::{DirectiveId, DirectiveStore};
+
 // pub type WabbajackFileHandle = Arc<Mutex<crate::compression::compress_tools::ArchiveHandle>>;
 
 pub mod wabbajack_file_handle;
@@ -75,6 +69,8 @@ pub struct DirectivesHandlerConfig {
     pub output_directory: PathBuf,
     pub game_directory: PathBuf,
     pub downloads_directory: PathBuf,
+    /// outputs that fail validation are kept for inspection instead of being removed
+    pub keep_abandoned_outputs: bool,
 }
 
 pub mod nested_archive_manager;
@@ -232,6 +228,7 @@ impl DirectivesHandler {
             output_directory,
             game_directory,
             downloads_directory,
+            keep_abandoned_outputs,
         } = config.clone();
         let download_summary: DownloadSummary = sync_summary
             .into_iter()
@@ -243,17 +240,21 @@ impl DirectivesHandler {
             config,
             create_bsa: create_bsa::CreateBSAHandler {
                 output_directory: output_directory.clone(),
+                keep_abandoned_outputs,
             },
             from_archive: from_archive::FromArchiveHandler {
                 output_directory: output_directory.clone(),
+                keep_abandoned_outputs,
                 download_summary: download_summary.clone(),
             },
             inline_file: inline_file::InlineFileHandler {
                 wabbajack_file: wabbajack_file.clone(),
                 output_directory: output_directory.clone(),
+                keep_abandoned_outputs,
             },
             patched_from_archive: patched_from_archive::PatchedFromArchiveHandler {
                 output_directory: output_directory.clone(),
+                keep_abandoned_outputs,
                 wabbajack_file: wabbajack_file.clone(),
                 download_summary: download_summary.clone(),
             },
@@ -264,67 +265,46 @@ impl DirectivesHandler {
                     downloads_directory,
                 }),
                 wabbajack_file: wabbajack_file.clone(),
+                keep_abandoned_outputs,
             },
             transformed_texture: transformed_texture::TransformedTextureHandler {
                 output_directory: output_directory.clone(),
+                keep_abandoned_outputs,
                 download_summary: download_summary.clone(),
             },
             download_summary,
         }
     }
 
-    #[allow(clippy::unnecessary_literal_unwrap)]
     #[instrument(skip_all, fields(directives=%directives.len()))]
-    pub fn handle_directives(self: Arc<Self>, directives: Vec<Directive>) -> impl Stream<Item = Result<u64>> {
+    pub fn handle_directives(self: Arc<Self>, store: Arc<DirectiveStore>, directives: Vec<DirectiveId>) -> impl Stream<Item = Result<u64>> {
         let handle_directives: &'static _ = tracing::Span::current()
             .tap(|pb| {
-                pb.pb_set_length(directives.iter().map(directive_size).sum());
+                pb.pb_set_length(directives.iter().map(|id| store.size(*id)).sum());
                 pb.pb_set_style(&io_progress_style());
             })
             .pipe(Box::new)
             .pipe(Box::leak);
 
-        fn directive_size(d: &Directive) -> u64 {
-            match d {
-                Directive::CreateBSA(directive) => directive.size(),
-                Directive::FromArchive(directive) => directive.size,
-                Directive::InlineFile(directive) => directive.size,
-                Directive::PatchedFromArchive(directive) => directive.size,
-                Directive::RemappedInlineFile(directive) => directive.size,
-                Directive::TransformedTexture(directive) => directive.size,
-            }
-        }
         let manager = self.clone();
 
         enum DirectiveStatus {
             Completed(u64),
-            NeedsRebuild { reason: anyhow::Error, directive: Directive },
+            NeedsRebuild { reason: anyhow::Error, id: DirectiveId },
         }
 
         let check_completed = {
             let output_directory = self.from_archive.output_directory.clone();
-            move |directive: Directive| {
-                let _kind = DirectiveKind::from(&directive);
-                match &directive {
-                    Directive::CreateBSA(create_bsa) => match create_bsa {
-                        CreateBSADirective::Bsa(CreateBSADirectiveKind { hash, size, to, .. }) => (hash.clone(), *size, to.clone()),
-                        CreateBSADirective::Ba2(CreateBSADirectiveKind { hash, size, to, .. }) => (hash.clone(), *size, to.clone()),
-                    },
-                    Directive::FromArchive(FromArchiveDirective { hash, size, to, .. }) => (hash.clone(), *size, to.clone()),
-                    Directive::InlineFile(InlineFileDirective { hash, size, to, .. }) => (hash.clone(), *size, to.clone()),
-                    Directive::PatchedFromArchive(PatchedFromArchiveDirective { hash, size, to, .. }) => (hash.clone(), *size, to.clone()),
-                    Directive::RemappedInlineFile(RemappedInlineFileDirective { hash, size, to, .. }) => (hash.clone(), *size, to.clone()),
-                    Directive::TransformedTexture(TransformedTextureDirective { hash, size, to, .. }) => (hash.clone(), *size, to.clone()),
-                }
-                .pipe(|(hash, size, to)| (hash, size, output_directory.join(to.into_path())))
-                .pipe(move |(hash, size, to)| {
-                    validate_hash_with_overrides(to.clone(), hash, size)
-                        .map(move |res| match res {
-                            Ok(_) => DirectiveStatus::Completed(size),
-                            Err(reason) => DirectiveStatus::NeedsRebuild { reason, directive },
-                        })
-                        .instrument(handle_directives.clone())
-                })
+            cloned![store];
+            move |id: DirectiveId| {
+                let (hash, size) = (store.expected_hash(id), store.size(id));
+                let to = path_resolver::existing_path(&output_directory, &store.to(id).into_path());
+                validate_hash_with_overrides(to, hash, size)
+                    .map(move |res| match res {
+                        Ok(_) => DirectiveStatus::Completed(size),
+                        Err(reason) => DirectiveStatus::NeedsRebuild { reason, id },
+                    })
+                    .instrument(handle_directives.clone())
             }
         };
         {
@@ -343,94 +323,94 @@ impl DirectivesHandler {
                 .collect::<Vec<_>>()
                 .instrument(validating_hashes)
         }
-        .then(|directives| {
-            (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new())
-                .pipe(
-                    |(
-                        mut create_bsa,
-                        mut from_archive,
-                        mut inline_file,
-                        mut patched_from_archive,
-                        mut remapped_inline_file,
-                        mut transformed_texture,
-                        mut completed,
-                    )| {
-                        directives
-                            .into_iter()
-                            .for_each(|directive| match directive {
-                                DirectiveStatus::Completed(size) => completed.push(size),
-                                DirectiveStatus::NeedsRebuild { reason, directive } => {
-                                    tracing::debug!(
-                                        "recomputing directive\ndirective:{directive}:\nreason:{reason:?}",
-                                        directive = format!("{directive:#?}")
-                                            .chars()
-                                            .take(256)
-                                            .collect::<String>(),
-                                    );
-                                    match directive {
-                                        Directive::CreateBSA(create_bsadirective) => create_bsa.push(create_bsadirective),
-                                        Directive::FromArchive(from_archive_directive) => from_archive.push(from_archive_directive),
-                                        Directive::InlineFile(inline_file_directive) => inline_file.push(inline_file_directive),
-                                        Directive::PatchedFromArchive(patched_from_archive_directive) => {
-                                            patched_from_archive.push(patched_from_archive_directive)
-                                        }
-                                        Directive::RemappedInlineFile(remapped_inline_file_directive) => {
-                                            remapped_inline_file.push(remapped_inline_file_directive)
+        .then({
+            cloned![store];
+            move |directives| {
+                (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new())
+                    .pipe(
+                        |(
+                            mut create_bsa,
+                            mut from_archive,
+                            mut inline_file,
+                            mut patched_from_archive,
+                            mut remapped_inline_file,
+                            mut transformed_texture,
+                            mut completed,
+                        )| {
+                            directives
+                                .into_iter()
+                                .for_each(|directive| match directive {
+                                    DirectiveStatus::Completed(size) => completed.push(Ok(size)),
+                                    DirectiveStatus::NeedsRebuild { reason, id } => {
+                                        tracing::debug!(
+                                            "recomputing directive\ndirective:{kind} [{to}]:\nreason:{reason:?}",
+                                            kind = store.kind(id),
+                                            to = store.to(id).0,
+                                        );
+                                        match store.kind(id) {
+                                            DirectiveKind::CreateBSA => create_bsa.push(id),
+                                            DirectiveKind::FromArchive => from_archive.push(id),
+                                            DirectiveKind::InlineFile => inline_file.push(id),
+                                            DirectiveKind::PatchedFromArchive => patched_from_archive.push(id),
+                                            DirectiveKind::RemappedInlineFile => remapped_inline_file.push(id),
+                                            DirectiveKind::TransformedTexture => transformed_texture.push(id),
+                                            DirectiveKind::Unsupported => completed.push(Err(anyhow::anyhow!("unsupported directives are never stored"))),
                                         }
-                                        Directive::TransformedTexture(transformed_texture_directive) => transformed_texture.push(transformed_texture_directive),
                                     }
-                                }
-                            })
-                            .pipe(|_| {
-                                (
-                                    create_bsa,
-                                    from_archive,
-                                    inline_file,
-                                    patched_from_archive,
-                                    remapped_inline_file,
-                                    transformed_texture,
-                                    completed,
-                                )
-                            })
-                    },
-                )
-                .pipe(ready)
+                                })
+                                .pipe(|_| {
+                                    (
+                                        create_bsa,
+                                        from_archive,
+                                        inline_file,
+                                        patched_from_archive,
+                                        remapped_inline_file,
+                                        transformed_texture,
+                                        completed,
+                                    )
+                                })
+                        },
+                    )
+                    .pipe(ready)
+            }
         })
         .into_stream()
         .flat_map(
             move |(create_bsa, from_archive, inline_file, patched_from_archive, remapped_inline_file, transformed_texture, completed)| {
                 futures::stream::empty()
-                    .chain(completed.pipe(futures::stream::iter).map(Ok))
+                    .chain(completed.pipe(futures::stream::iter))
                     .chain(
                         inline_file
                             .pipe(futures::stream::iter)
+                            .take_while(|_| ready(!shutdown::requested()))
                             .map({
-                                cloned![manager];
-                                move |directive| {
-                                    manager
-                                        .clone()
-                                        .inline_file
-                                        .clone()
-                                        .handle(directive.clone())
-                                        .instrument(handle_directives.clone())
-                                        .map(move |res| res.with_context(|| format!("handling directive [{directive:#?}]")))
+                                cloned![manager, store];
+                                move |id| {
+                                    match store.get(id) {
+                                        Directive::InlineFile(directive) => Ok(directive),
+                                        other => Err(anyhow::anyhow!("[{}] was sorted as an inline file", other.directive_kind())),
+                                    }
+                                    .pipe(ready)
+                                    .and_then({
+                                        cloned![manager];
+                                        move |directive| {
+                                            manager
+                                                .inline_file
+                                                .clone()
+                                                .handle(directive.clone())
+                                                .instrument(handle_directives.clone())
+                                                .map(move |res| res.with_context(|| format!("handling directive [{directive:#?}]")))
+                                        }
+                                    })
                                 }
                             })
                             .buffer_unordered(concurrency()),
                     )
                     .chain(
                         std::iter::empty()
-                            .chain(
-                                patched_from_archive
-                                    .into_iter()
-                                    .map(ArchivePathDirective::from),
-                            )
-                            .chain(from_archive.into_iter().map(ArchivePathDirective::from))
-                            .chain(
-                                transformed_texture
-                                    .into_iter()
-                                    .map(ArchivePathDirective::from),
-                            )
+                            .chain(patched_from_archive)
+                            .chain(from_archive)
+                            .chain(transformed_texture)
                             .collect_vec()
                             .pipe(|directives| {
                                 const DIRECTIVE_CHUNK_SIZE: u64 = 6 * 1024 * 1024 * 1024;
@@ -438,19 +418,36 @@ impl DirectivesHandler {
                                 info_span!("handling nested archive directives", total_size=%directives.len(), estimated_chunk_size_bytes=%DIRECTIVE_CHUNK_SIZE)
                                     .in_scope(|| {
                                         handle_directives.in_scope(|| {
-                                            crate::utils::chunk_while(directives, |d| d.iter().map(|d| d.directive_size()).sum::<u64>() > DIRECTIVE_CHUNK_SIZE)
+                                            crate::utils::chunk_while(directives, |d| d.iter().map(|id| store.size(*id)).sum::<u64>() > DIRECTIVE_CHUNK_SIZE)
                                                 .pipe(futures::stream::iter)
+                                                .take_while(|_| ready(!shutdown::requested()))
                                                 .flat_map({
-                                                    cloned![manager, download_summary];
+                                                    cloned![manager, download_summary, store];
                                                     move |directives| {
-                                                        info_span!("handling nested archive directives chunk", chunk_size=%directives.len()).in_scope(|| {
-                                                            nested_archive_directives::handle_nested_archive_directives(
-                                                                manager.clone(),
-                                                                download_summary.clone(),
-                                                                directives,
-                                                                concurrency(),
-                                                            )
-                                                        })
+                                                        // only the chunk that's being handled is ever expanded
+                                                        let (directives, missorted): (Vec<_>, Vec<_>) = directives
+                                                            .into_iter()
+                                                            .map(|id| match store.get(id) {
+                                                                Directive::FromArchive(directive) => Ok(ArchivePathDirective::from(directive)),
+                                                                Directive::PatchedFromArchive(directive) => Ok(ArchivePathDirective::from(directive)),
+                                                                Directive::TransformedTexture(directive) => Ok(ArchivePathDirective::from(directive)),
+                                                                other => {
+                                                                    Err(anyhow::anyhow!("[{}] was sorted as an archive path directive", other.directive_kind()))
+                                                                }
+                                                            })
+                                                            .partition_result();
+                                                        futures::stream::iter(missorted.into_iter().map(Err)).chain(
+                                                            info_span!("handling nested archive directives chunk", chunk_size=%directives.len()).in_scope(
+                                                                || {
+                                                                    nested_archive_directives::handle_nested_archive_directives(
+                                                                        manager.clone(),
+                                                                        download_summary.clone(),
+                                                                        directives,
+                                                                        concurrency(),
+                                                                    )
+                                                                },
+                                                            ),
+                                                        )
                                                     }
                                                 })
                                         })
@@ -460,34 +457,59 @@ impl DirectivesHandler {
                     .chain(
                         remapped_inline_file
                             .pipe(futures::stream::iter)
+                            .take_while(|_| ready(!shutdown::requested()))
                             .map({
-                                cloned![manager];
-                                move |remapped_inline_file| {
-                                    manager
-                                        .remapped_inline_file
-                                        .clone()
-                                        .handle(remapped_inline_file.clone())
-                                        .instrument(handle_directives.clone())
-                                        .map(move |res| res.with_context(|| format!("handling {remapped_inline_file:#?}")))
+                                cloned![manager, store];
+                                move |id| {
+                                    match store.get(id) {
+                                        Directive::RemappedInlineFile(directive) => Ok(directive),
+                                        other => Err(anyhow::anyhow!("[{}] was sorted as a remapped inline file", other.directive_kind())),
+                                    }
+                                    .pipe(ready)
+                                    .and_then({
+                                        cloned![manager];
+                                        move |remapped_inline_file| {
+                                            manager
+                                                .remapped_inline_file
+                                                .clone()
+                                                .handle(remapped_inline_file.clone())
+                                                .instrument(handle_directives.clone())
+                                                .map(move |res| res.with_context(|| format!("handling {remapped_inline_file:#?}")))
+                                        }
+                                    })
                                 }
                             })
                             .buffer_unordered(concurrency()),
                     )
-                    .chain(create_bsa.pipe(futures::stream::iter).then({
-                        cloned![manager];
-                        move |create_bsa| {
-                            let debug = format!("{create_bsa:#?}")
-                                .chars()
-                                .take(256)
-                                .collect::<String>();
-                            manager
-                                .create_bsa
-                                .clone()
-                                .handle(create_bsa)
-                                .instrument(handle_directives.clone())
-                                .map(move |res| res.with_context(|| format!("handling directive: [{debug}]")))
-                        }
-                    }))
+                    .chain(
+                        create_bsa
+                            .pipe(futures::stream::iter)
+                            .take_while(|_| ready(!shutdown::requested()))
+                            .then({
+                                cloned![manager, store];
+                                move |id| {
+                                    store
+                                        .create_bsa(id)
+                                        .with_context(|| format!("[{}] was sorted as a bsa", store.kind(id)))
+                                        .pipe(ready)
+                                        .and_then({
+                                            cloned![manager];
+                                            move |create_bsa| {
+                                                let debug = format!("{create_bsa:#?}")
+                                                    .chars()
+                                                    .take(256)
+                                                    .collect::<String>();
+                                                manager
+                                                    .create_bsa
+                                                    .clone()
+                                                    .handle(create_bsa)
+                                                    .instrument(handle_directives.clone())
+                                                    .map(move |res| res.with_context(|| format!("handling directive: [{debug}]")))
+                                            }
+                                        })
+                                }
+                            }),
+                    )
                     .inspect_ok({
                         move |size| {
                             handle_directives.pb_inc(*size);
@@ -11,6 +11,7 @@ use {
 #[derive(Clone, Debug)]
 pub struct CreateBSAHandler {
     pub output_directory: PathBuf,
+    pub keep_abandoned_outputs: bool,
 }
 
 pub mod fallout_4;
@@ -27,35 +28,45 @@ fn try_optimize_memory_mapping(memmap: &memmap2::Mmap) {
 }
 
 impl CreateBSAHandler {
+    /// the directive is shared with the directive store, bsa directives list every file in the archive
     #[tracing::instrument(skip(create_bsa_directive), level = "INFO")]
-    pub async fn handle(self, create_bsa_directive: CreateBSADirective) -> Result<u64> {
-        let Self { output_directory } = self;
+    pub async fn handle(self, create_bsa_directive: Arc<CreateBSADirective>) -> Result<u64> {
+        let Self {
+            output_directory,
+            keep_abandoned_outputs,
+        } = self;
         let size = create_bsa_directive.size();
         let span = tracing::Span::current();
         spawn_rayon(move || {
             span.in_scope(|| {
                 let bsa_creation_dir = output_directory.join(BSA_CREATION_DIR.with(|p| p.to_owned()));
-                match create_bsa_directive {
+                match create_bsa_directive.as_ref() {
                     CreateBSADirective::Ba2(ba2) => self::fallout_4::create_archive(bsa_creation_dir, ba2, |archive, options, output_path| {
-                        output_directory
-                            .join(output_path.into_path())
-                            .open_file_write()
+                        path_resolver::output_path(&output_directory, &output_path.into_path())
+                            .and_then(|output_path| create_file_all(&output_path, keep_abandoned_outputs).map(|output| (output_path, output)))
                             .context("opening file for writing")
-                            .and_then(|(output_path, output)| {
+                            .and_then(|(output_path, mut output)| {
                                 archive
-                                    .write(&mut tracing::Span::current().wrap_write(size, output), &options)
+                                    .write(&mut tracing::Span::current().wrap_write(size, &mut output), &options)
+                                    .context("writing archive")?;
+                                output
+                                    .commit()
                                     .with_context(|| format!("writing ba2 (fallout 4 / starfield) file to {output_path:?}"))
+                                    .map(|_| ())
                             })
                     }),
                     CreateBSADirective::Bsa(bsa) => self::tes_4::create_archive(bsa_creation_dir, bsa, |archive, options, output_path| {
-                        output_directory
-                            .join(output_path.into_path())
-                            .open_file_write()
+                        path_resolver::output_path(&output_directory, &output_path.into_path())
+                            .and_then(|output_path| create_file_all(&output_path, keep_abandoned_outputs).map(|output| (output_path, output)))
                             .context("opening file for writing")
-                            .and_then(|(output_path, output)| {
+                            .and_then(|(output_path, mut output)| {
                                 archive
-                                    .write(&mut tracing::Span::current().wrap_write(size, output), &options)
+                                    .write(&mut tracing::Span::current().wrap_write(size, &mut output), &options)
+                                    .context("writing archive")?;
+                                output
+                                    .commit()
                                     .with_context(|| format!("writing bsa file (skyrim and before) to {output_path:?}"))
+                                    .map(|_| ())
                             })
                     }),
                 }
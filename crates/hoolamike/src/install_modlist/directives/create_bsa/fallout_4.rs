@@ -6,6 +6,7 @@ use {
             type_guard::WithTypeGuard,
             BA2DX10EntryChunk,
         },
+        path_resolver,
         utils::MaybeWindowsPath,
     },
     anyhow::{Context, Result},
@@ -28,7 +29,7 @@ use {
         CompressionResult,
         ReaderWithOptions,
     },
-    rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    rayon::iter::{IntoParallelRefIterator, ParallelIterator},
     std::path::{Path, PathBuf},
     tap::prelude::*,
     tracing::{info_span, instrument},
@@ -158,13 +159,15 @@ pub fn create_archive<F: FnOnce(&Archive<'_>, ArchiveOptions, MaybeWindowsPath)
                         header_magic: _,
                         kind: _,
                         version,
+                        extra: _,
                     },
                 ..
             },
-    }: Ba2,
+        extra: _,
+    }: &Ba2,
     handle_archive: F,
 ) -> Result<()> {
-    let version: ArchiveVersion = match version {
+    let version: ArchiveVersion = match *version {
         1 => ArchiveVersion::v1,
         2 => ArchiveVersion::v2,
         3 => ArchiveVersion::v3,
@@ -180,22 +183,20 @@ pub fn create_archive<F: FnOnce(&Archive<'_>, ArchiveOptions, MaybeWindowsPath)
             pb.pb_set_length(file_states.len() as _);
         });
     file_states
-        .into_par_iter()
+        .par_iter()
         .map(move |file_state| match file_state {
-            FileState::BA2File(ba2_file_entry) => temp_id_dir
-                .join(ba2_file_entry.path.clone().into_path())
+            FileState::BA2File(ba2_file_entry) => path_resolver::existing_path(&temp_id_dir, &ba2_file_entry.path.clone().into_path())
                 .pipe(|path| path.open_file_read())
                 .and_then(|(_path, file)| LazyArchiveFile::new(&file, ba2_file_entry.clone()).map(LazyArchiveKind::from))
-                .and_then(|file| ba2_file_entry.pipe(|BA2FileEntry { path, .. }| create_key(path).map(|key| (key, file)))),
-            FileState::BA2DX10Entry(ba2_dx10_entry) => temp_id_dir
-                .join(ba2_dx10_entry.path.clone().into_path())
+                .and_then(|file| create_key(ba2_file_entry.path.clone()).map(|key| (key, file))),
+            FileState::BA2DX10Entry(ba2_dx10_entry) => path_resolver::existing_path(&temp_id_dir, &ba2_dx10_entry.path.clone().into_path())
                 .open_file_read()
                 .and_then(|(path, file)| {
                     LazyArchiveFile::new(&file, ba2_dx10_entry.clone())
                         .with_context(|| format!("opening file at [{path:?}]"))
                         .map(LazyArchiveKind::from)
                 })
-                .and_then(|file| ba2_dx10_entry.pipe(|BA2DX10Entry { path, .. }| create_key(path).map(|key| (key, file)))),
+                .and_then(|file| create_key(ba2_dx10_entry.path.clone()).map(|key| (key, file))),
         })
         .inspect(|_| reading_bsa_entries.pb_inc(1))
         .collect::<Result<Vec<_>>>()
@@ -232,8 +233,8 @@ pub fn create_archive<F: FnOnce(&Archive<'_>, ArchiveOptions, MaybeWindowsPath)
                                             acc.insert(key.clone(), file);
                                         })
                                     })
-                                    .pipe(|archive| (archive, options.version(version).strings(has_name_table).build()))
-                                    .pipe(|(archive, options)| handle_archive(&archive, options, to))
+                                    .pipe(|archive| (archive, options.version(version).strings(*has_name_table).build()))
+                                    .pipe(|(archive, options)| handle_archive(&archive, options, to.clone()))
                             })
                     })
                     .context("creating BA2 (fallout4/starfield) archive")
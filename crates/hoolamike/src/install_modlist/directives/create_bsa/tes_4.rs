@@ -5,6 +5,7 @@ use {
             directive::create_bsa_directive::bsa::{self, Bsa, DirectiveStateData, FileStateData},
             type_guard::WithTypeGuard,
         },
+        path_resolver,
         utils::MaybeWindowsPath,
     },
     anyhow::{Context, Result},
@@ -14,7 +15,7 @@ use {
         CompressionResult,
         ReaderWithOptions,
     },
-    rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    rayon::iter::{IntoParallelRefIterator, ParallelIterator},
     std::{ffi::OsStr, path::PathBuf},
     tap::prelude::*,
     tracing::{debug, info_span, instrument},
@@ -50,6 +51,7 @@ impl LazyArchiveFile<FileStateData> {
                  flip_compression: _,
                  index: _,
                  path: _,
+                 extra: _,
              }| {
                 File::read(
                     Borrowed(self.as_bytes()),
@@ -134,21 +136,23 @@ pub fn create_archive<F: FnOnce(&Archive<'_>, ArchiveOptions, MaybeWindowsPath)
                         file_flags,
                         magic: _,
                         version,
+                        extra: _,
                     },
                 ..
             },
-    }: Bsa,
+        extra: _,
+    }: &Bsa,
     handle_archive: F,
 ) -> Result<()> {
-    let version = match version {
+    let version = match *version {
         103 => Version::v103,
         104 => Version::v104,
         105 => Version::v105,
         other => anyhow::bail!("unsuppored version: {other}"),
     };
-    let archive_flags = ArchiveFlags::from_bits(archive_flags).with_context(|| format!("invalid flags: {archive_flags:b}"))?;
+    let archive_flags = ArchiveFlags::from_bits(*archive_flags).with_context(|| format!("invalid flags: {archive_flags:b}"))?;
     let archive_types = {
-        let file_flags = match file_flags {
+        let file_flags = match *file_flags {
             bsa::Either::Left(normal) => normal,
             bsa::Either::Right(weird) => {
                 tracing::warn!("encountered a weird file_flags: should be 16 bit but got 32 bit. casting and hoping for the best ({weird:b})");
@@ -166,14 +170,13 @@ pub fn create_archive<F: FnOnce(&Archive<'_>, ArchiveOptions, MaybeWindowsPath)
             pb.pb_set_length(file_states.len() as _);
         });
     file_states
-        .into_par_iter()
+        .par_iter()
         .map(move |WithTypeGuard { inner: file_state_data, .. }| {
             info_span!("handle_file_state", ?file_state_data).in_scope(|| {
-                temp_id_dir
-                    .join(file_state_data.path.clone().into_path())
+                path_resolver::existing_path(&temp_id_dir, &file_state_data.path.clone().into_path())
                     .pipe(|path| path.open_file_read())
                     .and_then(|(path, file)| LazyArchiveFile::new(&file, file_state_data.clone()).with_context(|| format!("loading file at [{path:?}]")))
-                    .and_then(|file| create_key(file_state_data.path).map(|key| (key, file)))
+                    .and_then(|file| create_key(file_state_data.path.clone()).map(|key| (key, file)))
             })
         })
         .inspect(|_| reading_bsa_entries.pb_inc(1))
@@ -219,7 +222,7 @@ pub fn create_archive<F: FnOnce(&Archive<'_>, ArchiveOptions, MaybeWindowsPath)
                                         .flags(archive_flags)
                                         .types(archive_types)
                                         .build(),
-                                    to,
+                                    to.clone(),
                                 )
                             })
                     })
@@ -0,0 +1,521 @@
+//! compact in-memory representation of the modlist directives, big lists have millions of them.
+//! hashes are kept as `u64`, the directories of paths and whole archive hash paths are interned, and a [Directive]
+//! is only rebuilt right before it's handled. fields a newer wabbajack added are reported up front and not stored
+use {
+    crate::{
+        install_modlist::download_cache::{to_base_64_from_u64, to_u64_from_base_64},
+        modlist_json::{
+            directive::{
+                create_bsa_directive::CreateBSADirective,
+                ArchiveHashPath,
+                FromArchiveDirective,
+                InlineFileDirective,
+                PatchedFromArchiveDirective,
+                RemappedInlineFileDirective,
+                TransformedTextureDirective,
+            },
+            Directive,
+            DirectiveKind,
+            ImageState,
+        },
+        utils::MaybeWindowsPath,
+    },
+    indexmap::IndexSet,
+    std::{mem::size_of, sync::Arc},
+    tap::prelude::*,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StringId(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArchivePathId(u32);
+
+/// directive index within the store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DirectiveId(u32);
+
+/// file names are mostly unique, so only the directory is interned
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CompactPath {
+    /// including the trailing separator
+    directory: StringId,
+    file_name: Box<str>,
+}
+
+/// hashes are base64 encoded xxhash64, the few that don't survive the round trip are kept as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompactHash {
+    Packed(u64),
+    Raw(StringId),
+}
+
+#[derive(Debug)]
+enum CompactKind {
+    FromArchive {
+        archive_hash_path: ArchivePathId,
+    },
+    PatchedFromArchive {
+        archive_hash_path: ArchivePathId,
+        from_hash: CompactHash,
+        patch_id: uuid::Uuid,
+    },
+    InlineFile {
+        source_data_id: uuid::Uuid,
+    },
+    RemappedInlineFile {
+        source_data_id: uuid::Uuid,
+    },
+    TransformedTexture {
+        archive_hash_path: ArchivePathId,
+        image_state: Box<ImageState>,
+    },
+    /// there's only a handful of these, but each one lists every file in the archive, so it's shared instead of copied
+    CreateBSA(Arc<CreateBSADirective>),
+}
+
+#[derive(Debug)]
+struct CompactDirective {
+    hash: CompactHash,
+    size: u64,
+    to: CompactPath,
+    kind: CompactKind,
+}
+
+#[derive(Debug, Default)]
+pub struct DirectiveStore {
+    strings: IndexSet<Box<str>>,
+    archive_hash_paths: IndexSet<(StringId, Box<[CompactPath]>)>,
+    directives: Vec<CompactDirective>,
+}
+
+impl DirectiveStore {
+    fn intern(&mut self, value: String) -> StringId {
+        self.strings
+            .get_index_of(value.as_str())
+            .unwrap_or_else(|| self.strings.insert_full(value.into_boxed_str()).0)
+            .pipe(|idx| StringId(idx as u32))
+    }
+
+    fn string(&self, StringId(id): StringId) -> &str {
+        &self.strings[id as usize]
+    }
+
+    fn intern_path(&mut self, MaybeWindowsPath(path): MaybeWindowsPath) -> CompactPath {
+        let (directory, file_name) = path
+            .rfind(['\\', '/'])
+            .map(|separator| path.split_at(separator + 1))
+            .unwrap_or(("", path.as_str()));
+        CompactPath {
+            directory: self.intern(directory.to_string()),
+            file_name: file_name.into(),
+        }
+    }
+
+    fn path(&self, CompactPath { directory, file_name }: &CompactPath) -> MaybeWindowsPath {
+        MaybeWindowsPath(format!("{}{file_name}", self.string(*directory)))
+    }
+
+    fn intern_hash(&mut self, hash: String) -> CompactHash {
+        match to_u64_from_base_64(hash.clone()) {
+            Ok(packed) if to_base_64_from_u64(packed) == hash => CompactHash::Packed(packed),
+            _ => CompactHash::Raw(self.intern(hash)),
+        }
+    }
+
+    fn hash(&self, hash: CompactHash) -> String {
+        match hash {
+            CompactHash::Packed(packed) => to_base_64_from_u64(packed),
+            CompactHash::Raw(id) => self.string(id).to_string(),
+        }
+    }
+
+    fn intern_archive_hash_path(&mut self, ArchiveHashPath { source_hash, path }: ArchiveHashPath) -> ArchivePathId {
+        let source_hash = self.intern(source_hash);
+        let path = path
+            .into_iter()
+            .map(|path| self.intern_path(path))
+            .collect::<Box<[_]>>();
+        self.archive_hash_paths
+            .insert_full((source_hash, path))
+            .0
+            .pipe(|idx| ArchivePathId(idx as u32))
+    }
+
+    fn archive_hash_path(&self, ArchivePathId(id): ArchivePathId) -> ArchiveHashPath {
+        let (source_hash, path) = &self.archive_hash_paths[id as usize];
+        ArchiveHashPath {
+            source_hash: self.string(*source_hash).to_string(),
+            path: path.iter().map(|path| self.path(path)).collect(),
+        }
+    }
+
+    fn compact(&mut self, directive: Directive) -> Option<CompactDirective> {
+        let compact = match directive {
+            Directive::CreateBSA(create_bsa) => CompactDirective {
+                hash: create_bsa
+                    .hash()
+                    .to_string()
+                    .pipe(|hash| self.intern_hash(hash)),
+                size: create_bsa.size(),
+                to: create_bsa.to().clone().pipe(|to| self.intern_path(to)),
+                kind: CompactKind::CreateBSA(Arc::new(create_bsa)),
+            },
+            Directive::FromArchive(FromArchiveDirective {
+                hash,
+                size,
+                to,
+                archive_hash_path,
+                extra: _,
+            }) => CompactDirective {
+                hash: self.intern_hash(hash),
+                size,
+                to: self.intern_path(to),
+                kind: CompactKind::FromArchive {
+                    archive_hash_path: self.intern_archive_hash_path(archive_hash_path),
+                },
+            },
+            Directive::PatchedFromArchive(PatchedFromArchiveDirective {
+                hash,
+                size,
+                to,
+                archive_hash_path,
+                from_hash,
+                patch_id,
+                extra: _,
+            }) => CompactDirective {
+                hash: self.intern_hash(hash),
+                size,
+                to: self.intern_path(to),
+                kind: CompactKind::PatchedFromArchive {
+                    archive_hash_path: self.intern_archive_hash_path(archive_hash_path),
+                    from_hash: self.intern_hash(from_hash),
+                    patch_id,
+                },
+            },
+            Directive::InlineFile(InlineFileDirective {
+                hash,
+                size,
+                source_data_id,
+                to,
+                extra: _,
+            }) => CompactDirective {
+                hash: self.intern_hash(hash),
+                size,
+                to: self.intern_path(to),
+                kind: CompactKind::InlineFile { source_data_id },
+            },
+            Directive::RemappedInlineFile(RemappedInlineFileDirective {
+                hash,
+                size,
+                source_data_id,
+                to,
+                extra: _,
+            }) => CompactDirective {
+                hash: self.intern_hash(hash),
+                size,
+                to: self.intern_path(to),
+                kind: CompactKind::RemappedInlineFile { source_data_id },
+            },
+            Directive::TransformedTexture(TransformedTextureDirective {
+                hash,
+                size,
+                image_state,
+                to,
+                archive_hash_path,
+                extra: _,
+            }) => CompactDirective {
+                hash: self.intern_hash(hash),
+                size,
+                to: self.intern_path(to),
+                kind: CompactKind::TransformedTexture {
+                    archive_hash_path: self.intern_archive_hash_path(archive_hash_path),
+                    image_state: Box::new(image_state),
+                },
+            },
+            // these are reported (or refused) before the installation starts, there's nothing to do with them here
+            Directive::Unsupported(entry) => {
+                tracing::debug!(type_name = entry.type_name(), "not storing unsupported directive");
+                return None;
+            }
+        };
+        Some(compact)
+    }
+
+    /// directives are compacted one by one, so the original strings are freed as it goes
+    pub fn from_directives(directives: impl IntoIterator<Item = Directive>) -> Self {
+        Self::from_directives_selecting(directives, |_| true).0
+    }
+
+    /// `select` sees every directive as it was parsed (the store drops the fields it has no use for),
+    /// the ids of the stored ones it picked are returned along with the store
+    pub fn from_directives_selecting(directives: impl IntoIterator<Item = Directive>, mut select: impl FnMut(&Directive) -> bool) -> (Self, Vec<DirectiveId>) {
+        let mut selected = vec![];
+        directives
+            .into_iter()
+            .fold(Self::default(), |store, directive| {
+                store.tap_mut(|store| {
+                    let is_selected = select(&directive);
+                    if let Some(compact) = store.compact(directive) {
+                        if is_selected {
+                            selected.push(DirectiveId(store.directives.len() as u32));
+                        }
+                        store.directives.push(compact);
+                    }
+                })
+            })
+            .tap_mut(|store| {
+                store.strings.shrink_to_fit();
+                store.archive_hash_paths.shrink_to_fit();
+                store.directives.shrink_to_fit();
+            })
+            .tap(|store| {
+                tracing::debug!(
+                    directives = store.directives.len(),
+                    strings = store.strings.len(),
+                    archive_hash_paths = store.archive_hash_paths.len(),
+                    estimated_bytes = store.estimated_size(),
+                    "compacted directives"
+                )
+            })
+            .pipe(|store| (store, selected))
+    }
+
+    /// what the store itself holds on the heap, bsa file lists excluded, for comparing against the parsed modlist
+    pub fn estimated_size(&self) -> usize {
+        // an index entry and a hash per element, on top of the element itself
+        const INDEX_ENTRY: usize = 2 * size_of::<usize>();
+        let strings = self
+            .strings
+            .iter()
+            .map(|string| size_of::<Box<str>>() + INDEX_ENTRY + string.len())
+            .sum::<usize>();
+        let archive_hash_paths = self
+            .archive_hash_paths
+            .iter()
+            .map(|(_, path)| {
+                size_of::<(StringId, Box<[CompactPath]>)>()
+                    + INDEX_ENTRY
+                    + path
+                        .iter()
+                        .map(|path| size_of::<CompactPath>() + path.file_name.len())
+                        .sum::<usize>()
+            })
+            .sum::<usize>();
+        let directives = self
+            .directives
+            .iter()
+            .map(|directive| {
+                size_of::<CompactDirective>()
+                    + directive.to.file_name.len()
+                    + match directive.kind {
+                        CompactKind::TransformedTexture { .. } => size_of::<ImageState>(),
+                        _ => 0,
+                    }
+            })
+            .sum::<usize>();
+        strings + archive_hash_paths + directives
+    }
+
+    pub fn len(&self) -> usize {
+        self.directives.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.directives.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = DirectiveId> {
+        (0..self.directives.len() as u32).map(DirectiveId)
+    }
+
+    fn directive(&self, DirectiveId(id): DirectiveId) -> &CompactDirective {
+        &self.directives[id as usize]
+    }
+
+    pub fn size(&self, id: DirectiveId) -> u64 {
+        self.directive(id).size
+    }
+
+    pub fn expected_hash(&self, id: DirectiveId) -> String {
+        self.hash(self.directive(id).hash)
+    }
+
+    pub fn to(&self, id: DirectiveId) -> MaybeWindowsPath {
+        self.path(&self.directive(id).to)
+    }
+
+    pub fn kind(&self, id: DirectiveId) -> DirectiveKind {
+        match self.directive(id).kind {
+            CompactKind::FromArchive { .. } => DirectiveKind::FromArchive,
+            CompactKind::PatchedFromArchive { .. } => DirectiveKind::PatchedFromArchive,
+            CompactKind::InlineFile { .. } => DirectiveKind::InlineFile,
+            CompactKind::RemappedInlineFile { .. } => DirectiveKind::RemappedInlineFile,
+            CompactKind::TransformedTexture { .. } => DirectiveKind::TransformedTexture,
+            CompactKind::CreateBSA(_) => DirectiveKind::CreateBSA,
+        }
+    }
+
+    /// shared with the store, use this instead of [DirectiveStore::get] to handle bsa directives
+    pub fn create_bsa(&self, id: DirectiveId) -> Option<Arc<CreateBSADirective>> {
+        match &self.directive(id).kind {
+            CompactKind::CreateBSA(create_bsa) => Some(create_bsa.clone()),
+            _ => None,
+        }
+    }
+
+    /// rebuilds the directive as it was in the modlist, bsa directives are copied as a whole
+    pub fn get(&self, id: DirectiveId) -> Directive {
+        let CompactDirective { hash, size, to, kind } = self.directive(id);
+        let (hash, size, to) = (self.hash(*hash), *size, self.path(to));
+        match kind {
+            CompactKind::FromArchive { archive_hash_path } => Directive::FromArchive(FromArchiveDirective {
+                hash,
+                size,
+                to,
+                archive_hash_path: self.archive_hash_path(*archive_hash_path),
+                extra: Default::default(),
+            }),
+            CompactKind::PatchedFromArchive {
+                archive_hash_path,
+                from_hash,
+                patch_id,
+            } => Directive::PatchedFromArchive(PatchedFromArchiveDirective {
+                hash,
+                size,
+                to,
+                archive_hash_path: self.archive_hash_path(*archive_hash_path),
+                from_hash: self.hash(*from_hash),
+                patch_id: *patch_id,
+                extra: Default::default(),
+            }),
+            CompactKind::InlineFile { source_data_id } => Directive::InlineFile(InlineFileDirective {
+                hash,
+                size,
+                source_data_id: *source_data_id,
+                to,
+                extra: Default::default(),
+            }),
+            CompactKind::RemappedInlineFile { source_data_id } => Directive::RemappedInlineFile(RemappedInlineFileDirective {
+                hash,
+                size,
+                source_data_id: *source_data_id,
+                to,
+                extra: Default::default(),
+            }),
+            CompactKind::TransformedTexture {
+                archive_hash_path,
+                image_state,
+            } => Directive::TransformedTexture(TransformedTextureDirective {
+                hash,
+                size,
+                image_state: image_state.as_ref().clone(),
+                to,
+                archive_hash_path: self.archive_hash_path(*archive_hash_path),
+                extra: Default::default(),
+            }),
+            CompactKind::CreateBSA(create_bsa) => Directive::CreateBSA(create_bsa.as_ref().clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_archive(hash: &str, to: &str, source_hash: &str) -> Directive {
+        Directive::FromArchive(FromArchiveDirective {
+            hash: hash.to_string(),
+            size: 42,
+            to: MaybeWindowsPath(to.to_string()),
+            archive_hash_path: ArchiveHashPath {
+                source_hash: source_hash.to_string(),
+                path: vec![MaybeWindowsPath("textures\\rock.dds".to_string())],
+            },
+            extra: Default::default(),
+        })
+    }
+
+    #[test]
+    fn test_directives_survive_the_round_trip() {
+        let directives = vec![
+            from_archive(&to_base_64_from_u64(1234), "mods\\a\\textures\\rock.dds", &to_base_64_from_u64(1)),
+            // not a valid hash, still has to come back the same
+            from_archive("not a hash", "mods\\b\\textures\\rock.dds", &to_base_64_from_u64(1)),
+        ];
+        let expected = directives
+            .iter()
+            .map(|directive| serde_json::to_string(directive).unwrap())
+            .collect::<Vec<_>>();
+        let store = DirectiveStore::from_directives(directives);
+        assert_eq!(store.len(), 2);
+        // both point into the same archive
+        assert_eq!(store.archive_hash_paths.len(), 1);
+        assert_eq!(
+            store
+                .ids()
+                .map(|id| serde_json::to_string(&store.get(id)).unwrap())
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert!(store
+            .ids()
+            .all(|id| store.kind(id) == DirectiveKind::FromArchive));
+    }
+
+    #[test]
+    fn test_directives_are_selected_as_they_were_parsed() {
+        let with_extra = from_archive(&to_base_64_from_u64(2), "mods\\b\\textures\\rock.dds", &to_base_64_from_u64(1)).tap_mut(|directive| {
+            if let Directive::FromArchive(directive) = directive {
+                directive
+                    .extra
+                    .insert("AddedLater".to_string(), serde_json::json!(true));
+            }
+        });
+        let expected_hash = with_extra.directive_hash();
+        let directives = vec![
+            from_archive(&to_base_64_from_u64(1), "mods\\a\\textures\\rock.dds", &to_base_64_from_u64(1)),
+            with_extra,
+        ];
+        let (store, selected) = DirectiveStore::from_directives_selecting(directives, |directive| directive.directive_hash() == expected_hash);
+        // the stored directive lost the extra field, so its hash no longer matches
+        assert_ne!(store.get(DirectiveId(1)).directive_hash(), expected_hash);
+        assert_eq!(selected, vec![DirectiveId(1)]);
+    }
+
+    #[test]
+    fn test_store_is_smaller_than_the_directives() {
+        let directives = (0..1000)
+            .map(|idx| {
+                from_archive(
+                    &to_base_64_from_u64(idx),
+                    &format!("mods\\[NoDelete] Some Texture Overhaul 2.1.3\\textures\\landscape\\rocks\\rock{idx:04}.dds"),
+                    &to_base_64_from_u64(1),
+                )
+            })
+            .collect::<Vec<_>>();
+        let parsed = directives
+            .iter()
+            .map(|directive| match directive {
+                Directive::FromArchive(FromArchiveDirective {
+                    hash, to, archive_hash_path, ..
+                }) => {
+                    size_of::<Directive>()
+                        + hash.len()
+                        + to.0.len()
+                        + archive_hash_path.source_hash.len()
+                        + archive_hash_path
+                            .path
+                            .iter()
+                            .map(|path| size_of::<MaybeWindowsPath>() + path.0.len())
+                            .sum::<usize>()
+                }
+                _ => unreachable!(),
+            })
+            .sum::<usize>();
+        let store = DirectiveStore::from_directives(directives);
+        // the shared directory, the archive and the directory inside of it
+        assert_eq!(store.strings.len(), 3);
+        assert!(store.estimated_size() < parsed, "{} >= {parsed}", store.estimated_size());
+    }
+}
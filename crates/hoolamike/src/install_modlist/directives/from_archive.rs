@@ -19,6 +19,7 @@ use {
 #[derivative(Debug)]
 pub struct FromArchiveHandler {
     pub output_directory: PathBuf,
+    pub keep_abandoned_outputs: bool,
     #[derivative(Debug = "ignore")]
     pub download_summary: DownloadSummary,
 }
@@ -54,6 +55,7 @@ impl FromArchiveHandler {
             size,
             to,
             archive_hash_path,
+            extra: _,
         }: FromArchiveDirective,
         preheated: Arc<PreheatedArchiveHashPaths>,
     ) -> Result<u64> {
@@ -62,7 +64,7 @@ impl FromArchiveHandler {
             .resolve_archive_path(&archive_hash_path)
             .and_then(|path| preheated.get_archive(path))
             .with_context(|| format!("reading archive for [{archive_hash_path:?}]"))?;
-        let output_path = self.output_directory.join(to.into_path());
+        let output_path = path_resolver::output_path(&self.output_directory, &to.into_path())?;
 
         spawn_rayon(move || -> Result<_> {
             let perform_copy = move |from: &mut dyn Read, to: &mut dyn Write, target_path: PathBuf| {
@@ -90,14 +92,16 @@ impl FromArchiveHandler {
             source_file
                 .open_file_read()
                 .and_then(|(source_path, mut final_source)| {
-                    create_file_all(&output_path).and_then(|mut output_file| {
-                        perform_copy(&mut final_source, &mut output_file, output_path.clone()).with_context(|| {
-                            format!(
-                                "when extracting from [{source_path:?}] ({:?}) to [{}]",
-                                archive_hash_path,
-                                output_path.display()
-                            )
-                        })
+                    create_file_all(&output_path, self.keep_abandoned_outputs).and_then(|mut output_file| {
+                        perform_copy(&mut final_source, &mut output_file, output_path.clone())
+                            .and_then(|_| output_file.commit())
+                            .with_context(|| {
+                                format!(
+                                    "when extracting from [{source_path:?}] ({:?}) to [{}]",
+                                    archive_hash_path,
+                                    output_path.display()
+                                )
+                            })
                     })
                 })?;
             Ok(())
@@ -9,6 +9,7 @@ use {
 pub struct InlineFileHandler {
     pub wabbajack_file: WabbajackFileHandle,
     pub output_directory: PathBuf,
+    pub keep_abandoned_outputs: bool,
 }
 
 impl InlineFileHandler {
@@ -20,12 +21,13 @@ impl InlineFileHandler {
             size,
             source_data_id,
             to,
+            extra: _,
         }: InlineFileDirective,
     ) -> Result<u64> {
-        let output_path = self.output_directory.join(to.into_path());
-        let wabbajack_file = self.wabbajack_file.clone();
+        let output_path = path_resolver::output_path(&self.output_directory, &to.into_path())?;
+        let (wabbajack_file, keep_abandoned_outputs) = (self.wabbajack_file.clone(), self.keep_abandoned_outputs);
         spawn_rayon(move || -> Result<_> {
-            let output_file = create_file_all(&output_path)?;
+            let mut output_file = create_file_all(&output_path, keep_abandoned_outputs)?;
 
             let archive = wabbajack_file;
             archive
@@ -36,7 +38,7 @@ impl InlineFileHandler {
                         .map(|(_, file)| (source_data, file))
                 })
                 .and_then(|(_guard, mut file)| {
-                    let mut writer = std::io::BufWriter::new(output_file);
+                    let mut writer = std::io::BufWriter::new(&mut output_file);
                     std::io::copy(
                         &mut tracing::Span::current().wrap_read(size, &mut file),
                         // WARN: stuff that's inside modlist.wabbajack/modlist(.json) is incorrect
@@ -47,6 +49,7 @@ impl InlineFileHandler {
                     .context("copying file from archive")
                     .and_then(|_| writer.flush().context("flushing"))
                 })
+                .and_then(|_| output_file.commit())
                 .map(|_| ())
         })
         .await
@@ -8,6 +8,7 @@ use {
         ResolvePathExt,
         StreamTryFlatMapExt,
     },
+    crate::shutdown,
     anyhow::{Context, Result},
     futures::{FutureExt, Stream, StreamExt, TryFutureExt},
     std::{future::ready, sync::Arc},
@@ -43,6 +44,7 @@ pub(crate) fn handle_nested_archive_directives(
         .try_flat_map(move |preheated| {
             directives
                 .pipe(futures::stream::iter)
+                .take_while(|_| ready(!shutdown::requested()))
                 .map(move |directive| match directive {
                     ArchivePathDirective::TransformedTexture(transformed_texture) => manager
                         .transformed_texture
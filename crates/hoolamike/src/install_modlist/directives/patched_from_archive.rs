@@ -20,6 +20,7 @@ pub struct PatchedFromArchiveHandler {
     #[derivative(Debug = "ignore")]
     pub wabbajack_file: WabbajackFileHandle,
     pub output_directory: PathBuf,
+    pub keep_abandoned_outputs: bool,
     pub download_summary: DownloadSummary,
 }
 
@@ -34,6 +35,7 @@ impl PatchedFromArchiveHandler {
             archive_hash_path,
             from_hash: _,
             patch_id,
+            extra: _,
         }: PatchedFromArchiveDirective,
         preheated: Arc<PreheatedArchiveHashPaths>,
     ) -> Result<u64> {
@@ -43,7 +45,7 @@ impl PatchedFromArchiveHandler {
             .and_then(|path| preheated.get_archive(path))
             .with_context(|| format!("reading archive for [{archive_hash_path:?}]"))?;
 
-        let output_path = self.output_directory.join(to.into_path());
+        let output_path = path_resolver::output_path(&self.output_directory, &to.into_path())?;
 
         spawn_rayon(move || -> Result<_> {
             let wabbajack_file = self.wabbajack_file.clone();
@@ -82,8 +84,9 @@ impl PatchedFromArchiveHandler {
             source_file
                 .open_file_read()
                 .and_then(|(final_source_path, mut final_source)| {
-                    create_file_all(&output_path).and_then(|mut output_file| {
+                    create_file_all(&output_path, self.keep_abandoned_outputs).and_then(|mut output_file| {
                         perform_copy(&mut final_source, delta_file, &mut output_file, size, hash)
+                            .and_then(|_| output_file.commit())
                             .with_context(|| format!("when extracting from [{final_source_path:?}] to [{output_path:?}]"))
                             .with_context(|| format!("when handling [{archive_hash_path:?}] copy"))
                     })
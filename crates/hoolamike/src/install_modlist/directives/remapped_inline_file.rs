@@ -106,6 +106,7 @@ impl RemappingContext {
 pub struct RemappedInlineFileHandler {
     pub remapping_context: Arc<RemappingContext>,
     pub wabbajack_file: WabbajackFileHandle,
+    pub keep_abandoned_outputs: bool,
 }
 
 impl RemappedInlineFileHandler {
@@ -117,11 +118,13 @@ impl RemappedInlineFileHandler {
             size,
             source_data_id,
             to,
+            extra: _,
         }: RemappedInlineFileDirective,
     ) -> Result<u64> {
         let Self {
             remapping_context,
             wabbajack_file,
+            keep_abandoned_outputs,
         } = self;
         spawn_rayon(move || {
             wabbajack_file
@@ -143,13 +146,12 @@ impl RemappedInlineFileHandler {
                 })
                 .map(|file| remapping_context.remap_file_contents(&file))
                 .and_then(|output| {
-                    remapping_context
-                        .output_directory
-                        .join(to.clone().into_path())
-                        .open_file_write()
-                        .and_then(|(_, mut file)| {
+                    path_resolver::output_path(&remapping_context.output_directory, &to.clone().into_path())
+                        .and_then(|output_path| create_file_all(&output_path, keep_abandoned_outputs))
+                        .and_then(|mut file| {
                             std::io::copy(&mut tracing::Span::current().wrap_read(size, std::io::Cursor::new(output)), &mut file)
                                 .context("writing remapped file")
+                                .and_then(|written| file.commit().map(|_| written))
                         })
                 })
         })
@@ -13,6 +13,7 @@ use {
 #[derivative(Debug)]
 pub struct TransformedTextureHandler {
     pub output_directory: PathBuf,
+    pub keep_abandoned_outputs: bool,
     #[derivative(Debug = "ignore")]
     pub download_summary: DownloadSummary,
 }
@@ -46,15 +47,17 @@ impl TransformedTextureHandler {
                     mip_levels,
                     perceptual_hash: _,
                     width,
+                    extra: _,
                 },
             to,
             archive_hash_path,
+            extra: _,
         }: TransformedTextureDirective,
         preheated: Arc<PreheatedArchiveHashPaths>,
     ) -> Result<u64> {
         let handle = tracing::Span::current();
         // let _image_dds_format = supported_image_format(format).context("checking for format support")?;
-        let output_path = self.output_directory.join(to.into_path());
+        let output_path = path_resolver::output_path(&self.output_directory, &to.into_path())?;
         let source_file = self
             .download_summary
             .resolve_archive_path(&archive_hash_path)
@@ -87,8 +90,9 @@ impl TransformedTextureHandler {
                 source_file
                     .open_file_read()
                     .and_then(|(source_path, mut final_source)| {
-                        create_file_all(&output_path).and_then(|mut output_file| {
+                        create_file_all(&output_path, self.keep_abandoned_outputs).and_then(|mut output_file| {
                             perform_copy(&mut final_source, &mut output_file, output_path.clone())
+                                .and_then(|_| output_file.commit())
                                 // .or_else(|reason| {
                                 //     let _span =
                                 //         tracing::error_span!("could not resize texture, copying the original", reason = %format!("{reason:?}")).entered();
@@ -25,15 +25,40 @@ pub struct WabbajackFileHandle {
 }
 
 impl WabbajackFileHandle {
+    /// where the data directives refer to by id is stored inside of the archive
+    pub fn source_data_path(source_data_id: uuid::Uuid) -> PathBuf {
+        PathBuf::from(source_data_id.as_hyphenated().to_string())
+    }
     #[instrument]
     pub fn get_source_data(&self, source_data_id: uuid::Uuid) -> Result<TempPath> {
         let mut preloaded = self.preloaded.lock();
         preloaded
-            .remove(Path::new(&source_data_id.as_hyphenated().to_string()))
+            .remove(&Self::source_data_path(source_data_id))
             .with_context(|| format!("no [{source_data_id:?}] inside wabbajack archive ({:#?})", preloaded.keys().collect_vec()))
     }
+    /// copies the data out without taking it, so that the directive can still use it afterwards
+    pub fn copy_source_data(&self, source_data_id: uuid::Uuid, to: &Path) -> Result<u64> {
+        self.preloaded
+            .lock()
+            .get(&Self::source_data_path(source_data_id))
+            .with_context(|| format!("no [{source_data_id:?}] inside wabbajack archive"))
+            .and_then(|source_data| std::fs::copy(source_data, to).with_context(|| format!("copying [{source_data_id:?}] to [{}]", to.display())))
+    }
     #[instrument]
     pub(crate) fn from_archive(archive_path: PathBuf) -> Result<Self> {
+        Self::from_archive_filtered(archive_path, |_| true)
+    }
+    /// only the data of given ids is extracted
+    #[instrument]
+    pub(crate) fn from_archive_entries(archive_path: PathBuf, source_data_ids: &[uuid::Uuid]) -> Result<Self> {
+        let wanted = source_data_ids
+            .iter()
+            .copied()
+            .map(Self::source_data_path)
+            .collect::<std::collections::BTreeSet<_>>();
+        Self::from_archive_filtered(archive_path, move |path| wanted.contains(path))
+    }
+    fn from_archive_filtered(archive_path: PathBuf, filter: impl Fn(&Path) -> bool) -> Result<Self> {
         archive_path
             .open_file_read()
             .and_then(|(at_path, _file)| ZipArchive::new(&at_path).with_context(|| format!("opening archive at path [{at_path:#?}]")))
@@ -47,6 +72,7 @@ impl WabbajackFileHandle {
                         paths
                             .iter()
                             .map(|p| p.as_path())
+                            .filter(|path| filter(*path))
                             .collect_vec()
                             .par_chunks(chunk_size)
                             .map(|chunk| {
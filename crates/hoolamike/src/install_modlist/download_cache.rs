@@ -98,14 +98,31 @@ pub fn to_u64_from_base_64(input: String) -> Result<u64> {
         .context("decoding string as hashed bytes")
 }
 
+/// base64 encoded xxhash64, the way wabbajack stores hashes
+pub async fn file_hash(path: PathBuf) -> Result<String> {
+    calculate_hash(path).map_ok(to_base_64_from_u64).await
+}
+
+/// the file is there, but it's not the one the modlist wants
+#[derive(Debug, thiserror::Error)]
+#[error("hash mismatch, expected [{expected}], found [{found}]")]
+pub struct HashMismatch {
+    pub expected: String,
+    pub found: String,
+}
+
 pub async fn validate_hash(path: PathBuf, expected_hash: String) -> Result<PathBuf> {
-    calculate_hash(path.clone())
-        .map_ok(to_base_64_from_u64)
+    file_hash(path.clone())
         .and_then(|hash| {
-            hash.eq(&expected_hash)
-                .then_some(path.clone())
-                .with_context(|| format!("hash mismatch, expected [{expected_hash}], found [{hash}]"))
-                .pipe(ready)
+            match hash == expected_hash {
+                true => Ok(path.clone()),
+                false => Err(HashMismatch {
+                    expected: expected_hash.clone(),
+                    found: hash,
+                }
+                .into()),
+            }
+            .pipe(ready)
         })
         .await
         .with_context(|| format!("validating hash for [{}]", path.display()))
@@ -125,7 +142,13 @@ impl DownloadCache {
         self.root_directory.join(file_name)
     }
     pub async fn verify(self: Arc<Self>, descriptor: ArchiveDescriptor) -> Result<WithArchiveDescriptor<PathBuf>> {
-        let ArchiveDescriptor { hash, meta: _, name, size } = descriptor.clone();
+        let ArchiveDescriptor {
+            hash,
+            meta: _,
+            name,
+            size,
+            extra: _,
+        } = descriptor.clone();
         self.download_output_path(name)
             .pipe(Ok)
             .pipe(ready)
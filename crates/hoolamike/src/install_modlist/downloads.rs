@@ -1,42 +1,95 @@
 use {
     super::*,
     crate::{
-        config_file::{DownloadersConfig, GamesConfig},
+        config_file::{ArchiveOverride, ArchiveUpgradesConfig, DownloadersConfig, GamesConfig, Ips4Config},
         downloaders::{
-            gamefile_source_downloader::{get_game_file_source_synchronizers, GameFileSourceSynchronizers},
+            gamefile_source_downloader::{find_game_file_source_synchronizer, get_game_file_source_synchronizers, GameFileSourceSynchronizers},
+            github::GitHubDownloader,
+            google_drive,
             helpers::FutureAnyhowExt,
+            ips4::{Ips4Downloader, OAuthToken},
             mediafire::MediaFireDownloader,
+            moddb::ModDBDownloader,
             nexus::{self, NexusDownloader},
+            response_sniffing::{check_headers, sniff_stream},
             wabbajack_cdn::WabbajackCDNDownloader,
+            wabbajack_upgrades::{apply_patch, ArchiveUpgrade, WabbajackUpgradesDownloader},
             CopyFileTask,
             DownloadTask,
+            Ips4DownloadTask,
             MergeDownloadTask,
+            MirroredDownloadTask,
             SyncTask,
+            UpgradeTask,
             WithArchiveDescriptor,
         },
         error::{MultiErrorCollectExt, TotalResult},
-        modlist_json::{Archive, GoogleDriveState, HttpState, HumanUrl, ManualState, MediaFireState, MegaState, NexusGameName, NexusState, State},
+        install_modlist::download_cache::validate_hash,
+        modlist_json::{
+            Archive,
+            ArchiveDescriptor,
+            GoogleDriveState,
+            HttpState,
+            HumanUrl,
+            Ips4OAuth2State,
+            ManualState,
+            MediaFireState,
+            MegaState,
+            ModDBState,
+            NexusState,
+            State,
+        },
         progress_bars_v2::IndicatifWrapIoExt,
+        shutdown,
     },
     anyhow::Result,
-    futures::{FutureExt, StreamExt, TryStreamExt},
-    std::{collections::HashMap, path::PathBuf, sync::Arc},
-    tracing::{debug, instrument, Instrument},
+    futures::{future::BoxFuture, FutureExt, StreamExt, TryStreamExt},
+    indexmap::IndexMap,
+    std::{path::PathBuf, sync::Arc},
+    tracing::{debug, info, instrument, Instrument},
 };
 
 #[derive(Clone)]
 pub struct DownloadersInner {
     pub nexus: Option<Arc<NexusDownloader>>,
+    pub upgrades: Option<Arc<WabbajackUpgradesDownloader>>,
+    pub github: Arc<GitHubDownloader>,
+    /// keyed by site name
+    pub ips4: Arc<IndexMap<String, Ips4Downloader>>,
 }
 
 impl DownloadersInner {
-    pub fn new(DownloadersConfig { nexus, downloads_directory: _ }: DownloadersConfig) -> Result<Self> {
+    pub fn new(
+        DownloadersConfig {
+            nexus,
+            downloads_directory: _,
+            archive_overrides: _,
+            archive_upgrades: ArchiveUpgradesConfig {
+                enabled: upgrades_enabled,
+                upgraded_archives_url,
+            },
+            github,
+            ips4: Ips4Config { token_directory, sites },
+        }: DownloadersConfig,
+    ) -> Result<Self> {
         Ok(Self {
             nexus: nexus
                 .api_key
                 .map(NexusDownloader::new)
                 .transpose()?
                 .map(Arc::new),
+            upgrades: upgrades_enabled
+                .then(|| WabbajackUpgradesDownloader::new(upgraded_archives_url))
+                .map(Arc::new),
+            github: GitHubDownloader::new(github).map(Arc::new)?,
+            ips4: sites
+                .into_iter()
+                .map(|(site, site_config)| {
+                    OAuthToken::load(&token_directory, &site)
+                        .map(|token| (site.clone(), Ips4Downloader::new(site, site_config, token_directory.clone(), token)))
+                })
+                .collect::<Result<IndexMap<_, _>>>()
+                .map(Arc::new)?,
         })
     }
 }
@@ -60,71 +113,6 @@ async fn copy_local_file(from: PathBuf, to: PathBuf, expected_size: u64) -> Resu
         .read(true)
         .open(&from)
         .map_with_context(|| format!("opening [{}]", from.display()))
-        .or_else(|error| {
-            tracing::warn_span!("could not find [{from:?}], trying case insensitive?", reason = format!("{error:?}"))
-                .in_scope(|| {
-                    tokio::task::block_in_place(|| {
-                        from.file_name()
-                            .context("no file name")
-                            .and_then(|name| {
-                                name.to_str()
-                                    .context("filename is not a utf8 string")
-                                    .map(|file_name| file_name.to_lowercase())
-                            })
-                            .and_then(|file_name| {
-                                from.parent()
-                                    .with_context(|| format!("[{from:?}] has no parent"))
-                                    .and_then(|parent| {
-                                        parent
-                                            .read_dir()
-                                            .context("reading directory")
-                                            .and_then(|dir| {
-                                                dir.into_iter()
-                                                    .map(|dir| {
-                                                        dir.context("reading entry")
-                                                            .and_then(|entry| {
-                                                                entry
-                                                                    .file_name()
-                                                                    .into_string()
-                                                                    .map_err(|name| {
-                                                                        anyhow::anyhow!("entry [{entry:?}] ([{name:?}]) is not a valid utf8 string")
-                                                                    })
-                                                                    .map(|name| (name, entry))
-                                                            })
-                                                            .map(|(filename, entry)| (filename.to_lowercase(), entry.path().to_owned()))
-                                                    })
-                                                    .collect::<Result<HashMap<_, _>>>()
-                                                    .with_context(|| format!("when listing [{parent:?}]"))
-                                            })
-                                            .and_then(|mut paths| {
-                                                paths.remove(&file_name).with_context(|| {
-                                                    format!(
-                                                        "no [{file_name}] in [{parent:?}] (looking up by lowercase filename, and choices are:\n[{paths:#?}])"
-                                                    )
-                                                })
-                                            })
-                                    })
-                            })
-                    })
-                })
-                .pipe(ready)
-                .and_then(|lowercase| async move {
-                    tokio::fs::OpenOptions::new()
-                        .read(true)
-                        .open(lowercase.clone())
-                        .map_with_context({
-                            cloned![lowercase];
-                            move || format!("opening lowercase file that matched the path: [{lowercase:?}]")
-                        })
-                        .await
-                        .tap_ok(|_| {
-                            tracing::warn!(
-                                "opened file matched by lowercase filename: [{lowercase:?}]. this is guesswork at this point, if this approach doesn't work \
-                                 file an issue"
-                            )
-                        })
-                })
-        })
         .await?;
     let target_file = tokio::fs::OpenOptions::new()
         .write(true)
@@ -145,26 +133,42 @@ async fn copy_local_file(from: PathBuf, to: PathBuf, expected_size: u64) -> Resu
 }
 #[instrument]
 pub async fn stream_merge_file(from: Vec<HumanUrl>, to: PathBuf, expected_size: u64) -> Result<PathBuf> {
-    let target_file = tokio::fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&to)
-        .map_with_context(|| format!("opening [{}]", to.display()))
-        .await?;
-
-    let mut writer = &mut tracing::Span::current().wrap_async_write(expected_size, target_file);
+    let mut writer = None;
     let mut downloaded = 0;
-    for from_chunk in from.clone().into_iter() {
-        let mut byte_stream = reqwest::get(from_chunk.to_string())
+    for (part, from_chunk) in from.clone().into_iter().enumerate() {
+        let response = reqwest::get(from_chunk.to_string())
+            .await
+            .with_context(|| format!("making request to {from_chunk}"))?;
+        check_headers(response.headers(), response.content_length(), None, &to).with_context(|| format!("bad response from {from_chunk}"))?;
+        let mut byte_stream = response.bytes_stream();
+        // only the beginning of the whole file says anything about what it is
+        let head = match part {
+            0 => sniff_stream(&mut byte_stream, &to)
+                .await
+                .with_context(|| format!("bad response from {from_chunk}"))?,
+            _ => vec![],
+        };
+        if writer.is_none() {
+            writer = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&to)
+                .map_with_context(|| format!("opening [{}]", to.display()))
+                .await
+                .map(|target_file| tracing::Span::current().wrap_async_write(expected_size, target_file))
+                .map(Some)?;
+        }
+        let writer = writer.as_mut().context("output file is not open")?;
+        downloaded += head.len() as u64;
+        tokio::io::copy(&mut head.as_slice(), writer)
             .await
-            .with_context(|| format!("making request to {from_chunk}"))?
-            .bytes_stream();
+            .with_context(|| format!("writing to fd {}", to.display()))?;
         while let Some(chunk) = byte_stream.next().await {
             match chunk {
                 Ok(chunk) => {
                     downloaded += chunk.len() as u64;
-                    tokio::io::copy(&mut chunk.as_ref(), &mut writer)
+                    tokio::io::copy(&mut chunk.as_ref(), writer)
                         .await
                         .with_context(|| format!("writing to fd {}", to.display()))?;
                 }
@@ -181,6 +185,33 @@ pub async fn stream_merge_file(from: Vec<HumanUrl>, to: PathBuf, expected_size:
 
 #[instrument]
 pub async fn stream_file(from: HumanUrl, to: PathBuf, expected_size: u64) -> Result<PathBuf> {
+    stream_request(reqwest::Client::new().get(from.to_string()), from, to, expected_size).await
+}
+
+/// without a token it's a plain download
+#[instrument(skip(bearer_token))]
+pub async fn stream_authorized_file(from: HumanUrl, bearer_token: Option<String>, to: PathBuf, expected_size: u64) -> Result<PathBuf> {
+    let request = reqwest::Client::new().get(from.to_string());
+    let request = match bearer_token {
+        Some(bearer_token) => request.bearer_auth(bearer_token),
+        None => request,
+    };
+    stream_request(request, from, to, expected_size).await
+}
+
+async fn stream_request(request: reqwest::RequestBuilder, from: HumanUrl, to: PathBuf, expected_size: u64) -> Result<PathBuf> {
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("making request to {from}"))?
+        .error_for_status()
+        .with_context(|| format!("bad status from {from}"))?;
+    check_headers(response.headers(), response.content_length(), Some(expected_size), &to).with_context(|| format!("bad response from {from}"))?;
+    let mut byte_stream = response.bytes_stream();
+    // the output is only touched once the response looks like the archive
+    let head = sniff_stream(&mut byte_stream, &to)
+        .await
+        .with_context(|| format!("bad response from {from}"))?;
     let target_file = tokio::fs::OpenOptions::new()
         .write(true)
         .create(true)
@@ -189,11 +220,10 @@ pub async fn stream_file(from: HumanUrl, to: PathBuf, expected_size: u64) -> Res
         .map_with_context(|| format!("opening [{}]", to.display()))
         .await?;
     let mut writer = &mut tracing::Span::current().wrap_async_write(expected_size, tokio::io::BufWriter::new(target_file));
-    let mut byte_stream = reqwest::get(from.to_string())
+    let mut downloaded = head.len() as u64;
+    tokio::io::copy(&mut head.as_slice(), &mut writer)
         .await
-        .with_context(|| format!("making request to {from}"))?
-        .bytes_stream();
-    let mut downloaded = 0;
+        .with_context(|| format!("writing to fd {}", to.display()))?;
     while let Some(chunk) = byte_stream.next().await {
         match chunk {
             Ok(chunk) => {
@@ -211,57 +241,155 @@ pub async fn stream_file(from: HumanUrl, to: PathBuf, expected_size: u64) -> Res
     }
     Ok(to)
 }
+
+#[instrument]
+pub async fn stream_file_from_mirrors(mirrors: Vec<HumanUrl>, to: PathBuf, expected_size: u64) -> Result<PathBuf> {
+    let mut failures = vec![];
+    for mirror in mirrors {
+        match stream_file(mirror.clone(), to.clone(), expected_size).await {
+            Ok(downloaded) => return Ok(downloaded),
+            Err(reason) => {
+                tracing::warn!(%mirror, ?reason, "mirror failed, trying the next one");
+                failures.push(reason);
+            }
+        }
+    }
+    anyhow::bail!("all mirrors failed:\n{failures:#?}")
+}
+
 impl Synchronizers {
     pub fn new(config: DownloadersConfig, games_config: GamesConfig) -> Result<Self> {
         Ok(Self {
             config: Arc::new(config.clone()),
             cache: Arc::new(download_cache::DownloadCache::new(config.downloads_directory.clone()).context("building download cache")?),
+            game_synchronizers: Arc::new(
+                get_game_file_source_synchronizers(games_config, &config.downloads_directory).context("building game file source synchronizers")?,
+            ),
             inner: DownloadersInner::new(config).context("building downloaders")?,
-            game_synchronizers: Arc::new(get_game_file_source_synchronizers(games_config).context("building game file source synchronizers")?),
         })
     }
 
-    pub async fn prepare_sync_task(self, Archive { descriptor, state }: Archive) -> Result<SyncTask> {
-        match state.clone() {
-            State::Nexus(NexusState {
-                game_name, file_id, mod_id, ..
-            }) => {
-                self.inner
-                    .nexus
-                    .clone()
-                    .context("nexus not configured")
-                    .pipe(ready)
-                    .and_then(|nexus| {
-                        nexus.download(nexus::DownloadFileRequest {
-                            // TODO: validate this
-                            game_domain_name: match game_name {
-                                NexusGameName::GameName(game_name) => game_name.to_string(),
-                                NexusGameName::Special(special) => match special {
-                                    crate::modlist_json::SpecialGameName::ModdingTools => "site".into(),
-                                    crate::modlist_json::SpecialGameName::FalloutNewVegas => "newvegas".into(),
-                                },
-                            },
-                            mod_id,
-                            file_id,
-                        })
-                    })
+    fn prepare_override_sync_task(&self, descriptor: ArchiveDescriptor, archive_override: ArchiveOverride) -> Result<SyncTask> {
+        let to = self.cache.download_output_path(descriptor.name.clone());
+        info!(name=%descriptor.name, ?archive_override, "using user-supplied archive override");
+        match archive_override {
+            ArchiveOverride::LocalPath { local_path } => local_path
+                .ne(&to)
+                .then(|| CopyFileTask {
+                    inner: (local_path.clone(), to),
+                    descriptor,
+                })
+                .with_context(|| format!("override [{}] points at the download cache path itself", local_path.display()))
+                .map(SyncTask::from),
+            ArchiveOverride::Url { url } => DownloadTask { inner: (url, to), descriptor }
+                .pipe(SyncTask::from)
+                .pipe(Ok),
+        }
+    }
+
+    pub async fn prepare_sync_task(self, archive: Archive) -> Result<SyncTask> {
+        let descriptor = archive.descriptor.clone();
+        match self.clone().prepare_original_sync_task(archive).await {
+            Ok(sync_task) => Ok(sync_task),
+            // a missing api key or a dropped connection is reported as it is, instead of being patched over
+            Err(reason) if Self::original_is_gone(&reason) => self.prepare_upgrade_sync_task(descriptor, reason).await,
+            Err(reason) => Err(reason),
+        }
+    }
+
+    /// wabbajack publishes patches rebuilding archives whose original version is no longer available out of their newer versions
+    async fn prepare_upgrade_sync_task(self, descriptor: ArchiveDescriptor, reason: anyhow::Error) -> Result<SyncTask> {
+        let Some(upgrades) = self.inner.upgrades.clone() else {
+            return Err(reason);
+        };
+        match upgrades.find_upgrade(&descriptor).await {
+            Ok(ArchiveUpgrade { replacement, patch_url }) => {
+                info!(
+                    original=%descriptor.name,
+                    replacement=%replacement.descriptor.name,
+                    "original archive is not available, it will be rebuilt out of its newer version"
+                );
+                self.clone()
+                    .prepare_original_sync_task(replacement)
                     .await
-                    .map(|url| DownloadTask {
-                        inner: (url, self.cache.download_output_path(descriptor.name.clone())),
+                    .map(|replacement| UpgradeTask {
+                        inner: (Box::new(replacement), patch_url, self.cache.download_output_path(descriptor.name.clone())),
                         descriptor,
                     })
                     .map(SyncTask::from)
+                    .context("preparing download of the replacement archive")
+                    .with_context(|| format!("original archive is not available: {reason:?}"))
             }
-            State::GoogleDrive(GoogleDriveState { id }) => crate::downloaders::google_drive::GoogleDriveDownloader::download(id, descriptor.size)
+            Err(upgrade_reason) => Err(reason).with_context(|| format!("no upgrade available: {upgrade_reason:#}")),
+        }
+    }
+
+    async fn prepare_ips4_sync_task(&self, site: &str, state: &Ips4OAuth2State, descriptor: ArchiveDescriptor) -> Result<SyncTask> {
+        self.inner
+            .ips4
+            .get(site)
+            .with_context(|| format!("[{site}] is not configured"))
+            .pipe(ready)
+            .and_then(|downloader| downloader.prepare_download(state))
+            .await
+            .map(|url| Ips4DownloadTask {
+                inner: (url, site.to_string(), self.cache.download_output_path(descriptor.name.clone())),
+                descriptor,
+            })
+            .map(SyncTask::from)
+            .with_context(|| {
+                format!(
+                    "Manual action is required:\n\nURL: {}\nlog in with `hoolamike ips4-login {site}` or download the file manually",
+                    state.ips4_url
+                )
+            })
+    }
+
+    async fn prepare_original_sync_task(self, Archive { descriptor, state }: Archive) -> Result<SyncTask> {
+        if let Some(archive_override) = self.config.archive_override(&descriptor).cloned() {
+            return self
+                .prepare_override_sync_task(descriptor, archive_override)
+                .with_context(|| format!("when preparing overridden download for\n{state:#?}"));
+        }
+        match state.clone() {
+            State::Nexus(NexusState {
+                game_name, file_id, mod_id, ..
+            }) => self
+                .inner
+                .nexus
+                .clone()
+                .context("nexus not configured")
+                .pipe(ready)
+                .and_then(|nexus| {
+                    nexus.download(nexus::DownloadFileRequest {
+                        game_domain_name: game_name.nexus_domain_or_guess(),
+                        mod_id,
+                        file_id,
+                    })
+                })
                 .await
                 .map(|url| DownloadTask {
                     inner: (url, self.cache.download_output_path(descriptor.name.clone())),
                     descriptor,
                 })
                 .map(SyncTask::from),
+            State::GoogleDrive(GoogleDriveState { id, .. }) => google_drive::GoogleDriveDownloader::download(id.clone(), descriptor.size)
+                .await
+                .map(|url| DownloadTask {
+                    inner: (url, self.cache.download_output_path(descriptor.name.clone())),
+                    descriptor,
+                })
+                .map(SyncTask::from)
+                .map_err(|reason| match reason.downcast_ref::<google_drive::response_parsing::GoogleDriveError>() {
+                    Some(_) => reason.context(format!(
+                        "Manual action is required:\n\nURL: {}\nGo to the website and download the file manually",
+                        google_drive::manual_download_url(&id)
+                    )),
+                    None => reason,
+                }),
             State::GameFileSource(state) => self
                 .game_synchronizers
-                .get(&state.game)
+                .pipe_ref(|synchronizers| find_game_file_source_synchronizer(synchronizers, &state.game))
                 .with_context(|| format!("check config, no game source configured for [{}]", state.game))
                 .pipe(ready)
                 .and_then(|synchronizer| synchronizer.prepare_copy(state))
@@ -272,7 +400,7 @@ impl Synchronizers {
                 })
                 .map(SyncTask::from),
 
-            State::Http(HttpState { url, headers: _ }) => url
+            State::Http(HttpState { url, .. }) => url
                 .pipe(|url| DownloadTask {
                     inner: (url, self.cache.download_output_path(descriptor.name.clone())),
                     descriptor,
@@ -287,11 +415,11 @@ impl Synchronizers {
                     descriptor,
                 })
                 .map(SyncTask::from),
-            State::Manual(ManualState { prompt, url }) => Err(anyhow::anyhow!("Manual action is required:\n\nURL: {url}\n{prompt}")),
-            State::Mega(MegaState { url }) => Err(anyhow::anyhow!(
+            State::Manual(ManualState { prompt, url, .. }) => Err(anyhow::anyhow!("Manual action is required:\n\nURL: {url}\n{prompt}")),
+            State::Mega(MegaState { url, .. }) => Err(anyhow::anyhow!(
                 "Manual action is required:\n\nURL: {url}\nMega is not supported (yet?), please download the file manually"
             )),
-            State::MediaFire(MediaFireState { url }) => {
+            State::MediaFire(MediaFireState { url, .. }) => {
                 // it cannot be done
                 MediaFireDownloader::download(url.clone())
                     .await
@@ -303,10 +431,152 @@ impl Synchronizers {
                     .map(SyncTask::from)
                     .with_context(|| format!("Manual action is required:\n\nURL: {url}\nGo to the website and download the file(s) manually"))
             }
+            State::GitHub(state) => self
+                .inner
+                .github
+                .prepare_download(&state)
+                .await
+                .map(|url| DownloadTask {
+                    inner: (url, self.cache.download_output_path(descriptor.name.clone())),
+                    descriptor,
+                })
+                .map(SyncTask::from),
+            State::LoversLab(state) => {
+                self.prepare_ips4_sync_task("loverslab", &state, descriptor)
+                    .await
+            }
+            State::VectorPlexus(state) => {
+                self.prepare_ips4_sync_task("vectorplexus", &state, descriptor)
+                    .await
+            }
+            State::ModDB(ModDBState { url, .. }) => ModDBDownloader::download(url.clone())
+                .await
+                .context("moddb")
+                .map(|mirrors| MirroredDownloadTask {
+                    inner: (mirrors, self.cache.download_output_path(descriptor.name.clone())),
+                    descriptor,
+                })
+                .map(SyncTask::from)
+                .with_context(|| format!("Manual action is required:\n\nURL: {url}\nGo to the website and download the file(s) manually")),
+            State::Unsupported(entry) => Err(anyhow::anyhow!(
+                "Manual action is required:\n\n[{}] is downloaded with [{}], which this version of hoolamike does not support, please download the file \
+                 manually\n{}",
+                descriptor.name,
+                entry.type_name(),
+                entry.state_rejection()
+            )),
         }
         .with_context(|| format!("when preparing download for\n{state:#?}"))
     }
 
+    fn execute_sync_task(self, sync_task: SyncTask, span: tracing::Span) -> BoxFuture<'static, Result<WithArchiveDescriptor<PathBuf>>> {
+        match sync_task {
+            SyncTask::MergeDownload(WithArchiveDescriptor { inner: (from, to), descriptor }) => stream_merge_file(from.clone(), to.clone(), descriptor.size)
+                .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
+                .map(move |res| res.with_context(|| format!("when downloading [{from:?} -> {to:?}]")))
+                .instrument(span)
+                .boxed(),
+            SyncTask::Download(WithArchiveDescriptor { inner: (from, to), descriptor }) => stream_file(from.clone(), to.clone(), descriptor.size)
+                .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
+                .map(move |res| res.with_context(|| format!("when downloading [{from} -> {to:?}]")))
+                .instrument(span)
+                .boxed(),
+            SyncTask::MirroredDownload(WithArchiveDescriptor {
+                inner: (mirrors, to),
+                descriptor,
+            }) => stream_file_from_mirrors(mirrors.clone(), to.clone(), descriptor.size)
+                .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
+                .map(move |res| res.with_context(|| format!("when downloading [{mirrors:?} -> {to:?}]")))
+                .instrument(span)
+                .boxed(),
+            SyncTask::Ips4Download(WithArchiveDescriptor {
+                inner: (from, site, to),
+                descriptor,
+            }) => {
+                let (url, output, expected_size) = (from.clone(), to.clone(), descriptor.size);
+                let (ips4, configured_site) = (self.inner.ips4.clone(), site.clone());
+                async move {
+                    let bearer_token = ips4
+                        .get(&configured_site)
+                        .with_context(|| format!("[{configured_site}] is not configured"))?
+                        .authorization_for(&url)
+                        .await?;
+                    stream_authorized_file(url, bearer_token, output, expected_size).await
+                }
+                .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
+                .map(move |res| res.with_context(|| format!("when downloading [{from} -> {to:?}] from [{site}]")))
+                .instrument(span)
+                .boxed()
+            }
+            SyncTask::Copy(WithArchiveDescriptor { inner: (from, to), descriptor }) => copy_local_file(from.clone(), to.clone(), descriptor.size)
+                .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
+                .map(move |res| res.with_context(|| format!("when when copying [{from:?} -> {to:?}]")))
+                .instrument(span)
+                .boxed(),
+            SyncTask::Upgrade(WithArchiveDescriptor {
+                inner: (replacement, patch_url, to),
+                descriptor,
+            }) => {
+                let name = descriptor.name.clone();
+                let upgrades = self.inner.upgrades.clone();
+                self.execute_sync_task(*replacement, span.clone())
+                    .and_then(move |replacement| async move {
+                        let patch = upgrades
+                            .context("archive upgrades are disabled")?
+                            .fetch_patch(patch_url)
+                            .await
+                            .context("fetching patch")?;
+                        tokio::task::spawn_blocking(move || {
+                            apply_patch(&replacement.inner, &patch, &to, descriptor.size).map(|inner| WithArchiveDescriptor { inner, descriptor })
+                        })
+                        .map_context("thread crashed")
+                        .and_then(ready)
+                        .await
+                    })
+                    .map(move |res| res.with_context(|| format!("when rebuilding [{name}] out of its newer version")))
+                    .instrument(span)
+                    .boxed()
+            }
+        }
+        .and_then(|WithArchiveDescriptor { inner, descriptor }| {
+            validate_hash(inner, descriptor.hash.clone())
+                .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
+                .map(|res| res.context("synchronized file does not match the modlist"))
+        })
+        .boxed()
+    }
+
+    /// upgrades are only worth it when the original is gone - transient failures (eg. a dropped connection) are reported as they are
+    pub(crate) fn original_is_gone(reason: &anyhow::Error) -> bool {
+        reason.chain().any(|cause| {
+            cause.is::<download_cache::HashMismatch>()
+                || cause
+                    .downcast_ref::<reqwest::Error>()
+                    .and_then(reqwest::Error::status)
+                    .is_some_and(|status| matches!(status, reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE))
+        })
+    }
+
+    fn execute_sync_task_with_upgrade_fallback(self, sync_task: SyncTask, span: tracing::Span) -> BoxFuture<'static, Result<WithArchiveDescriptor<PathBuf>>> {
+        let descriptor = sync_task.descriptor().clone();
+        let is_upgrade = matches!(sync_task, SyncTask::Upgrade(_));
+        self.clone()
+            .execute_sync_task(sync_task, span.clone())
+            .or_else(move |reason| async move {
+                match is_upgrade || !Self::original_is_gone(&reason) {
+                    true => Err(reason),
+                    false => {
+                        let upgrade = self
+                            .clone()
+                            .prepare_upgrade_sync_task(descriptor, reason)
+                            .await?;
+                        self.execute_sync_task(upgrade, span).await
+                    }
+                }
+            })
+            .boxed()
+    }
+
     #[instrument(skip_all, fields(archives=%archives.len()))]
     pub async fn sync_downloads(self, archives: Vec<Archive>) -> TotalResult<WithArchiveDescriptor<PathBuf>> {
         let base_concurrency = 7;
@@ -345,37 +615,18 @@ impl Synchronizers {
             .collect::<Vec<_>>()
             .await
             .pipe(futures::stream::iter)
+            .take_while(|_| ready(!shutdown::requested()))
             .map_ok(|file| {
                 let name = match &file {
                     Either::Left(left) => left.descriptor.name.clone(),
-                    Either::Right(right) => match right {
-                        SyncTask::MergeDownload(d) => d.descriptor.name.clone(),
-                        SyncTask::Download(d) => d.descriptor.name.clone(),
-                        SyncTask::Copy(d) => d.descriptor.name.clone(),
-                    },
+                    Either::Right(right) => right.descriptor().name.clone(),
                 };
 
                 match file {
                     Either::Left(exists) => exists.pipe(Ok).pipe(ready).boxed(),
-                    Either::Right(sync_task) => match sync_task {
-                        SyncTask::MergeDownload(WithArchiveDescriptor { inner: (from, to), descriptor }) => {
-                            stream_merge_file(from.clone(), to.clone(), descriptor.size)
-                                .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
-                                .map(move |res| res.with_context(|| format!("when downloading [{from:?} -> {to:?}]")))
-                                .instrument(sync_downloads.clone())
-                                .boxed()
-                        }
-                        SyncTask::Download(WithArchiveDescriptor { inner: (from, to), descriptor }) => stream_file(from.clone(), to.clone(), descriptor.size)
-                            .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
-                            .map(move |res| res.with_context(|| format!("when downloading [{from} -> {to:?}]")))
-                            .instrument(sync_downloads.clone())
-                            .boxed(),
-                        SyncTask::Copy(WithArchiveDescriptor { inner: (from, to), descriptor }) => copy_local_file(from.clone(), to.clone(), descriptor.size)
-                            .map_ok(|inner| WithArchiveDescriptor { inner, descriptor })
-                            .map(move |res| res.with_context(|| format!("when when copying [{from:?} -> {to:?}]")))
-                            .instrument(sync_downloads.clone())
-                            .boxed(),
-                    },
+                    Either::Right(sync_task) => self
+                        .clone()
+                        .execute_sync_task_with_upgrade_fallback(sync_task, sync_downloads.clone()),
                 }
                 .inspect_err({
                     let name = name.clone();
@@ -0,0 +1,284 @@
+//! checks the files a modlist copies out of the game directory before anything is downloaded,
+//! so that a wrong game version shows up as a single report instead of a hash mismatch deep inside the downloads.
+//! the report is only a warning, renamed or moved game files can still be found through the game hash index
+use {
+    super::download_cache::file_hash,
+    crate::{
+        config_file::GamesConfig,
+        modlist_json::{GameFileSourceState, GameName},
+        path_resolver,
+    },
+    anyhow::Context,
+    futures::{StreamExt, TryFutureExt},
+    indexmap::IndexMap,
+    itertools::Itertools,
+    std::{
+        fmt::{self, Display},
+        path::{Path, PathBuf},
+    },
+    tap::prelude::*,
+    tracing::{info, instrument, warn},
+};
+
+/// how many paths are listed in the report before it's cut short
+const LISTED_PATHS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamePreflight {
+    pub game: GameName,
+    pub game_directory: PathBuf,
+    /// versions the modlist author had installed, usually just one
+    pub expected_versions: Vec<String>,
+    /// read from the main executable, [None] when it could not be determined
+    pub installed_version: Option<String>,
+    pub checked: usize,
+    pub differing: Vec<PathBuf>,
+    pub missing: Vec<PathBuf>,
+}
+
+impl GamePreflight {
+    pub fn is_ok(&self) -> bool {
+        self.differing.is_empty() && self.missing.is_empty()
+    }
+}
+
+fn list_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .take(LISTED_PATHS)
+        .map(|path| format!("\n  - {}", path.display()))
+        .chain((paths.len() > LISTED_PATHS).then(|| format!("\n  - ... and {} more", paths.len() - LISTED_PATHS)))
+        .join("")
+}
+
+impl Display for GamePreflight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = self.expected_versions.iter().join(", ");
+        let installed = self.installed_version.as_deref().unwrap_or("unknown");
+        if self.is_ok() {
+            return write!(f, "[{}] matches expected version {expected} ({} files checked)", self.game, self.checked);
+        }
+        if !self.differing.is_empty() {
+            write!(
+                f,
+                "[{}] is version {installed}, but the modlist expects version {expected}, these {} files differ:{}",
+                self.game,
+                self.differing.len(),
+                list_paths(&self.differing)
+            )?;
+        }
+        if !self.missing.is_empty() {
+            if !self.differing.is_empty() {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "[{}] {} files are missing from [{}] (Creation Club content? wrong edition?):{}",
+                self.game,
+                self.missing.len(),
+                self.game_directory.display(),
+                list_paths(&self.missing)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `VS_FIXEDFILEINFO` is located by its signature instead of walking the resource directory of the executable
+pub fn read_file_version(executable: &[u8]) -> Option<String> {
+    const SIGNATURE: [u8; 4] = 0xFEEF04BDu32.to_le_bytes();
+    let u32_at = |offset: usize| -> Option<u32> {
+        executable
+            .get(offset..offset + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u32::from_le_bytes)
+    };
+    executable
+        .windows(SIGNATURE.len())
+        .position(|window| window == SIGNATURE)
+        // signature, struct version, then the most and least significant halves of the file version
+        .and_then(|start| u32_at(start + 8).zip(u32_at(start + 12)))
+        .map(|(most, least)| format!("{}.{}.{}.{}", most >> 16, most & 0xFFFF, least >> 16, least & 0xFFFF))
+}
+
+fn installed_version(game: &GameName, game_directory: &Path) -> Option<String> {
+    game.game()
+        .ok()
+        .and_then(|game| game.main_executable(game_directory))
+        .filter(|executable| executable.exists())
+        .and_then(|executable| {
+            std::fs::read(&executable)
+                .with_context(|| format!("reading [{}]", executable.display()))
+                .tap_err(|reason| warn!("could not read game version: {reason:?}"))
+                .ok()
+        })
+        .and_then(|executable| read_file_version(&executable))
+}
+
+enum FileStatus {
+    Matches,
+    Differs(PathBuf),
+    Missing(PathBuf),
+}
+
+async fn check_file(game_directory: PathBuf, GameFileSourceState { hash, game_file, .. }: GameFileSourceState) -> FileStatus {
+    let relative = game_file.into_path();
+    let path = path_resolver::existing_path(&game_directory, &relative);
+    match tokio::fs::try_exists(&path).await {
+        Ok(true) => match file_hash(path)
+            .await
+            .tap_err(|reason| warn!("could not hash [{}]: {reason:?}", relative.display()))
+        {
+            Ok(found) if found == hash => FileStatus::Matches,
+            _ => FileStatus::Differs(relative),
+        },
+        _ => FileStatus::Missing(relative),
+    }
+}
+
+/// games that are not configured are skipped, setting up the downloaders reports those
+#[instrument(skip_all)]
+pub async fn check_game_files(games: &GamesConfig, states: impl IntoIterator<Item = GameFileSourceState>) -> Vec<GamePreflight> {
+    let by_game = states
+        .into_iter()
+        .fold(IndexMap::<GameName, Vec<GameFileSourceState>>::new(), |acc, state| {
+            acc.tap_mut(|acc| acc.entry(state.game.clone()).or_default().push(state))
+        });
+    futures::stream::iter(by_game)
+        .filter_map(|(game, states)| async move {
+            let game_directory = games
+                .iter()
+                .find(|(configured, _)| configured.is_same_game(&game))
+                .map(|(_, config)| config.root_directory.clone())?;
+            let expected_versions = states
+                .iter()
+                .map(|state| state.game_version.clone())
+                .unique()
+                .collect_vec();
+            let checked = states.len();
+            let statuses = futures::stream::iter(states)
+                .map(|state| check_file(game_directory.clone(), state))
+                .buffer_unordered(num_cpus::get())
+                .collect::<Vec<_>>()
+                .await;
+            let installed_version = tokio::task::spawn_blocking({
+                let game = game.clone();
+                let game_directory = game_directory.clone();
+                move || installed_version(&game, &game_directory)
+            })
+            .unwrap_or_else(|_| None)
+            .await;
+            let (differing, missing) = statuses
+                .into_iter()
+                .fold((vec![], vec![]), |(differing, missing), status| match status {
+                    FileStatus::Matches => (differing, missing),
+                    FileStatus::Differs(path) => (differing.tap_mut(|d| d.push(path)), missing),
+                    FileStatus::Missing(path) => (differing, missing.tap_mut(|m| m.push(path))),
+                });
+            Some(GamePreflight {
+                game,
+                game_directory,
+                expected_versions,
+                installed_version,
+                checked,
+                differing: differing.tap_mut(|d| d.sort()),
+                missing: missing.tap_mut(|m| m.sort()),
+            })
+        })
+        .collect()
+        .await
+}
+
+/// warns once per game that does not match what the modlist expects, the mismatching reports are returned
+pub async fn preflight(games: &GamesConfig, states: impl IntoIterator<Item = GameFileSourceState>) -> Vec<GamePreflight> {
+    check_game_files(games, states)
+        .await
+        .into_iter()
+        .filter(|report| match report.is_ok() {
+            true => {
+                info!("{report}");
+                false
+            }
+            false => {
+                warn!("{report}\nthe downloads will look for these files by their contents before giving up");
+                true
+            }
+        })
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{config_file::GameConfig, utils::MaybeWindowsPath},
+        anyhow::Result,
+    };
+
+    #[test]
+    fn test_read_file_version() {
+        let executable = [
+            b"MZ garbage".as_slice(),
+            &0xFEEF04BDu32.to_le_bytes(),
+            &0x00010000u32.to_le_bytes(),
+            &0x00010006u32.to_le_bytes(),
+            &0x04920000u32.to_le_bytes(),
+        ]
+        .concat();
+        assert_eq!(read_file_version(&executable).as_deref(), Some("1.6.1170.0"));
+        assert_eq!(read_file_version(b"MZ no version here"), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_game_files() -> Result<()> {
+        let game_directory = tempfile::tempdir()?;
+        std::fs::create_dir_all(game_directory.path().join("Data"))?;
+        std::fs::write(game_directory.path().join("Data/Skyrim.esm"), b"expected")?;
+        std::fs::write(game_directory.path().join("Data/Update.esm"), b"older version")?;
+        let expected_hash = file_hash(game_directory.path().join("Data/Skyrim.esm")).await?;
+        let game = GameName::new("SkyrimSpecialEdition".to_string());
+        let state = |game_file: &str| GameFileSourceState {
+            game_version: "1.6.1170.0".to_string(),
+            hash: expected_hash.clone(),
+            game_file: MaybeWindowsPath(game_file.to_string()),
+            game: game.clone(),
+            extra: Default::default(),
+        };
+        let games = GamesConfig::from_iter([(
+            game.clone(),
+            GameConfig {
+                root_directory: game_directory.path().to_owned(),
+            },
+        )]);
+
+        let reports = check_game_files(&games, [state("Data\\Skyrim.esm")]).await;
+        assert!(reports.iter().all(GamePreflight::is_ok));
+        assert_eq!(reports.len(), 1);
+
+        let reports = check_game_files(
+            &games,
+            [state("Data\\Skyrim.esm"), state("Data\\Update.esm"), state("Data\\ccBGSSSE001-Fish.esm")],
+        )
+        .await;
+        assert_eq!(
+            reports,
+            [GamePreflight {
+                game: game.clone(),
+                game_directory: game_directory.path().to_owned(),
+                expected_versions: vec!["1.6.1170.0".to_string()],
+                installed_version: None,
+                checked: 3,
+                differing: vec![PathBuf::from("Data/Update.esm")],
+                missing: vec![PathBuf::from("Data/ccBGSSSE001-Fish.esm")],
+            }]
+        );
+        assert_eq!(preflight(&games, [state("Data\\Update.esm")]).await.len(), 1);
+        assert!(preflight(&games, [state("Data\\Skyrim.esm")])
+            .await
+            .is_empty());
+        assert!(check_game_files(&GamesConfig::new(), [state("Data\\Skyrim.esm")])
+            .await
+            .is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+//! advisory locks on the installation and downloads directories, two installs racing on the same outputs corrupt both
+use {
+    anyhow::{Context, Result},
+    chrono::{DateTime, Utc},
+    fs2::FileExt,
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{self, Display},
+        fs::File,
+        io::{Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+    },
+    tap::prelude::*,
+    tracing::{debug, warn},
+};
+
+pub const LOCK_FILE_NAME: &str = ".hoolamike.lock";
+
+/// written into the lock file so that whoever runs into the lock knows what is holding it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub host: String,
+    pub started_at: DateTime<Utc>,
+}
+
+fn current_host() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+impl LockOwner {
+    pub fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: current_host(),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// [None] when it can't be told, e.g. the owner is on another host
+    pub fn is_alive(&self) -> Option<bool> {
+        (self.host == current_host() && cfg!(target_os = "linux")).then(|| Path::new("/proc").join(self.pid.to_string()).exists())
+    }
+}
+
+impl Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pid {} on [{}], started at {}", self.pid, self.host, self.started_at.to_rfc3339())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InstallLockError {
+    #[error("[{path}] is locked by another hoolamike process ({owner}), wait for it to finish or stop it")]
+    Held { path: PathBuf, owner: LockOwner },
+    #[error("[{path}] is locked by another process, but it did not say which one")]
+    HeldByUnknown { path: PathBuf },
+}
+
+/// released when dropped, the lock file itself stays around but is emptied
+#[derive(Debug)]
+pub struct InstallLock {
+    path: PathBuf,
+    file: File,
+}
+
+fn read_owner(file: &mut File) -> Result<Option<LockOwner>> {
+    String::new()
+        .pipe(|mut contents| {
+            file.seek(SeekFrom::Start(0))
+                .and_then(|_| file.read_to_string(&mut contents))
+                .map(|_| contents)
+        })
+        .context("reading lock file")
+        .map(|contents| match contents.trim().is_empty() {
+            true => None,
+            // a half written lock file is as good as an empty one
+            false => serde_json::from_str(&contents)
+                .tap_err(|e| debug!(?e, "lock file is not readable"))
+                .ok(),
+        })
+}
+
+impl InstallLock {
+    #[tracing::instrument]
+    pub fn acquire(directory: &Path) -> Result<Self> {
+        std::fs::create_dir_all(directory).with_context(|| format!("creating [{}]", directory.display()))?;
+        let path = directory.join(LOCK_FILE_NAME);
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("opening lock file at [{}]", path.display()))?;
+        if let Err(e) = FileExt::try_lock_exclusive(&file) {
+            debug!(?e, "could not lock");
+            return read_owner(&mut file)
+                .unwrap_or_default()
+                .map_or_else(
+                    || InstallLockError::HeldByUnknown { path: path.clone() },
+                    |owner| InstallLockError::Held { path: path.clone(), owner },
+                )
+                .pipe(anyhow::Error::from)
+                .pipe(Err);
+        }
+        // the lock is released by the os when a process dies, whatever is still written in there is stale
+        if let Some(stale) = read_owner(&mut file)? {
+            warn!(
+                "taking over the lock at [{}] left behind by a run that did not exit cleanly ({stale}, {})",
+                path.display(),
+                match stale.is_alive() {
+                    Some(true) => "still running, but no longer holding the lock",
+                    Some(false) => "no longer running",
+                    None => "state unknown",
+                }
+            );
+        }
+        serde_json::to_string(&LockOwner::current())
+            .context("serializing lock owner")
+            .and_then(|owner| {
+                file.set_len(0)
+                    .and_then(|_| file.seek(SeekFrom::Start(0)))
+                    .and_then(|_| file.write_all(owner.as_bytes()))
+                    .and_then(|_| file.sync_data())
+                    .context("writing lock file")
+            })
+            .map(|_| Self { path, file })
+            .with_context(|| format!("locking [{}]", directory.display()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for InstallLock {
+    fn drop(&mut self) {
+        if let Err(e) = self
+            .file
+            .set_len(0)
+            .and_then(|_| FileExt::unlock(&self.file))
+        {
+            warn!(?e, "could not release lock at [{}]", self.path.display());
+        }
+    }
+}
+
+/// locks every distinct directory, the installation and downloads can be the same one
+pub fn lock_directories<'a>(directories: impl IntoIterator<Item = &'a Path>) -> Result<Vec<InstallLock>> {
+    directories
+        .into_iter()
+        .map(|directory| {
+            std::fs::create_dir_all(directory)
+                .and_then(|_| directory.canonicalize())
+                .with_context(|| format!("resolving [{}]", directory.display()))
+        })
+        .collect::<Result<std::collections::BTreeSet<_>>>()?
+        .into_iter()
+        .map(|directory| InstallLock::acquire(&directory))
+        .collect::<Result<Vec<_>>>()
+        .tap_ok(|locks| {
+            locks
+                .iter()
+                .for_each(|lock| debug!(path=%lock.path().display(), "locked"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lock_is_refused() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let lock = InstallLock::acquire(directory.path())?;
+        let error = InstallLock::acquire(directory.path()).unwrap_err();
+        match error.downcast_ref::<InstallLockError>() {
+            Some(InstallLockError::Held { owner, .. }) => assert_eq!(owner.pid, std::process::id()),
+            other => panic!("unexpected error: {other:?}"),
+        }
+        drop(lock);
+        InstallLock::acquire(directory.path()).map(|_| ())
+    }
+
+    #[test]
+    fn test_stale_lock_is_taken_over() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        LockOwner {
+            pid: u32::MAX,
+            host: "elsewhere".to_string(),
+            started_at: Utc::now(),
+        }
+        .pipe_ref(serde_json::to_string)?
+        .pipe(|stale| std::fs::write(directory.path().join(LOCK_FILE_NAME), stale))?;
+        let _lock = InstallLock::acquire(directory.path())?;
+        let owner: LockOwner = serde_json::from_str(&std::fs::read_to_string(directory.path().join(LOCK_FILE_NAME))?)?;
+        assert_eq!(owner.pid, std::process::id());
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_directory_is_locked_once() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let locks = lock_directories([directory.path(), directory.path().join(".").as_path()])?;
+        assert_eq!(locks.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+//! runs a single directive into a scratch directory, keeping everything it reads and writes around for inspection
+use {
+    super::{
+        directives::{
+            preheat_archive_hash_paths::PreheatedArchiveHashPaths,
+            wabbajack_file_handle::WabbajackFileHandle,
+            DirectivesHandler,
+            DirectivesHandlerConfig,
+            ResolvePathExt,
+        },
+        download_cache::file_hash,
+        downloads::Synchronizers,
+        install_lock,
+        required_games,
+    },
+    crate::{
+        config_file::{HoolamikeConfig, InstallationConfig},
+        game_locator,
+        helpers::human_readable_size,
+        modlist_json::{
+            consistency::{bsa_files, bsa_staging_directory},
+            Directive,
+            DirectiveKind,
+            Modlist,
+        },
+        path_resolver,
+        utils::{abandoned_output_path, spawn_rayon, MaybeWindowsPath},
+        wabbajack_file::WabbajackFile,
+    },
+    anyhow::{Context, Result},
+    itertools::Itertools,
+    std::{
+        collections::BTreeSet,
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+    tap::prelude::*,
+    tracing::info,
+};
+
+/// indices of the directive and, when it packs a BSA, of the directives staging its files (those go first)
+fn directives_to_run(modlist: &Modlist, directive_hash: &str) -> Result<Vec<usize>> {
+    let (index, directive) = modlist
+        .directives
+        .iter()
+        .enumerate()
+        .find(|(_, directive)| directive.directive_hash() == directive_hash)
+        .with_context(|| format!("no directive with hash [{directive_hash}] in the modlist"))?;
+    Ok(match directive {
+        Directive::CreateBSA(create_bsa) => {
+            let (temp_id, _) = bsa_files(create_bsa);
+            let staging = format!("{}/", bsa_staging_directory(temp_id));
+            modlist
+                .directives
+                .iter()
+                .enumerate()
+                .filter(|(_, staged)| {
+                    staged
+                        .to()
+                        .is_some_and(|to| to.normalized().starts_with(&staging))
+                })
+                .map(|(index, _)| index)
+                .chain(std::iter::once(index))
+                .collect()
+        }
+        _ => vec![index],
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// copies of what the directive reads - the extracted source and the data stored in the .wabbajack file
+fn dump_inputs(
+    directive: &Directive,
+    handler: &DirectivesHandler,
+    preheated: &PreheatedArchiveHashPaths,
+    wabbajack_file: &WabbajackFileHandle,
+    to: &Path,
+) -> Result<Vec<(&'static str, PathBuf)>> {
+    std::fs::create_dir_all(to).with_context(|| format!("creating [{}]", to.display()))?;
+    let source = directive
+        .archive_hash_path()
+        .map(|archive_hash_path| {
+            let resolved = handler
+                .download_summary
+                .resolve_archive_path(archive_hash_path)?;
+            let dump = to.join(format!("source-{}", file_name(resolved.last())));
+            preheated
+                .get_archive(resolved)
+                .and_then(|source| std::fs::copy(&*source, &dump).context("copying extracted source"))
+                .with_context(|| format!("dumping source of [{archive_hash_path:?}]"))
+                .map(|_| {
+                    let label = match directive.directive_kind() {
+                        DirectiveKind::TransformedTexture => "source (before recompression)",
+                        _ => "source",
+                    };
+                    (label, dump)
+                })
+        })
+        .transpose()?;
+    let data = directive
+        .wabbajack_entry_id()
+        .map(|id| {
+            let label = match directive.directive_kind() {
+                DirectiveKind::PatchedFromArchive => "patch",
+                _ => "inline data",
+            };
+            let dump = to.join(format!("{}-{}", label.replace(' ', "-"), id.as_hyphenated()));
+            wabbajack_file
+                .copy_source_data(id, &dump)
+                .map(|_| (label, dump))
+        })
+        .transpose()?;
+    Ok(source.into_iter().chain(data).collect())
+}
+
+async fn handle(handler: &DirectivesHandler, preheated: &Arc<PreheatedArchiveHashPaths>, directive: Directive) -> Result<u64> {
+    match directive {
+        Directive::CreateBSA(d) => handler.create_bsa.clone().handle(Arc::new(d)).await,
+        Directive::FromArchive(d) => {
+            handler
+                .from_archive
+                .clone()
+                .handle(d, preheated.clone())
+                .await
+        }
+        Directive::InlineFile(d) => handler.inline_file.clone().handle(d).await,
+        Directive::PatchedFromArchive(d) => {
+            handler
+                .patched_from_archive
+                .clone()
+                .handle(d, preheated.clone())
+                .await
+        }
+        Directive::RemappedInlineFile(d) => handler.remapped_inline_file.clone().handle(d).await,
+        Directive::TransformedTexture(d) => {
+            handler
+                .transformed_texture
+                .clone()
+                .handle(d, preheated.clone())
+                .await
+        }
+        Directive::Unsupported(entry) => anyhow::bail!("[{}] directives are not supported", entry.type_name()),
+    }
+}
+
+/// hash and size of whatever the directive left behind, finished or not
+async fn actual_output(output_directory: &Path, to: &MaybeWindowsPath) -> Option<(PathBuf, String, u64)> {
+    let output = path_resolver::existing_path(output_directory, &to.clone().into_path());
+    let output = match output.exists() {
+        true => output,
+        false => abandoned_output_path(&output),
+    };
+    let size = std::fs::metadata(&output).ok()?.len();
+    let hash = file_hash(output.clone())
+        .await
+        .tap_err(|e| tracing::warn!("hashing [{}]: {e:?}", output.display()))
+        .ok()?;
+    Some((output, hash, size))
+}
+
+/// resolves only what the directive needs and runs it on its own, every intermediate file is kept under `scratch_directory`
+pub async fn run_directive(
+    HoolamikeConfig {
+        downloaders,
+        installation: InstallationConfig {
+            wabbajack_file_path,
+            installation_path: _,
+        },
+        games,
+        fixup: _,
+        extras: _,
+    }: HoolamikeConfig,
+    directive_hash: String,
+    scratch_directory: PathBuf,
+) -> Result<()> {
+    // the downloads are shared with installations, which must not sync them at the same time
+    let _lock = install_lock::lock_directories([downloaders.downloads_directory.as_path()])
+        .context("an installation seems to be running on the same downloads directory")?;
+    let (_, modlist) = spawn_rayon({
+        let wabbajack_file_path = wabbajack_file_path.clone();
+        move || WabbajackFile::read_modlist(&wabbajack_file_path)
+    })
+    .await
+    .context("reading modlist")?;
+    let to_run = directives_to_run(&modlist, &directive_hash)?;
+    let games = game_locator::fill_missing_games(games, required_games(&modlist), game_locator::locate_installed_games);
+    let Modlist {
+        archives,
+        directives,
+        game_type,
+        ..
+    } = modlist;
+    let mut directives = directives.into_iter().map(Some).collect_vec();
+    let directives = to_run
+        .iter()
+        .map(|index| (*index, directives[*index].take().expect("indices are unique")))
+        .collect_vec();
+    let source_hashes = directives
+        .iter()
+        .filter_map(|(_, directive)| directive.archive_hash_path())
+        .map(|path| path.source_hash.clone())
+        .collect::<BTreeSet<_>>();
+    let entry_ids = directives
+        .iter()
+        .filter_map(|(_, directive)| directive.wabbajack_entry_id())
+        .collect_vec();
+    let archives = archives
+        .into_iter()
+        .filter(|archive| source_hashes.contains(&archive.descriptor.hash))
+        .collect_vec();
+    info!(directives = directives.len(), archives = archives.len(), "running directive [{directive_hash}]");
+
+    let game_directory = games
+        .get(&game_type)
+        .with_context(|| format!("[{game_type}] not found in {:?}", games.keys().collect::<Vec<_>>()))?
+        .root_directory
+        .clone();
+    let summary = Synchronizers::new(downloaders.clone(), games.clone())
+        .context("setting up downloaders")?
+        .sync_downloads(archives)
+        .await
+        .map_err(|errors| {
+            errors
+                .iter()
+                .enumerate()
+                .for_each(|(idx, reason)| tracing::error!("{idx}. {reason:?}", idx = idx + 1));
+            anyhow::anyhow!("could not get [{}] archives the directive reads from", errors.len())
+        })?;
+    let wabbajack_file = spawn_rayon(move || WabbajackFileHandle::from_archive_entries(wabbajack_file_path, &entry_ids))
+        .await
+        .context("reading directive data from the .wabbajack file")?;
+
+    let output_directory = scratch_directory.join("output");
+    let intermediate_directory = scratch_directory.join("intermediate");
+    let handler = DirectivesHandler::new(
+        DirectivesHandlerConfig {
+            wabbajack_file: wabbajack_file.clone(),
+            output_directory: output_directory.clone(),
+            game_directory,
+            downloads_directory: downloaders.downloads_directory.clone(),
+            // failed outputs are what's interesting here
+            keep_abandoned_outputs: true,
+        },
+        summary,
+    );
+    let paths = directives
+        .iter()
+        .filter_map(|(_, directive)| directive.archive_hash_path())
+        .map(|path| handler.download_summary.resolve_archive_path(path))
+        .collect::<Result<Vec<_>>>()?;
+    let preheated = tokio::task::spawn_blocking(move || PreheatedArchiveHashPaths::preheat_archive_hash_paths(paths))
+        .await
+        .context("thread crashed")?
+        .context("extracting source files")?
+        .pipe(Arc::new);
+
+    let mut target_result = Ok(0);
+    for (index, directive) in directives {
+        let is_target = index == *to_run.last().expect("the directive itself is always run");
+        let (kind, expected_hash, expected_size, to) = (
+            directive.directive_kind(),
+            directive.hash().map(str::to_owned).unwrap_or_default(),
+            directive.size(),
+            directive
+                .to()
+                .cloned()
+                .unwrap_or_else(|| MaybeWindowsPath(String::new())),
+        );
+        let dumps = dump_inputs(
+            &directive,
+            &handler,
+            &preheated,
+            &wabbajack_file,
+            &intermediate_directory.join(index.to_string()),
+        )?;
+        let result = handle(&handler, &preheated, directive).await;
+        let actual = actual_output(&output_directory, &to).await;
+
+        println!("#{index} {kind} -> {to}");
+        dumps
+            .iter()
+            .for_each(|(label, path)| println!("    {label}: {}", path.display()));
+        match &actual {
+            Some((output, hash, size)) => {
+                let label = match kind {
+                    DirectiveKind::TransformedTexture => "output (after recompression)",
+                    _ => "output",
+                };
+                println!("    {label}: {}", output.display());
+                println!(
+                    "    hash: expected [{expected_hash}], got [{hash}]{}",
+                    if *hash == expected_hash { "" } else { " MISMATCH" }
+                );
+                println!(
+                    "    size: expected [{}], got [{}]{}",
+                    human_readable_size(expected_size),
+                    human_readable_size(*size),
+                    if *size == expected_size { "" } else { " MISMATCH" }
+                );
+            }
+            None => println!("    output: nothing was written"),
+        }
+        if let Err(e) = &result {
+            println!("    error: {e:?}");
+        }
+        if is_target {
+            target_result = result;
+        }
+    }
+    target_result
+        .with_context(|| format!("running directive [{directive_hash}]"))
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::modlist_json::test_fixtures::{create_bsa, from_archive, modlist, ARCHIVE_HASH},
+    };
+
+    #[test]
+    fn test_bsa_directives_run_after_their_staged_files() -> Result<()> {
+        let modlist = modlist(
+            vec![],
+            vec![
+                from_archive("TEMP_BSA_FILES\\bsa-1\\textures\\pebble.dds", ARCHIVE_HASH, "textures\\pebble.dds"),
+                from_archive("TEMP_BSA_FILES\\bsa-10\\textures\\rock.dds", ARCHIVE_HASH, "textures\\rock.dds"),
+                create_bsa("mods\\Rocks\\Rocks.bsa", "bsa-1", &["textures\\pebble.dds"]),
+            ],
+        );
+        let hash = |index: usize| modlist.directives[index].directive_hash();
+        assert_eq!(directives_to_run(&modlist, &hash(2))?, [0, 2]);
+        assert_eq!(directives_to_run(&modlist, &hash(1))?, [1]);
+        assert!(directives_to_run(&modlist, "nope").is_err());
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@
 use {
     anyhow::{Context, Result},
     clap::{Args, Parser, Subcommand, ValueEnum},
-    modlist_data::ModlistSummary,
+    modlist_data::ModlistReport,
     modlist_json::DirectiveKind,
     num::ToPrimitive,
     std::{ops::Div, path::PathBuf, str::FromStr},
@@ -47,11 +47,25 @@ pub struct DebugHelpers {
     skip_kind: Vec<DirectiveKind>,
     #[arg(long)]
     contains: Vec<String>,
+    /// skip directives and downloads this version of hoolamike does not understand, instead of refusing to install
+    #[arg(long)]
+    allow_unsupported: bool,
 }
 
 #[derive(Subcommand)]
 enum HoolamikeDebugCommand {
-    ReserializeDirectives { modlist_file: PathBuf },
+    ReserializeDirectives {
+        modlist_file: PathBuf,
+    },
+    #[command(flatten)]
+    WabbajackFile(debug_cli::WabbajackFileCommand),
+    /// runs a single directive of the configured modlist, keeping the extracted source, patch and output around
+    RunDirective {
+        /// the same hash `--start-from-directive` takes
+        directive_hash: String,
+        #[arg(long, default_value = "run-directive")]
+        scratch_directory: PathBuf,
+    },
 }
 
 #[derive(Args)]
@@ -76,15 +90,17 @@ enum Commands {
         at_path: PathBuf,
     },
     HoolamikeDebug(HoolamikeDebug),
-    /// tests the modlist parser
+    /// checks that the modlist parses and agrees with itself, exits with an error when it does not
     ValidateModlist {
-        /// path to modlist (.wabbajack) file
+        /// path to modlist (.wabbajack) file, or the bare modlist json (wabbajack entries are not checked then)
         path: PathBuf,
     },
     /// prints information about the modlist
     ModlistInfo {
         /// path to modlist (.wabbajack) file
         path: PathBuf,
+        #[arg(long, value_enum, default_value_t = Default::default())]
+        format: modlist_data::ReportFormat,
     },
     Install {
         #[command(flatten)]
@@ -92,11 +108,18 @@ enum Commands {
     },
     /// prints default config. save it and modify to your liking
     PrintDefaultConfig,
+    /// logs in to an invision community (IPS4) site such as loverslab, the token is stored in `downloaders.ips4.token_directory`
+    Ips4Login {
+        /// site name as configured in `downloaders.ips4.sites`
+        site: String,
+    },
     /// runs post-install fixup - wouldn't be possible without extensive research done by Omni
     /// make sure to star his repo: https://github.com/Omni-guides/Wabbajack-Modlist-Linux
     PostInstallFixup,
     /// exposes the bare archive handling functionality used in hoolamike, useful for debugging
     Archive(archive_cli::ArchiveCliCommand),
+    /// inspects the modlist contents
+    Modlist(modlist_cli::ModlistCliCommand),
     Audio(audio_cli::AudioCliCommand),
 }
 
@@ -108,15 +131,22 @@ pub mod archive_cli;
 pub mod audio_cli;
 pub mod compression;
 pub mod config_file;
+pub mod debug_cli;
 pub mod downloaders;
 pub mod error;
+pub mod game_locator;
+pub mod games;
 pub mod helpers;
 pub mod install_modlist;
+pub mod modlist_cli;
 pub mod modlist_data;
 pub mod modlist_json;
 pub mod octadiff_reader;
+pub mod path_resolver;
 pub mod post_install_fixup;
 pub mod progress_bars_v2;
+pub mod safe_path;
+pub mod shutdown;
 pub mod wabbajack_file;
 
 /// non-wabbajack extensions will go here
@@ -221,19 +251,42 @@ async fn async_main() -> Result<()> {
             let (_config_path, config) = config_file::HoolamikeConfig::find(&hoolamike_config).context("reading hoolamike config file")?;
             post_install_fixup::run_post_install_fixup(&config)
         }
-        Commands::ValidateModlist { path } => tokio::fs::read_to_string(&path)
-            .await
-            .context("reading test file")
-            .and_then(|input| modlist_json::parsing_helpers::validate_modlist_file(&input))
-            .with_context(|| format!("testing file {}", path.display())),
-        Commands::ModlistInfo { path } => wabbajack_file::WabbajackFile::load_wabbajack_file(path)
+        Commands::ValidateModlist { path } => match path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("wabbajack"))
+        {
+            true => wabbajack_file::WabbajackFile::read_modlist(&path).map(|(entries, modlist)| (Some(entries), modlist)),
+            false => std::fs::File::open(&path)
+                .context("opening test file")
+                .and_then(modlist_json::parsing_helpers::validate_modlist_file)
+                .map(|modlist| (None, modlist)),
+        }
+        .and_then(|(entries, modlist)| {
+            println!("{}", modlist_json::unsupported::UnsupportedReport::new(&modlist));
+            let consistency = modlist_json::consistency::ConsistencyReport::new(&modlist, entries.as_deref());
+            println!("{consistency}");
+            anyhow::ensure!(
+                consistency.is_ok(),
+                "modlist is not consistent, found [{}] problems",
+                consistency.problem_count()
+            );
+            Ok(())
+        })
+        .with_context(|| format!("testing file {}", path.display())),
+        Commands::ModlistInfo { path, format } => wabbajack_file::WabbajackFile::read_modlist(&path)
             .context("reading modlist")
-            .map(|(_, modlist)| ModlistSummary::new(&modlist.modlist))
-            .map(|modlist| modlist.print())
-            .map(|modlist| println!("\n{modlist}")),
+            .map(|(_, modlist)| ModlistReport::new(&modlist))
+            .and_then(|report| report.render(format))
+            .map(|report| println!("{report}")),
         Commands::PrintDefaultConfig => config_file::HoolamikeConfig::default()
             .write()
             .map(|config| println!("{config}")),
+        Commands::Ips4Login { site } => {
+            let (_config_path, config) = config_file::HoolamikeConfig::find(&hoolamike_config).context("reading hoolamike config file")?;
+            downloaders::ips4::login(&config.downloaders.ips4, &site)
+                .await
+                .map(|_| ())
+        }
         Commands::Install { debug } => {
             let (config_path, config) = config_file::HoolamikeConfig::find(&hoolamike_config).context("reading hoolamike config file")?;
             info!("found config at [{}]", config_path.display());
@@ -261,8 +314,17 @@ async fn async_main() -> Result<()> {
                         .pipe_ref(|directives| serde_json::to_string_pretty(directives).context("serializing directives"))
                 })
                 .map(|directives| println!("{directives}")),
+            HoolamikeDebugCommand::WabbajackFile(command) => command.run(),
+            HoolamikeDebugCommand::RunDirective {
+                directive_hash,
+                scratch_directory,
+            } => {
+                let (_config_path, config) = config_file::HoolamikeConfig::find(&hoolamike_config).context("reading hoolamike config file")?;
+                install_modlist::run_directive::run_directive(config, directive_hash, scratch_directory).await
+            }
         },
         Commands::Archive(archive_cli_command) => archive_cli_command.run(),
+        Commands::Modlist(modlist_cli_command) => modlist_cli_command.run(),
         Commands::Audio(audio_cli_command) => audio_cli_command
             .command
             .pipe(|c| c.clone().run().with_context(|| format!("running\n{c:#?}"))),
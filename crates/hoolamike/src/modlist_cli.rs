@@ -0,0 +1,34 @@
+use {
+    crate::wabbajack_file::WabbajackFile,
+    anyhow::{Context, Result},
+    std::path::PathBuf,
+};
+
+pub mod query;
+
+#[derive(clap::Args)]
+pub struct ModlistCliCommand {
+    #[command(subcommand)]
+    pub command: ModlistCliCommandInner,
+}
+
+#[derive(clap::Subcommand)]
+pub enum ModlistCliCommandInner {
+    /// traces installed files back to the archives they come from, and archives to the files they feed
+    Query {
+        /// path to modlist (.wabbajack) file
+        path: PathBuf,
+        #[command(flatten)]
+        query: query::Query,
+    },
+}
+
+impl ModlistCliCommand {
+    pub fn run(self) -> Result<()> {
+        match self.command {
+            ModlistCliCommandInner::Query { path, query } => WabbajackFile::read_modlist(&path)
+                .with_context(|| format!("reading [{}]", path.display()))
+                .and_then(|(_, modlist)| query.run(&modlist)),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! reverse index over the modlist - which archive produces a file, and which files an archive feeds
+use {
+    crate::{
+        modlist_json::{
+            consistency::{bsa_files, bsa_staging_directory},
+            directive::ArchiveHashPath,
+            Archive,
+            Directive,
+            DownloadKind,
+            Modlist,
+            State,
+        },
+        utils::MaybeWindowsPath,
+    },
+    anyhow::Result,
+    itertools::Itertools,
+    std::collections::HashMap,
+};
+
+#[derive(clap::Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct Query {
+    /// installed file (relative to the installation directory), a partial path matches every file ending with it
+    #[arg(long)]
+    pub output: Option<String>,
+    /// archive name or hash, lists every file it contributes to
+    #[arg(long)]
+    pub archive: Option<String>,
+    /// nexus mod id, lists every file its archives contribute to
+    #[arg(long = "mod")]
+    pub mod_id: Option<usize>,
+}
+
+pub struct ModlistIndex<'a> {
+    modlist: &'a Modlist,
+    archives: HashMap<&'a str, &'a Archive>,
+    /// normalized output path -> directives writing it
+    outputs: HashMap<String, Vec<usize>>,
+    /// archive hash -> directives reading from it
+    consumers: HashMap<&'a str, Vec<usize>>,
+    /// normalized staging directory -> the [CreateBSA](Directive::CreateBSA) directive packing it
+    bsa_staging: HashMap<String, usize>,
+}
+
+impl<'a> ModlistIndex<'a> {
+    pub fn new(modlist: &'a Modlist) -> Self {
+        let directives = || modlist.directives.iter().enumerate();
+        Self {
+            modlist,
+            archives: modlist
+                .archives
+                .iter()
+                .map(|archive| (archive.descriptor.hash.as_str(), archive))
+                .collect(),
+            outputs: directives()
+                .filter_map(|(index, directive)| directive.to().map(|to| (to.normalized(), index)))
+                .into_group_map(),
+            consumers: directives()
+                .filter_map(|(index, directive)| {
+                    directive
+                        .archive_hash_path()
+                        .map(|path| (path.source_hash.as_str(), index))
+                })
+                .into_group_map(),
+            bsa_staging: directives()
+                .filter_map(|(index, directive)| match directive {
+                    Directive::CreateBSA(create_bsa) => {
+                        let (temp_id, _) = bsa_files(create_bsa);
+                        Some((bsa_staging_directory(temp_id), index))
+                    }
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    fn directive(&self, index: usize) -> &'a Directive {
+        &self.modlist.directives[index]
+    }
+
+    /// exact matches win, otherwise every output ending with the query
+    pub fn find_outputs(&self, query: &str) -> Vec<usize> {
+        let query = MaybeWindowsPath(query.to_string()).normalized();
+        self.outputs.get(&query).cloned().unwrap_or_else(|| {
+            self.outputs
+                .iter()
+                .filter(|(output, _)| output.ends_with(&format!("/{query}")))
+                .flat_map(|(_, indices)| indices.iter().copied())
+                .sorted()
+                .collect()
+        })
+    }
+
+    /// by hash, or by (case insensitive) file name
+    pub fn find_archives(&self, query: &str) -> Vec<&'a Archive> {
+        self.archives
+            .get(query)
+            .map(|archive| vec![*archive])
+            .unwrap_or_else(|| {
+                self.modlist
+                    .archives
+                    .iter()
+                    .filter(|archive| archive.descriptor.name.eq_ignore_ascii_case(query))
+                    .collect()
+            })
+    }
+
+    pub fn nexus_mod_archives(&self, mod_id: usize) -> Vec<&'a Archive> {
+        self.modlist
+            .archives
+            .iter()
+            .filter(|archive| matches!(&archive.state, State::Nexus(nexus) if nexus.mod_id == mod_id))
+            .collect()
+    }
+
+    /// directives reading from the archive
+    pub fn consumers(&self, archive: &Archive) -> &[usize] {
+        self.consumers
+            .get(archive.descriptor.hash.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// files staged for a BSA don't stay in the installation, this is the directive they end up packed by
+    pub fn packed_into(&self, to: &MaybeWindowsPath) -> Option<usize> {
+        let to = to.normalized();
+        self.bsa_staging
+            .iter()
+            .find(|(staging, _)| to.starts_with(&format!("{staging}/")))
+            .map(|(_, index)| *index)
+    }
+
+    pub fn describe_archive(archive: &Archive) -> String {
+        match &archive.state {
+            State::Nexus(nexus) => format!("{} [nexus mod {}, file {}]", archive.descriptor.name, nexus.mod_id, nexus.file_id),
+            other => format!("{} [{}]", archive.descriptor.name, DownloadKind::from(other)),
+        }
+    }
+
+    /// the archive, followed by the path to the file within it (nested archives included)
+    pub fn describe_source(&self, ArchiveHashPath { source_hash, path }: &ArchiveHashPath) -> String {
+        std::iter::once(
+            self.archives
+                .get(source_hash.as_str())
+                .map(|archive| Self::describe_archive(archive))
+                .unwrap_or_else(|| format!("<unknown archive {source_hash}>")),
+        )
+        .chain(path.iter().map(|path| path.0.clone()))
+        .join(" > ")
+    }
+
+    fn describe_output(&self, index: usize) -> String {
+        let directive = self.directive(index);
+        let to = directive
+            .to()
+            .map(|to| to.0.as_str())
+            .unwrap_or("<unknown>");
+        match self.packed_into(&MaybeWindowsPath(to.to_string())) {
+            Some(bsa) => format!(
+                "#{index} {} -> {to} (packed into #{bsa} {})",
+                directive.directive_kind(),
+                self.directive(bsa)
+                    .to()
+                    .map(|to| to.0.as_str())
+                    .unwrap_or_default()
+            ),
+            None => format!("#{index} {} -> {to}", directive.directive_kind()),
+        }
+    }
+
+    pub fn describe_directive(&self, index: usize) -> String {
+        let directive = self.directive(index);
+        let mut lines = vec![self.describe_output(index), format!("    size: {}", directive.size())];
+        match directive {
+            Directive::CreateBSA(create_bsa) => {
+                lines.push(format!("    hash: {}", create_bsa.hash()));
+                let (temp_id, files) = bsa_files(create_bsa);
+                lines.push(format!("    packs: [{}] files staged under {}", files.len(), bsa_staging_directory(temp_id)));
+            }
+            Directive::FromArchive(d) => {
+                lines.push(format!("    hash: {}", d.hash));
+                lines.push(format!("    source: {}", self.describe_source(&d.archive_hash_path)));
+            }
+            Directive::PatchedFromArchive(d) => {
+                lines.push(format!("    hash: {}", d.hash));
+                lines.push(format!("    source: {}", self.describe_source(&d.archive_hash_path)));
+                lines.push(format!("    source hash: {}", d.from_hash));
+                lines.push(format!("    patch: {}", d.patch_id.as_hyphenated()));
+            }
+            Directive::TransformedTexture(d) => {
+                lines.push(format!("    hash: {}", d.hash));
+                lines.push(format!("    source: {}", self.describe_source(&d.archive_hash_path)));
+                lines.push(format!(
+                    "    texture: {:?} {}x{}, {} mips",
+                    d.image_state.format, d.image_state.width, d.image_state.height, d.image_state.mip_levels
+                ));
+            }
+            Directive::InlineFile(d) => {
+                lines.push(format!("    hash: {}", d.hash));
+                lines.push(format!("    inline data: {}", d.source_data_id.as_hyphenated()));
+            }
+            Directive::RemappedInlineFile(d) => {
+                lines.push(format!("    hash: {}", d.hash));
+                lines.push(format!("    inline data (remapped): {}", d.source_data_id.as_hyphenated()));
+            }
+            Directive::Unsupported(entry) => lines.push(format!("    unsupported: {}", entry.type_name())),
+        }
+        lines.join("\n")
+    }
+
+    pub fn describe_archive_outputs(&self, archive: &Archive) -> String {
+        let consumers = self.consumers(archive);
+        std::iter::once(format!("{} feeds [{}] files:", Self::describe_archive(archive), consumers.len()))
+            .chain(consumers.iter().map(|index| {
+                let from = self
+                    .directive(*index)
+                    .archive_hash_path()
+                    .map(|path| path.path.iter().map(|path| path.0.as_str()).join(" > "))
+                    .unwrap_or_default();
+                format!("  {} <- {from}", self.describe_output(*index))
+            }))
+            .join("\n")
+    }
+}
+
+impl Query {
+    pub fn run(self, modlist: &Modlist) -> Result<()> {
+        let index = ModlistIndex::new(modlist);
+        let Self { output, archive, mod_id } = self;
+        let found = match (output, archive, mod_id) {
+            (Some(output), _, _) => index
+                .find_outputs(&output)
+                .into_iter()
+                .map(|found| index.describe_directive(found))
+                .collect_vec(),
+            (_, Some(archive), _) => index
+                .find_archives(&archive)
+                .into_iter()
+                .map(|archive| index.describe_archive_outputs(archive))
+                .collect_vec(),
+            (_, _, Some(mod_id)) => index
+                .nexus_mod_archives(mod_id)
+                .into_iter()
+                .map(|archive| index.describe_archive_outputs(archive))
+                .collect_vec(),
+            (None, None, None) => anyhow::bail!("nothing to look for"),
+        };
+        anyhow::ensure!(!found.is_empty(), "nothing in the modlist matches the query");
+        found.into_iter().for_each(|found| println!("{found}\n"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::modlist_json::test_fixtures::{create_bsa, from_archive, modlist, nexus, ARCHIVE_HASH},
+    };
+
+    #[test]
+    fn test_outputs_are_traced_back_to_archives() -> Result<()> {
+        let modlist = modlist(
+            vec![nexus("Rocks.7z", "SkyrimSpecialEdition", 42, 7)],
+            vec![
+                from_archive("mods\\Rocks\\textures\\rock.dds", ARCHIVE_HASH, "textures\\rock.dds"),
+                from_archive("TEMP_BSA_FILES\\bsa-1\\textures\\pebble.dds", ARCHIVE_HASH, "textures\\pebble.dds"),
+                create_bsa("mods\\Rocks\\Rocks.bsa", "bsa-1", &["textures\\pebble.dds"]),
+            ],
+        );
+        let index = ModlistIndex::new(&modlist);
+        assert_eq!(index.find_outputs("MODS/rocks/textures/ROCK.dds"), [0]);
+        assert_eq!(index.find_outputs("rock.dds"), [0]);
+        assert!(index
+            .describe_directive(0)
+            .contains("Rocks.7z [nexus mod 42, file 7] > textures\\rock.dds"));
+        let archives = index.nexus_mod_archives(42);
+        assert_eq!(archives.len(), 1);
+        assert_eq!(index.consumers(archives[0]), [0, 1]);
+        assert_eq!(
+            index.packed_into(&MaybeWindowsPath("TEMP_BSA_FILES\\bsa-1\\textures\\pebble.dds".to_string())),
+            Some(2)
+        );
+        assert_eq!(index.find_archives("rocks.7z").len(), 1);
+        Ok(())
+    }
+}
@@ -1,6 +1,11 @@
 use {
-    crate::{helpers::human_readable_size, modlist_json::Modlist},
+    crate::{
+        helpers::human_readable_size,
+        modlist_json::{unsupported::UnsupportedReport, Directive, DirectiveKind, DownloadKind, Modlist, State},
+    },
+    anyhow::{Context, Result},
     itertools::Itertools,
+    serde::Serialize,
     std::collections::BTreeMap,
     tabled::{
         settings::{object::Columns, Color, Rotate, Style},
@@ -9,32 +14,50 @@ use {
     tap::prelude::*,
 };
 
-#[derive(Tabled)]
+/// how many of the largest archives and outputs are listed
+const LARGEST_COUNT: usize = 20;
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy, Default)]
+pub enum ReportFormat {
+    #[default]
+    Table,
+    /// everything in a single document, sizes in bytes
+    Json,
+    /// one csv block per section, each preceded by a `# <section>` line
+    Csv,
+}
+
+#[derive(Tabled, Serialize)]
 pub struct ModlistSummary {
     pub author: String,
     pub total_mods: usize,
     pub total_directives: usize,
-    pub unique_directive_kinds: String,
+    #[tabled(display_with = "display_value_counts")]
+    pub unique_directive_kinds: BTreeMap<DirectiveKind, usize>,
     // pub unique_authors: usize,
-    pub sources: String,
+    #[tabled(display_with = "display_value_counts")]
+    pub sources: BTreeMap<DownloadKind, usize>,
     pub name: String,
     // pub unique_headers: String,
     pub website: String,
-    pub total_download_size: String,
+    #[tabled(display_with = "display_size")]
+    pub total_download_size: u64,
     pub description: String,
+    #[serde(skip)]
     pub directive_examples: String,
+    pub unsupported: String,
 }
 
-fn summarize_value_count<'a, I: std::fmt::Display + Ord + Clone + Eq>(items: impl Iterator<Item = I> + 'a) -> String {
-    items
-        .fold(BTreeMap::new(), |acc, directive| {
-            acc.tap_mut(move |acc| {
-                *acc.entry(directive).or_insert(0) += 1;
-            })
+fn summarize_value_count<'a, I: Ord>(items: impl Iterator<Item = I> + 'a) -> BTreeMap<I, usize> {
+    items.fold(BTreeMap::new(), |acc, directive| {
+        acc.tap_mut(move |acc| {
+            *acc.entry(directive).or_insert(0) += 1;
         })
-        .iter()
-        .map(|(k, v)| format!("{k}: {v}"))
-        .join("\n")
+    })
+}
+
+fn display_value_counts<I: std::fmt::Display>(counts: &BTreeMap<I, usize>) -> String {
+    counts.iter().map(|(k, v)| format!("{k}: {v}")).join("\n")
 }
 impl ModlistSummary {
     pub fn print(&self) -> String {
@@ -45,8 +68,8 @@ impl ModlistSummary {
             .to_string()
     }
 
-    pub fn new(
-        Modlist {
+    pub fn new(modlist: &Modlist) -> Self {
+        let Modlist {
             archives,
             author,
             description,
@@ -59,8 +82,8 @@ impl ModlistSummary {
             readme: _,
             version: _,
             wabbajack_version: _,
-        }: &Modlist,
-    ) -> Self {
+            extra: _,
+        } = modlist;
         Self {
             directive_examples: directives
                 .iter()
@@ -76,7 +99,7 @@ impl ModlistSummary {
             author: author.clone(),
             sources: archives
                 .iter()
-                .map(|archive| archive.state.kind().to_string())
+                .map(|archive| archive.state.kind())
                 .pipe(summarize_value_count),
             total_mods: archives.len(),
             // unique_authors: archives
@@ -101,12 +124,286 @@ impl ModlistSummary {
             //     .unique()
             //     .join(",\n"),
             website: website.clone(),
-            total_download_size: archives
-                .iter()
-                .map(|a| a.descriptor.size)
-                .sum::<u64>()
-                .pipe(human_readable_size),
+            total_download_size: archives.iter().map(|a| a.descriptor.size).sum::<u64>(),
             description: description.clone(),
+            unsupported: UnsupportedReport::new(modlist).to_string(),
         }
     }
 }
+
+fn display_size(size: &u64) -> String {
+    human_readable_size(*size)
+}
+
+fn display_optional(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+#[derive(Debug, Tabled, Serialize)]
+pub struct SourceBreakdown {
+    pub source: DownloadKind,
+    pub archives: usize,
+    #[tabled(display_with = "display_size")]
+    pub size: u64,
+}
+
+#[derive(Debug, Tabled, Serialize)]
+pub struct LargestArchive {
+    pub name: String,
+    pub source: DownloadKind,
+    #[tabled(display_with = "display_size")]
+    pub size: u64,
+}
+
+#[derive(Debug, Tabled, Serialize)]
+pub struct LargestOutput {
+    pub to: String,
+    pub kind: DirectiveKind,
+    #[tabled(display_with = "display_size")]
+    pub size: u64,
+}
+
+#[derive(Debug, Tabled, Serialize)]
+pub struct NexusMod {
+    pub name: String,
+    #[tabled(display_with = "display_optional")]
+    pub author: Option<String>,
+    pub version: String,
+    pub game: String,
+    pub mod_id: usize,
+    pub file_id: usize,
+    pub nsfw: bool,
+    pub archive: String,
+}
+
+#[derive(Debug, Tabled, Serialize)]
+pub struct GameFileRequirement {
+    pub game: String,
+    pub game_version: String,
+    pub file: String,
+    pub hash: String,
+    #[tabled(display_with = "display_size")]
+    pub size: u64,
+}
+
+#[derive(Debug, Tabled, Serialize)]
+pub struct BsaOutput {
+    pub to: String,
+    pub files: usize,
+    #[tabled(display_with = "display_size")]
+    pub size: u64,
+}
+
+/// everything `modlist-info` knows about the modlist, usable for diffing modlist versions and producing credits
+#[derive(Serialize)]
+pub struct ModlistReport {
+    pub summary: ModlistSummary,
+    pub sources: Vec<SourceBreakdown>,
+    pub largest_archives: Vec<LargestArchive>,
+    pub largest_outputs: Vec<LargestOutput>,
+    pub nexus_mods: Vec<NexusMod>,
+    pub game_files: Vec<GameFileRequirement>,
+    pub bsa_outputs: Vec<BsaOutput>,
+}
+
+/// quotes the cell only when it has to
+fn csv_cell(value: &serde_json::Value) -> String {
+    let cell = match value {
+        serde_json::Value::String(value) => value.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    match cell.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell,
+    }
+}
+
+/// header taken from the field names of the first row
+fn csv_section<T: Serialize>(name: &str, rows: &[T]) -> Result<String> {
+    let rows = rows
+        .iter()
+        .map(|row| match serde_json::to_value(row).context("serializing row")? {
+            serde_json::Value::Object(fields) => Ok(fields),
+            other => anyhow::bail!("expected a row to be an object, got {other}"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(std::iter::once(format!("# {name}"))
+        .chain(rows.first().map(|fields| fields.keys().join(",")))
+        .chain(
+            rows.iter()
+                .map(|fields| fields.values().map(csv_cell).join(",")),
+        )
+        .join("\n"))
+}
+
+fn table_section<T: Tabled>(name: &str, rows: &[T]) -> String {
+    match rows.is_empty() {
+        true => format!("{name}: none"),
+        false => format!("{name}:\n{}", tabled::Table::new(rows).with(Style::modern())),
+    }
+}
+
+impl ModlistReport {
+    pub fn new(modlist: &Modlist) -> Self {
+        Self {
+            summary: ModlistSummary::new(modlist),
+            sources: modlist
+                .archives
+                .iter()
+                .fold(BTreeMap::<_, Vec<_>>::new(), |acc, archive| {
+                    acc.tap_mut(|acc| acc.entry(archive.state.kind()).or_default().push(archive))
+                })
+                .into_iter()
+                .map(|(source, archives)| SourceBreakdown {
+                    source,
+                    archives: archives.len(),
+                    size: archives.iter().map(|archive| archive.descriptor.size).sum(),
+                })
+                .sorted_by_key(|breakdown| std::cmp::Reverse(breakdown.size))
+                .collect(),
+            largest_archives: modlist
+                .archives
+                .iter()
+                .sorted_by_key(|archive| std::cmp::Reverse(archive.descriptor.size))
+                .take(LARGEST_COUNT)
+                .map(|archive| LargestArchive {
+                    name: archive.descriptor.name.clone(),
+                    source: archive.state.kind(),
+                    size: archive.descriptor.size,
+                })
+                .collect(),
+            largest_outputs: modlist
+                .directives
+                .iter()
+                .sorted_by_key(|directive| std::cmp::Reverse(directive.size()))
+                .take(LARGEST_COUNT)
+                .map(|directive| LargestOutput {
+                    to: directive.to().map(|to| to.0.clone()).unwrap_or_default(),
+                    kind: directive.directive_kind(),
+                    size: directive.size(),
+                })
+                .collect(),
+            nexus_mods: modlist
+                .archives
+                .iter()
+                .filter_map(|archive| match &archive.state {
+                    State::Nexus(nexus) => Some(NexusMod {
+                        name: nexus.name.clone(),
+                        author: nexus.author.clone(),
+                        version: nexus.version.clone(),
+                        game: nexus.game_name.to_string(),
+                        mod_id: nexus.mod_id,
+                        file_id: nexus.file_id,
+                        nsfw: nexus.is_nsfw,
+                        archive: archive.descriptor.name.clone(),
+                    }),
+                    _ => None,
+                })
+                .sorted_by(|left, right| left.name.to_lowercase().cmp(&right.name.to_lowercase()))
+                .collect(),
+            game_files: modlist
+                .archives
+                .iter()
+                .filter_map(|archive| match &archive.state {
+                    State::GameFileSource(game_file) => Some(GameFileRequirement {
+                        game: game_file.game.to_string(),
+                        game_version: game_file.game_version.clone(),
+                        file: game_file.game_file.0.clone(),
+                        hash: game_file.hash.clone(),
+                        size: archive.descriptor.size,
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            bsa_outputs: modlist
+                .directives
+                .iter()
+                .filter_map(|directive| match directive {
+                    Directive::CreateBSA(create_bsa) => Some(BsaOutput {
+                        to: create_bsa.to().0.clone(),
+                        files: crate::modlist_json::consistency::bsa_files(create_bsa)
+                            .1
+                            .len(),
+                        size: create_bsa.size(),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        match format {
+            ReportFormat::Table => [
+                self.summary.print(),
+                table_section("download sources", &self.sources),
+                table_section("largest archives", &self.largest_archives),
+                table_section("largest outputs", &self.largest_outputs),
+                table_section("nexus mods", &self.nexus_mods),
+                table_section("game files", &self.game_files),
+                table_section(&format!("BSA outputs ({})", self.bsa_outputs.len()), &self.bsa_outputs),
+            ]
+            .join("\n\n")
+            .pipe(Ok),
+            ReportFormat::Json => serde_json::to_string_pretty(self).context("serializing report"),
+            ReportFormat::Csv => [
+                csv_section("summary", std::slice::from_ref(&self.summary))?,
+                csv_section("sources", &self.sources)?,
+                csv_section("largest_archives", &self.largest_archives)?,
+                csv_section("largest_outputs", &self.largest_outputs)?,
+                csv_section("nexus_mods", &self.nexus_mods)?,
+                csv_section("game_files", &self.game_files)?,
+                csv_section("bsa_outputs", &self.bsa_outputs)?,
+            ]
+            .join("\n\n")
+            .pipe(Ok),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::modlist_json::test_fixtures::{from_archive, modlist, nexus, ARCHIVE_HASH},
+    };
+
+    #[test]
+    fn test_csv_cells_are_quoted_when_needed() {
+        assert_eq!(csv_cell(&serde_json::json!("plain")), "plain");
+        assert_eq!(csv_cell(&serde_json::json!("a, \"b\"")), "\"a, \"\"b\"\"\"");
+        assert_eq!(csv_cell(&serde_json::json!(42)), "42");
+        assert_eq!(csv_cell(&serde_json::Value::Null), "");
+    }
+
+    #[test]
+    fn test_csv_section_uses_field_names_as_header() -> Result<()> {
+        let section = csv_section(
+            "bsa_outputs",
+            &[BsaOutput {
+                to: "mods\\a\\a.bsa".to_string(),
+                files: 2,
+                size: 1024,
+            }],
+        )?;
+        assert_eq!(section, "# bsa_outputs\nto,files,size\nmods\\a\\a.bsa,2,1024");
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_counts_and_sizes_are_machine_readable() -> Result<()> {
+        let modlist = modlist(
+            vec![nexus("a.7z", "SkyrimSpecialEdition", 2, 1)],
+            vec![
+                from_archive("mods\\a\\a.esp", ARCHIVE_HASH, "a.esp"),
+                from_archive("mods\\a\\b.esp", ARCHIVE_HASH, "b.esp"),
+            ],
+        );
+        let report = serde_json::to_value(ModlistReport::new(&modlist))?;
+        assert_eq!(report["summary"]["total_download_size"], 4);
+        assert_eq!(report["summary"]["sources"], serde_json::json!({"Nexus": 1}));
+        assert_eq!(report["summary"]["unique_directive_kinds"], serde_json::json!({"FromArchive": 2}));
+        Ok(())
+    }
+}
@@ -42,8 +42,11 @@ impl std::fmt::Debug for HumanUrl {
     }
 }
 
+/// fields that a newer version of wabbajack added to a directive or a download source this version knows,
+/// they're reported and otherwise ignored
+pub type ExtraFields = std::collections::BTreeMap<String, serde_json::Value>;
+
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct Modlist {
     /// archives: Vec<Archive>
@@ -95,10 +98,13 @@ pub struct Modlist {
     /// Description: The modlist's website or homepage.
     /// Usage: Provide users with a link for more information.
     pub website: String,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Debug, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord, Clone)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Serialize, Deserialize, Clone, derivative::Derivative)]
+#[derivative(Hash, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "PascalCase")]
 pub struct ArchiveDescriptor {
     /// hash: String
@@ -117,9 +123,13 @@ pub struct ArchiveDescriptor {
     /// Description: Size of the archive in bytes.
     /// Usage: For progress tracking and verifying download completeness.
     pub size: u64,
+    /// fields added by a newer version of wabbajack, they don't tell archives apart
+    #[serde(flatten)]
+    #[derivative(Hash = "ignore", PartialEq = "ignore", PartialOrd = "ignore", Ord = "ignore")]
+    pub extra: ExtraFields,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct Archive {
     #[serde(flatten)]
@@ -148,73 +158,155 @@ pub enum State {
     GoogleDrive(GoogleDriveState),
     #[serde(rename = "MediaFireDownloader+State, Wabbajack.Lib")]
     MediaFire(MediaFireState),
+    #[serde(rename = "ModDBDownloader, Wabbajack.Lib")]
+    ModDB(ModDBState),
+    #[serde(rename = "GitHubDownloader, Wabbajack.Lib")]
+    GitHub(GitHubState),
+    #[serde(rename = "LoversLabOAuthDownloader, Wabbajack.Lib")]
+    LoversLab(Ips4OAuth2State),
+    #[serde(rename = "VectorPlexusOAuthDownloader+State, Wabbajack.Lib")]
+    VectorPlexus(Ips4OAuth2State),
     #[serde(rename = "HttpDownloader, Wabbajack.Lib")]
     Http(HttpState),
     #[serde(rename = "ManualDownloader, Wabbajack.Lib")]
     Manual(ManualState),
     #[serde(rename = "WabbajackCDNDownloader+State, Wabbajack.Lib")]
     WabbajackCDN(WabbajackCDNDownloaderState),
+    /// a downloader this version does not know about, or a known one that could not be read
+    #[serde(untagged)]
+    Unsupported(unsupported::UnsupportedEntry),
 }
 
 impl State {
     pub fn kind(&self) -> DownloadKind {
         DownloadKind::from(self)
     }
+
+    /// fields a newer version of wabbajack added, which this version ignores
+    pub fn extra_fields(&self) -> Option<&ExtraFields> {
+        match self {
+            State::Nexus(state) => Some(&state.extra),
+            State::GameFileSource(state) => Some(&state.extra),
+            State::Mega(state) => Some(&state.extra),
+            State::GoogleDrive(state) => Some(&state.extra),
+            State::MediaFire(state) => Some(&state.extra),
+            State::ModDB(state) => Some(&state.extra),
+            State::GitHub(state) => Some(&state.extra),
+            State::LoversLab(state) => Some(&state.extra),
+            State::VectorPlexus(state) => Some(&state.extra),
+            State::Http(state) => Some(&state.extra),
+            State::Manual(state) => Some(&state.extra),
+            State::WabbajackCDN(state) => Some(&state.extra),
+            State::Unsupported(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct HttpState {
     #[serde(default)]
     pub headers: Vec<()>,
     pub url: HumanUrl,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct ManualState {
     pub prompt: String,
     pub url: HumanUrl,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct WabbajackCDNDownloaderState {
     pub url: HumanUrl,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct GoogleDriveState {
     pub id: String,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct MediaFireState {
     pub url: HumanUrl,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ModDBState {
+    pub url: HumanUrl,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct Ips4OAuth2State {
+    /// file id, or attachment id when [Self::is_attachment] is set
+    #[serde(rename = "IPS4Mod")]
+    pub ips4_mod: u64,
+    /// name of the file within the download
+    #[serde(rename = "IPS4File")]
+    pub ips4_file: String,
+    pub is_attachment: bool,
+    #[serde(rename = "IPS4Url")]
+    pub ips4_url: String,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct GitHubState {
+    pub user: String,
+    pub repository: String,
+    pub tag: String,
+    pub asset_name: String,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct MegaState {
     pub url: HumanUrl,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct GameFileSourceState {
     pub game_version: String,
     pub hash: String,
     pub game_file: MaybeWindowsPath,
     pub game: GameName,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, derive_more::Display, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Constructor)]
@@ -234,7 +326,6 @@ pub enum NexusGameName {
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct NexusState {
     pub game_name: NexusGameName,
     #[serde(rename = "FileID")]
@@ -261,6 +352,9 @@ pub struct NexusState {
     /// Description: The version of the mod.
     /// Usage: Ensure correct versions are downloaded.
     pub version: String,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -348,6 +442,13 @@ pub struct UnknownState {
 
 pub mod directive;
 
+pub mod unsupported;
+
+pub mod consistency;
+
+#[cfg(test)]
+pub(crate) mod test_fixtures;
+
 #[derive(Debug, Serialize, Deserialize, enum_kinds::EnumKind)]
 #[serde(tag = "$type")]
 #[serde(deny_unknown_fields)]
@@ -359,6 +460,9 @@ pub enum Directive {
     PatchedFromArchive(directive::PatchedFromArchiveDirective),
     RemappedInlineFile(directive::RemappedInlineFileDirective),
     TransformedTexture(directive::TransformedTextureDirective),
+    /// a directive this version does not know about, or a known one that could not be read
+    #[serde(untagged)]
+    Unsupported(unsupported::UnsupportedEntry),
 }
 
 impl Directive {
@@ -370,6 +474,11 @@ impl Directive {
             Directive::PatchedFromArchive(d) => d.size,
             Directive::RemappedInlineFile(d) => d.size,
             Directive::TransformedTexture(d) => d.size,
+            Directive::Unsupported(d) => d
+                .raw
+                .get("Size")
+                .and_then(serde_json::Value::as_u64)
+                .unwrap_or_default(),
         }
     }
     pub fn directive_hash(&self) -> String {
@@ -385,12 +494,69 @@ impl Directive {
     pub fn directive_kind(&self) -> DirectiveKind {
         DirectiveKind::from(self)
     }
+
+    pub fn to(&self) -> Option<&MaybeWindowsPath> {
+        match self {
+            Directive::CreateBSA(d) => Some(d.to()),
+            Directive::FromArchive(d) => Some(&d.to),
+            Directive::InlineFile(d) => Some(&d.to),
+            Directive::PatchedFromArchive(d) => Some(&d.to),
+            Directive::RemappedInlineFile(d) => Some(&d.to),
+            Directive::TransformedTexture(d) => Some(&d.to),
+            Directive::Unsupported(_) => None,
+        }
+    }
+
+    /// fields a newer version of wabbajack added, which this version ignores
+    pub fn extra_fields(&self) -> Option<&ExtraFields> {
+        match self {
+            Directive::CreateBSA(d) => Some(d.extra()),
+            Directive::FromArchive(d) => Some(&d.extra),
+            Directive::InlineFile(d) => Some(&d.extra),
+            Directive::PatchedFromArchive(d) => Some(&d.extra),
+            Directive::RemappedInlineFile(d) => Some(&d.extra),
+            Directive::TransformedTexture(d) => Some(&d.extra),
+            Directive::Unsupported(_) => None,
+        }
+    }
+
+    /// expected hash of the output
+    pub fn hash(&self) -> Option<&str> {
+        match self {
+            Directive::CreateBSA(d) => Some(d.hash()),
+            Directive::FromArchive(d) => Some(&d.hash),
+            Directive::InlineFile(d) => Some(&d.hash),
+            Directive::PatchedFromArchive(d) => Some(&d.hash),
+            Directive::RemappedInlineFile(d) => Some(&d.hash),
+            Directive::TransformedTexture(d) => Some(&d.hash),
+            Directive::Unsupported(_) => None,
+        }
+    }
+
+    /// the archive (and the path within it) the directive reads from
+    pub fn archive_hash_path(&self) -> Option<&directive::ArchiveHashPath> {
+        match self {
+            Directive::FromArchive(d) => Some(&d.archive_hash_path),
+            Directive::PatchedFromArchive(d) => Some(&d.archive_hash_path),
+            Directive::TransformedTexture(d) => Some(&d.archive_hash_path),
+            _ => None,
+        }
+    }
+
+    /// the file stored inside of the .wabbajack archive the directive reads from
+    pub fn wabbajack_entry_id(&self) -> Option<uuid::Uuid> {
+        match self {
+            Directive::InlineFile(d) => Some(d.source_data_id),
+            Directive::RemappedInlineFile(d) => Some(d.source_data_id),
+            Directive::PatchedFromArchive(d) => Some(d.patch_id),
+            _ => None,
+        }
+    }
 }
 
 pub mod image_format;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct ImageState {
     /// format: String
@@ -413,6 +579,9 @@ pub struct ImageState {
     /// Description: Width of the image in pixels.
     /// Usage: May be needed for processing or validation.
     pub width: u32,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 // #[allow(clippy::large_enum_variant)]
@@ -544,7 +713,6 @@ pub struct UnknownDirectiveState {
 // }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct BA2DX10EntryChunk {
     /// align: u64
@@ -567,14 +735,19 @@ pub struct BA2DX10EntryChunk {
     /// Description: Starting mipmap level for this chunk.
     /// Usage: For texture processing.
     pub start_mip: u64,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 pub mod parsing_helpers {
     use {
         anyhow::{Context, Result},
-        itertools::Itertools,
         serde_json::Value,
-        std::collections::BTreeMap,
+        std::{
+            collections::BTreeMap,
+            io::{BufRead, BufReader, Read, Seek, SeekFrom},
+        },
         tap::prelude::*,
         tracing::info,
     };
@@ -587,33 +760,74 @@ pub mod parsing_helpers {
         Other(&'a serde_json::Value),
     }
 
-    pub fn validate_modlist_file(input: &str) -> Result<()> {
-        input
-            .tap(|input| {
-                info!("file is {} bytes long", input.bytes().len());
+    /// how many bytes around a parsing error are shown, modlists are usually a single line so whole lines are no good
+    const ERROR_WINDOW: u64 = 512;
+
+    /// byte offset of a (1-based) line and column as reported by [serde_json::Error]
+    fn error_offset(reader: &mut impl BufRead, line: usize, column: usize) -> std::io::Result<u64> {
+        let mut offset = 0;
+        let mut buffer = Vec::new();
+        for _ in 1..line {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer)? {
+                0 => break,
+                read => offset += read as u64,
+            }
+        }
+        Ok(offset + column.saturating_sub(1) as u64)
+    }
+
+    /// reads a small window around where parsing failed, instead of keeping the whole document around for that
+    fn error_window<R: Read + Seek>(reader: &mut R, error: &serde_json::Error) -> Result<String> {
+        reader.seek(SeekFrom::Start(0)).context("rewinding")?;
+        let offset = error_offset(&mut BufReader::new(&mut *reader), error.line(), error.column()).context("looking for error location")?;
+        let start = offset.saturating_sub(ERROR_WINDOW / 2);
+        reader
+            .seek(SeekFrom::Start(start))
+            .context("seeking to error location")?;
+        Vec::new()
+            .pipe(|mut window| {
+                reader
+                    .take(ERROR_WINDOW)
+                    .read_to_end(&mut window)
+                    .map(|_| window)
             })
-            .pipe_as_ref(serde_json::from_str::<Value>)
-            .context("bad json")
-            .and_then(|node| serde_json::to_string_pretty(&node).context("serializing"))
-            .and_then(move |pretty_input| {
-                serde_json::from_str::<crate::modlist_json::Modlist>(&pretty_input)
-                    .pipe(|res| match res.as_ref() {
-                        Ok(_) => res.context(""),
-                        Err(e) => e.line().pipe(|line| {
-                            res.with_context(|| {
-                                pretty_input
-                                    .lines()
-                                    .enumerate()
-                                    .map(|(idx, line)| format!("{}. {line}", idx + 1))
-                                    .skip(line - 20)
-                                    .take(40)
-                                    .join("\n")
-                            })
-                        }),
-                    })
-                    .context("bad modlist")
+            .context("reading around error location")
+            .map(|window| {
+                let marker = ((offset - start) as usize).min(window.len());
+                format!(
+                    "line {}, column {} (byte {offset}):\n...{} >>> HERE >>> {}...",
+                    error.line(),
+                    error.column(),
+                    String::from_utf8_lossy(&window[..marker]),
+                    String::from_utf8_lossy(&window[marker..]),
+                )
             })
-            .map(|_| ())
+    }
+
+    /// deserializes straight from the reader, the document is never held in memory as a whole
+    pub fn read_json<T: serde::de::DeserializeOwned, R: Read + Seek>(mut reader: R) -> Result<T> {
+        match serde_json::from_reader(BufReader::with_capacity(crate::BUFFER_SIZE, &mut reader)) {
+            Ok(value) => Ok(value),
+            Err(error) => match error_window(&mut reader, &error) {
+                Ok(window) => Err(anyhow::Error::from(error).context(window)),
+                Err(reason) => Err(anyhow::Error::from(error).context(format!("could not show where the error is: {reason:?}"))),
+            },
+        }
+    }
+
+    pub fn read_modlist<R: Read + Seek>(reader: R) -> Result<crate::modlist_json::Modlist> {
+        read_json(reader).context("not a valid modlist file")
+    }
+
+    pub fn validate_modlist_file<R: Read + Seek>(mut reader: R) -> Result<crate::modlist_json::Modlist> {
+        reader
+            .seek(SeekFrom::End(0))
+            .tap_ok(|size| info!("file is {size} bytes long"))
+            .and_then(|_| reader.seek(SeekFrom::Start(0)))
+            .context("checking file size")
+            .and_then(|_| read_modlist(reader))
+            .context("bad modlist")
     }
 
     #[allow(unexpected_cfgs)]
@@ -638,13 +852,32 @@ pub mod parsing_helpers {
             }
         }
 
+        #[test]
+        fn test_error_window_points_at_failure() {
+            let input = format!(
+                r#"{{"a": [{}], "b": tru}}"#,
+                (0..1000)
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+            let error = read_json::<Value, _>(std::io::Cursor::new(input)).unwrap_err();
+            let message = format!("{error:?}");
+            // only the end of the document is shown
+            assert!(message.contains(">>> HERE >>>") && message.contains(r#"999], "b": tru"#), "{message}");
+            assert!(!message.contains(r#"[0,1,2"#), "{message}");
+        }
+
         #[cfg(ignore)]
         // #[ignore]
         #[test_log::test]
         fn test_wasteland_reborn() -> anyhow::Result<()> {
             use super::*;
 
-            include_str!("../../../playground/dupa/modlist").pipe(validate_modlist_file)
+            include_str!("../../../playground/dupa/modlist")
+                .pipe(std::io::Cursor::new)
+                .pipe(validate_modlist_file)
+                .map(|_| ())
         }
     }
 }
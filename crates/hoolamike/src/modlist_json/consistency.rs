@@ -0,0 +1,260 @@
+//! checks that the modlist agrees with itself - a modlist can deserialize just fine and still be impossible to install
+use {
+    super::*,
+    crate::install_modlist::directives::remapped_inline_file::wabbajack_consts::BSA_CREATION_DIR,
+    directive::create_bsa_directive::{ba2, CreateBSADirective},
+    itertools::Itertools,
+    std::{
+        collections::HashSet,
+        fmt::{self, Display},
+        path::PathBuf,
+    },
+};
+
+/// only the first few problems of each category are printed, broken modlists tend to be broken in bulk
+const SHOWN_PER_CATEGORY: usize = 10;
+
+/// the directory a [CreateBSA](Directive::CreateBSA) directive picks its files up from, relative to the installation and normalized
+pub(crate) fn bsa_staging_directory(temp_id: &str) -> String {
+    BSA_CREATION_DIR.with(|directory| MaybeWindowsPath(format!("{}/{temp_id}", directory.display())).normalized())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingArchive {
+    pub index: usize,
+    pub to: String,
+    pub source_hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingWabbajackEntry {
+    pub index: usize,
+    pub kind: DirectiveKind,
+    pub to: String,
+    pub id: uuid::Uuid,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateOutput {
+    pub to: String,
+    pub indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnstagedBsaFile {
+    pub index: usize,
+    pub to: String,
+    pub temp_id: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownNexusGame {
+    pub archive: String,
+    pub game_name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsistencyReport {
+    pub missing_archives: Vec<MissingArchive>,
+    /// [None] when only the modlist json was given, there's nothing to check the entries against
+    pub missing_wabbajack_entries: Option<Vec<MissingWabbajackEntry>>,
+    pub duplicate_outputs: Vec<DuplicateOutput>,
+    pub unstaged_bsa_files: Vec<UnstagedBsaFile>,
+    pub unknown_nexus_games: Vec<UnknownNexusGame>,
+}
+
+/// the staging directory name and the files packed into the archive
+pub(crate) fn bsa_files(directive: &CreateBSADirective) -> (&str, Vec<&MaybeWindowsPath>) {
+    match directive {
+        CreateBSADirective::Bsa(archive) => (
+            archive.temp_id.as_str(),
+            archive
+                .file_states
+                .iter()
+                .map(|file_state| &file_state.inner.path)
+                .collect(),
+        ),
+        CreateBSADirective::Ba2(archive) => (
+            archive.temp_id.as_str(),
+            archive
+                .file_states
+                .iter()
+                .map(|file_state| match file_state {
+                    ba2::FileState::BA2File(entry) => &entry.path,
+                    ba2::FileState::BA2DX10Entry(entry) => &entry.path,
+                })
+                .collect(),
+        ),
+    }
+}
+
+impl ConsistencyReport {
+    /// `wabbajack_entries` are the file names inside of the .wabbajack archive
+    pub fn new(modlist: &Modlist, wabbajack_entries: Option<&[PathBuf]>) -> Self {
+        let archives = modlist
+            .archives
+            .iter()
+            .map(|archive| archive.descriptor.hash.as_str())
+            .collect::<HashSet<_>>();
+        let outputs = modlist
+            .directives
+            .iter()
+            .enumerate()
+            .filter_map(|(index, directive)| {
+                directive
+                    .to()
+                    .map(|to| (to.normalized(), (index, to.0.as_str())))
+            })
+            .into_group_map();
+        let written = &outputs;
+        Self {
+            missing_archives: modlist
+                .directives
+                .iter()
+                .enumerate()
+                .filter_map(|(index, directive)| {
+                    directive
+                        .archive_hash_path()
+                        .map(|path| (index, directive, path))
+                })
+                .filter(|(_, _, path)| !archives.contains(path.source_hash.as_str()))
+                .map(|(index, directive, path)| MissingArchive {
+                    index,
+                    to: directive.to().map(|to| to.0.clone()).unwrap_or_default(),
+                    source_hash: path.source_hash.clone(),
+                })
+                .collect(),
+            missing_wabbajack_entries: wabbajack_entries.map(|entries| {
+                let entries = entries
+                    .iter()
+                    .filter_map(|entry| entry.file_name().and_then(|name| name.to_str()))
+                    .filter_map(|name| uuid::Uuid::parse_str(name).ok())
+                    .collect::<HashSet<_>>();
+                modlist
+                    .directives
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, directive)| {
+                        directive
+                            .wabbajack_entry_id()
+                            .map(|id| (index, directive, id))
+                    })
+                    .filter(|(_, _, id)| !entries.contains(id))
+                    .map(|(index, directive, id)| MissingWabbajackEntry {
+                        index,
+                        kind: directive.directive_kind(),
+                        to: directive.to().map(|to| to.0.clone()).unwrap_or_default(),
+                        id,
+                    })
+                    .collect()
+            }),
+            duplicate_outputs: outputs
+                .values()
+                .filter(|written| written.len() > 1)
+                .map(|written| DuplicateOutput {
+                    to: written[0].1.to_string(),
+                    indices: written.iter().map(|(index, _)| *index).collect(),
+                })
+                .sorted_by_key(|duplicate| duplicate.indices[0])
+                .collect(),
+            unstaged_bsa_files: modlist
+                .directives
+                .iter()
+                .enumerate()
+                .filter_map(|(index, directive)| match directive {
+                    Directive::CreateBSA(create_bsa) => Some((index, create_bsa)),
+                    _ => None,
+                })
+                .flat_map(|(index, create_bsa)| {
+                    let (temp_id, files) = bsa_files(create_bsa);
+                    files
+                        .into_iter()
+                        .filter(move |path| !written.contains_key(&MaybeWindowsPath(format!("{}/{}", bsa_staging_directory(temp_id), path.0)).normalized()))
+                        .map(move |path| UnstagedBsaFile {
+                            index,
+                            to: create_bsa.to().0.clone(),
+                            temp_id: temp_id.to_string(),
+                            path: path.0.clone(),
+                        })
+                })
+                .collect(),
+            unknown_nexus_games: modlist
+                .archives
+                .iter()
+                .filter_map(|archive| match &archive.state {
+                    State::Nexus(nexus) => Some((archive, nexus)),
+                    _ => None,
+                })
+                .filter(|(_, nexus)| nexus.game_name.nexus_domain().is_err())
+                .map(|(archive, nexus)| UnknownNexusGame {
+                    archive: archive.descriptor.name.clone(),
+                    game_name: nexus.game_name.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.missing_archives.len()
+            + self
+                .missing_wabbajack_entries
+                .as_ref()
+                .map(Vec::len)
+                .unwrap_or_default()
+            + self.duplicate_outputs.len()
+            + self.unstaged_bsa_files.len()
+            + self.unknown_nexus_games.len()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.problem_count() == 0
+    }
+}
+
+fn write_category<T>(f: &mut fmt::Formatter<'_>, title: &str, problems: &[T], describe: impl Fn(&T) -> String) -> fmt::Result {
+    match problems.len() {
+        0 => write!(f, "\n[ok] {title}"),
+        count => {
+            write!(f, "\n[{count}] {title}:")?;
+            problems
+                .iter()
+                .take(SHOWN_PER_CATEGORY)
+                .try_for_each(|problem| write!(f, "\n  - {}", describe(problem)))?;
+            match count.saturating_sub(SHOWN_PER_CATEGORY) {
+                0 => Ok(()),
+                more => write!(f, "\n  ... and {more} more"),
+            }
+        }
+    }
+}
+
+impl Display for ConsistencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.problem_count() {
+            0 => write!(f, "modlist is consistent")?,
+            count => write!(f, "modlist has [{count}] consistency problems")?,
+        }
+        write_category(f, "directives reading from archives missing from the modlist", &self.missing_archives, |p| {
+            format!("#{} [{}]: no archive with hash [{}]", p.index, p.to, p.source_hash)
+        })?;
+        match &self.missing_wabbajack_entries {
+            Some(missing) => write_category(f, "directives referencing data missing from the .wabbajack file", missing, |p| {
+                format!("#{} {} [{}]: no entry [{}]", p.index, p.kind, p.to, p.id.as_hyphenated())
+            })?,
+            None => write!(f, "\n[skipped] wabbajack entries (not a .wabbajack file)")?,
+        }
+        write_category(f, "outputs written by more than one directive", &self.duplicate_outputs, |p| {
+            format!("[{}] by directives {}", p.to, p.indices.iter().map(|index| format!("#{index}")).join(", "))
+        })?;
+        write_category(f, "BSA files that no directive stages", &self.unstaged_bsa_files, |p| {
+            format!("#{} [{}]: [{}] is not staged under [{}]", p.index, p.to, p.path, p.temp_id)
+        })?;
+        write_category(f, "nexus downloads for games without a known nexus domain", &self.unknown_nexus_games, |p| {
+            format!("{}: [{}]", p.archive, p.game_name)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests;
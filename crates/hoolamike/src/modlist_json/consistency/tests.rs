@@ -0,0 +1,85 @@
+use {
+    super::*,
+    crate::modlist_json::test_fixtures::{create_bsa, from_archive, inline_file, modlist, nexus, ARCHIVE_HASH},
+};
+
+const INLINE_ID: &str = "3f2504e0-4f89-11d3-9a0c-0305e82c3301";
+const MISSING_ID: &str = "6ba7b810-9dad-11d1-80b4-00c04fd430c8";
+
+#[test]
+fn test_consistent_modlist() {
+    let modlist = modlist(
+        vec![nexus("a.7z", "SkyrimSpecialEdition", 2, 1)],
+        vec![
+            from_archive("mods\\a\\a.esp", ARCHIVE_HASH, "a.esp"),
+            from_archive("TEMP_BSA_FILES\\bsa-1\\textures\\rock.dds", ARCHIVE_HASH, "a.esp"),
+            create_bsa("mods\\a\\a.bsa", "bsa-1", &["Textures\\Rock.dds"]),
+            inline_file("mods\\a\\meta.ini", INLINE_ID),
+        ],
+    );
+    let report = ConsistencyReport::new(&modlist, Some(&[PathBuf::from(INLINE_ID), PathBuf::from("modlist")]));
+    assert!(report.is_ok(), "{report}");
+}
+
+#[test]
+fn test_every_category_is_reported() {
+    let modlist = modlist(
+        vec![nexus("a.7z", "NotAGame", 2, 1)],
+        vec![
+            from_archive("mods\\a\\a.esp", "bWlzc2luZzA=", "a.esp"),
+            from_archive("MODS/A/A.ESP", ARCHIVE_HASH, "a.esp"),
+            create_bsa("mods\\a\\a.bsa", "bsa-1", &["textures\\rock.dds"]),
+            inline_file("mods\\a\\meta.ini", MISSING_ID),
+        ],
+    );
+    let report = ConsistencyReport::new(&modlist, Some(&[PathBuf::from(INLINE_ID)]));
+    assert_eq!(
+        report
+            .missing_archives
+            .iter()
+            .map(|p| p.index)
+            .collect::<Vec<_>>(),
+        [0]
+    );
+    assert_eq!(
+        report
+            .missing_wabbajack_entries
+            .as_ref()
+            .map(|missing| missing.iter().map(|p| p.id.to_string()).collect::<Vec<_>>()),
+        Some(vec![MISSING_ID.to_string()])
+    );
+    // paths differing only in case and separators are the same file
+    assert_eq!(
+        report
+            .duplicate_outputs
+            .iter()
+            .map(|p| p.indices.clone())
+            .collect::<Vec<_>>(),
+        [vec![0, 1]]
+    );
+    assert_eq!(
+        report
+            .unstaged_bsa_files
+            .iter()
+            .map(|p| p.path.as_str())
+            .collect::<Vec<_>>(),
+        ["textures\\rock.dds"]
+    );
+    assert_eq!(
+        report
+            .unknown_nexus_games
+            .iter()
+            .map(|p| p.game_name.as_str())
+            .collect::<Vec<_>>(),
+        ["NotAGame"]
+    );
+    assert_eq!(report.problem_count(), 5);
+}
+
+#[test]
+fn test_entries_are_skipped_without_wabbajack_file() {
+    let modlist = modlist(vec![], vec![inline_file("a.ini", MISSING_ID)]);
+    let report = ConsistencyReport::new(&modlist, None);
+    assert!(report.is_ok(), "{report}");
+    assert!(report.to_string().contains("[skipped]"));
+}
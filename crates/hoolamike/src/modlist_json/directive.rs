@@ -6,7 +6,6 @@ pub mod create_bsa_directive;
 
 pub use archive_hash_path::ArchiveHashPath;
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct FromArchiveDirective {
     /// hash: String
@@ -25,10 +24,12 @@ pub struct FromArchiveDirective {
     /// Description: Paths within an archive, identified by their hashes.
     /// Usage: Locate specific files inside archives.
     pub archive_hash_path: ArchiveHashPath,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct InlineFileDirective {
     /// hash: String
@@ -48,10 +49,12 @@ pub struct InlineFileDirective {
     /// Description: Destination path for the directive's output.
     /// Usage: Where to place extracted or processed files.
     pub to: MaybeWindowsPath,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct PatchedFromArchiveDirective {
     /// hash: String
@@ -79,10 +82,12 @@ pub struct PatchedFromArchiveDirective {
     /// Description: Identifier for a patch operation.
     /// Usage: Apply the correct patch during installation.
     pub patch_id: uuid::Uuid,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct RemappedInlineFileDirective {
     /// hash: String
@@ -102,10 +107,12 @@ pub struct RemappedInlineFileDirective {
     /// Description: Destination path for the directive's output.
     /// Usage: Where to place extracted or processed files.
     pub to: MaybeWindowsPath,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct TransformedTextureDirective {
     /// hash: String
@@ -128,4 +135,7 @@ pub struct TransformedTextureDirective {
     /// Description: Paths within an archive, identified by their hashes.
     /// Usage: Locate specific files inside archives.
     pub archive_hash_path: ArchiveHashPath,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
@@ -1,7 +1,6 @@
 use {super::*, crate::serde_type_guard};
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct CreateBSADirectiveKind<DirectiveState, FileState> {
     /// hash: String
@@ -29,13 +28,15 @@ pub struct CreateBSADirectiveKind<DirectiveState, FileState> {
     /// Description: Additional metadata about the directive's state.
     /// Usage: Process directives accurately based on their state.
     pub state: DirectiveState,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 pub mod ba2;
 pub mod bsa;
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum CreateBSADirective {
     Bsa(bsa::Bsa),
@@ -52,4 +53,22 @@ impl CreateBSADirective {
             CreateBSADirective::Ba2(d) => d.size,
         }
     }
+    pub fn hash(&self) -> &str {
+        match self {
+            CreateBSADirective::Bsa(d) => &d.hash,
+            CreateBSADirective::Ba2(d) => &d.hash,
+        }
+    }
+    pub fn to(&self) -> &MaybeWindowsPath {
+        match self {
+            CreateBSADirective::Bsa(d) => &d.to,
+            CreateBSADirective::Ba2(d) => &d.to,
+        }
+    }
+    pub fn extra(&self) -> &ExtraFields {
+        match self {
+            CreateBSADirective::Bsa(d) => &d.extra,
+            CreateBSADirective::Ba2(d) => &d.extra,
+        }
+    }
 }
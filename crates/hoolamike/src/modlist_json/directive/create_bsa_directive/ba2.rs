@@ -1,7 +1,6 @@
 use {super::*, crate::serde_type_guard, type_guard::WithTypeGuard};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct BA2DX10Entry {
     /// dir_hash: u64
@@ -61,10 +60,12 @@ pub struct BA2DX10Entry {
     /// Description: File system path to the file.
     /// Usage: Access the file during installation.
     pub path: MaybeWindowsPath,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(deny_unknown_fields)]
 #[serde(rename_all = "PascalCase")]
 pub struct BA2FileEntry {
     /// align: u64
@@ -99,12 +100,14 @@ pub struct BA2FileEntry {
     /// Description: File system path to the file.
     /// Usage: Access the file during installation.
     pub path: MaybeWindowsPath,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug, Serialize, Deserialize, enum_kinds::EnumKind)]
+#[derive(Debug, Serialize, Deserialize, Clone, enum_kinds::EnumKind)]
 #[serde(tag = "$type")]
-#[serde(deny_unknown_fields)]
 #[enum_kind(BA2FileStateKind, derive(Serialize, Deserialize, PartialOrd, Ord, derive_more::Display,))]
 pub enum FileState {
     #[serde(rename_all = "PascalCase")]
@@ -114,8 +117,7 @@ pub enum FileState {
 
 serde_type_guard!(BA2DirectiveStateGuard, "BA2State, Compression.BSA");
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct DirectiveStateData {
     /// has_name_table: bool
@@ -135,6 +137,9 @@ pub struct DirectiveStateData {
     /// Description: Version number of the directive or file format.
     /// Usage: Ensure compatibility with processing routines.
     pub version: u64,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 pub type DirectiveState = WithTypeGuard<DirectiveStateData, BA2DirectiveStateGuard>;
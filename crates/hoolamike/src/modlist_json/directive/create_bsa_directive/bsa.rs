@@ -9,7 +9,6 @@ pub enum Either<L, R> {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct FileStateData {
     pub flip_compression: bool,
     /// index: usize
@@ -20,14 +19,16 @@ pub struct FileStateData {
     /// Description: File system path to the file.
     /// Usage: Access the file during installation.
     pub path: MaybeWindowsPath,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 serde_type_guard!(BSAFileStateTypeGuard, "BSAFileState, Compression.BSA");
 pub type FileState = WithTypeGuard<FileStateData, BSAFileStateTypeGuard>;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "PascalCase")]
-#[serde(deny_unknown_fields)]
 pub struct DirectiveStateData {
     pub archive_flags: u32,
     pub file_flags: Either<u16, u32>,
@@ -39,6 +40,9 @@ pub struct DirectiveStateData {
     /// Description: Version number of the directive or file format.
     /// Usage: Ensure compatibility with processing routines.
     pub version: u64,
+    /// fields added by a newer version of wabbajack
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 serde_type_guard!(BSADirectiveStateTypeGuard, "BSAState, Compression.BSA");
@@ -0,0 +1,44 @@
+//! modlist json shared by the tests that need a small modlist to work on
+
+use {super::*, serde_json::json};
+
+pub const ARCHIVE_HASH: &str = "c291cmNlMDA=";
+
+pub fn nexus(name: &str, game_name: &str, mod_id: usize, file_id: usize) -> serde_json::Value {
+    json!({
+        "Hash": ARCHIVE_HASH, "Meta": "", "Name": name, "Size": 4,
+        "State": {
+            "$type": "NexusDownloader, Wabbajack.Lib",
+            "GameName": game_name, "FileID": file_id, "ModID": mod_id, "Author": null, "Description": null,
+            "ImageURL": null, "IsNSFW": false, "Name": name, "Version": "1.0"
+        }
+    })
+}
+
+pub fn from_archive(to: &str, source_hash: &str, path: &str) -> serde_json::Value {
+    json!({"$type": "FromArchive", "Hash": "aGFzaGhhc2g=", "Size": 4, "To": to, "ArchiveHashPath": [source_hash, path]})
+}
+
+pub fn inline_file(to: &str, source_data_id: &str) -> serde_json::Value {
+    json!({"$type": "InlineFile", "Hash": "aGFzaGhhc2g=", "Size": 4, "SourceDataID": source_data_id, "To": to})
+}
+
+pub fn create_bsa(to: &str, temp_id: &str, files: &[&str]) -> serde_json::Value {
+    json!({
+        "$type": "CreateBSA", "Hash": "aGFzaGhhc2g=", "Size": 4, "To": to, "TempID": temp_id,
+        "FileStates": files
+            .iter()
+            .enumerate()
+            .map(|(index, path)| json!({"$type": "BSAFileState, Compression.BSA", "FlipCompression": false, "Index": index, "Path": path}))
+            .collect::<Vec<_>>(),
+        "State": {"$type": "BSAState, Compression.BSA", "ArchiveFlags": 3, "FileFlags": 0, "Magic": "BSA\u{0}", "Version": 105}
+    })
+}
+
+pub fn modlist(archives: Vec<serde_json::Value>, directives: Vec<serde_json::Value>) -> Modlist {
+    serde_json::from_value(json!({
+        "Archives": archives, "Author": "", "Description": "", "Directives": directives, "GameType": "SkyrimSpecialEdition",
+        "Image": "", "IsNSFW": false, "Name": "test", "Readme": "", "Version": "1.0", "WabbajackVersion": "3.0", "Website": ""
+    }))
+    .expect("valid test modlist")
+}
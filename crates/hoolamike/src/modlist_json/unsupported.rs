@@ -0,0 +1,242 @@
+//! directives and download sources that this version of hoolamike does not understand (new types, or known ones that can't be read).
+//! they're kept as raw json instead of failing the whole modlist, and only become an error once something has to process them.
+//! fields that were added to known types are ignored, and only reported
+use {
+    super::*,
+    itertools::Itertools,
+    serde::de::DeserializeOwned,
+    serde_json::Value,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fmt::{self, Display},
+    },
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsupportedEntry {
+    pub raw: Value,
+}
+
+impl Serialize for UnsupportedEntry {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.raw.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UnsupportedEntry {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Value::deserialize(deserializer).map(|raw| Self { raw })
+    }
+}
+
+impl UnsupportedEntry {
+    pub fn type_name(&self) -> &str {
+        self.raw
+            .get("$type")
+            .and_then(Value::as_str)
+            .unwrap_or("<missing $type>")
+    }
+
+    /// deserializes the entry again as `T`, this time without falling back, to tell what's wrong with it
+    fn rejection<T: DeserializeOwned>(&self) -> String {
+        self.raw
+            .clone()
+            .tap_mut(|raw| {
+                if let Some(raw) = raw.as_object_mut() {
+                    raw.remove("$type");
+                }
+            })
+            .pipe(serde_json::from_value::<T>)
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_else(|| "it parses on its own, but not as a part of the modlist".to_string())
+    }
+
+    /// why the directive could not be read
+    pub fn directive_rejection(&self) -> String {
+        use directive::*;
+        match self.type_name() {
+            "CreateBSA" => self.rejection::<create_bsa_directive::CreateBSADirective>(),
+            "FromArchive" => self.rejection::<FromArchiveDirective>(),
+            "InlineFile" => self.rejection::<InlineFileDirective>(),
+            "PatchedFromArchive" => self.rejection::<PatchedFromArchiveDirective>(),
+            "RemappedInlineFile" => self.rejection::<RemappedInlineFileDirective>(),
+            "TransformedTexture" => self.rejection::<TransformedTextureDirective>(),
+            other => format!("unknown directive type [{other}]"),
+        }
+    }
+
+    /// why the download source could not be read
+    pub fn state_rejection(&self) -> String {
+        match self.type_name() {
+            "NexusDownloader, Wabbajack.Lib" => self.rejection::<NexusState>(),
+            "GameFileSourceDownloader, Wabbajack.Lib" => self.rejection::<GameFileSourceState>(),
+            "MegaDownloader, Wabbajack.Lib" => self.rejection::<MegaState>(),
+            "GoogleDriveDownloader, Wabbajack.Lib" => self.rejection::<GoogleDriveState>(),
+            "MediaFireDownloader+State, Wabbajack.Lib" => self.rejection::<MediaFireState>(),
+            "ModDBDownloader, Wabbajack.Lib" => self.rejection::<ModDBState>(),
+            "GitHubDownloader, Wabbajack.Lib" => self.rejection::<GitHubState>(),
+            "LoversLabOAuthDownloader, Wabbajack.Lib" | "VectorPlexusOAuthDownloader+State, Wabbajack.Lib" => self.rejection::<Ips4OAuth2State>(),
+            "HttpDownloader, Wabbajack.Lib" => self.rejection::<HttpState>(),
+            "ManualDownloader, Wabbajack.Lib" => self.rejection::<ManualState>(),
+            "WabbajackCDNDownloader+State, Wabbajack.Lib" => self.rejection::<WabbajackCDNDownloaderState>(),
+            other => format!("unknown download source [{other}]"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedDirective {
+    pub index: usize,
+    pub type_name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedArchive {
+    pub name: String,
+    pub type_name: String,
+    pub reason: String,
+}
+
+/// names of the fields that are ignored, by directive or download source type
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoredFields(pub BTreeMap<String, BTreeSet<String>>);
+
+impl IgnoredFields {
+    fn insert(&mut self, type_name: String, extra: &ExtraFields) {
+        if !extra.is_empty() {
+            self.0
+                .entry(type_name)
+                .or_default()
+                .extend(extra.keys().cloned());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for IgnoredFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ignoring fields added by a newer version of wabbajack:")?;
+        self.0
+            .iter()
+            .try_for_each(|(type_name, fields)| write!(f, "\n  - [{type_name}]: {}", fields.iter().join(", ")))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnsupportedReport {
+    pub directives: Vec<UnsupportedDirective>,
+    pub archives: Vec<UnsupportedArchive>,
+    /// these don't stop anything, they're only worth a warning
+    pub ignored_fields: IgnoredFields,
+}
+
+impl UnsupportedReport {
+    pub fn new(modlist: &Modlist) -> Self {
+        Self {
+            directives: modlist
+                .directives
+                .iter()
+                .enumerate()
+                .filter_map(|(index, directive)| match directive {
+                    Directive::Unsupported(entry) => Some(UnsupportedDirective {
+                        index,
+                        type_name: entry.type_name().to_string(),
+                        reason: entry.directive_rejection(),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            archives: modlist
+                .archives
+                .iter()
+                .filter_map(|archive| match &archive.state {
+                    State::Unsupported(entry) => Some(UnsupportedArchive {
+                        name: archive.descriptor.name.clone(),
+                        type_name: entry.type_name().to_string(),
+                        reason: entry.state_rejection(),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            ignored_fields: modlist
+                .directives
+                .iter()
+                .filter_map(|directive| {
+                    directive
+                        .extra_fields()
+                        .map(|extra| (directive.directive_kind().to_string(), extra))
+                })
+                .chain(modlist.archives.iter().filter_map(|archive| {
+                    archive
+                        .state
+                        .extra_fields()
+                        .map(|extra| (archive.state.kind().to_string(), extra))
+                }))
+                .chain(
+                    modlist
+                        .archives
+                        .iter()
+                        .map(|archive| ("Archive".to_string(), &archive.descriptor.extra)),
+                )
+                .chain(std::iter::once(("Modlist".to_string(), &modlist.extra)))
+                .fold(IgnoredFields::default(), |ignored, (type_name, extra)| {
+                    ignored.tap_mut(|ignored| ignored.insert(type_name, extra))
+                }),
+        }
+    }
+
+    /// only unsupported directives and archives count, ignored fields don't stop anything
+    pub fn is_empty(&self) -> bool {
+        self.directives.is_empty() && self.archives.is_empty()
+    }
+}
+
+impl Display for UnsupportedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            write!(f, "everything in the modlist is supported")?;
+            if !self.ignored_fields.is_empty() {
+                write!(f, "\n{}", self.ignored_fields)?;
+            }
+            return Ok(());
+        }
+        if !self.directives.is_empty() {
+            write!(f, "[{}] unsupported directives:", self.directives.len())?;
+            self.directives
+                .iter()
+                .into_group_map_by(|directive| directive.type_name.as_str())
+                .into_iter()
+                .sorted_by_key(|(type_name, _)| *type_name)
+                .try_for_each(|(type_name, directives)| {
+                    write!(
+                        f,
+                        "\n  - [{type_name}] x{} (first at index {}): {}",
+                        directives.len(),
+                        directives[0].index,
+                        directives[0].reason
+                    )
+                })?;
+        }
+        if !self.archives.is_empty() {
+            if !self.directives.is_empty() {
+                writeln!(f)?;
+            }
+            write!(f, "[{}] archives with unsupported download sources:", self.archives.len())?;
+            self.archives
+                .iter()
+                .try_for_each(|archive| write!(f, "\n  - {} [{}]: {}", archive.name, archive.type_name, archive.reason))?;
+        }
+        if !self.ignored_fields.is_empty() {
+            write!(f, "\n{}", self.ignored_fields)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,101 @@
+use {
+    super::*,
+    crate::modlist_json::test_fixtures::{create_bsa, from_archive, modlist, nexus, ARCHIVE_HASH},
+    serde_json::json,
+};
+
+#[test]
+fn test_known_directives_still_parse() -> anyhow::Result<()> {
+    serde_json::from_value::<Directive>(from_archive("mods\\a.esp", ARCHIVE_HASH, "a.esp"))
+        .map(|directive| assert_eq!(directive.directive_kind(), DirectiveKind::FromArchive))?;
+    Ok(())
+}
+
+#[test]
+fn test_unknown_directives_are_kept() -> anyhow::Result<()> {
+    let directives = serde_json::from_value::<Vec<Directive>>(json!([
+        from_archive("mods\\a.esp", ARCHIVE_HASH, "a.esp"),
+        {"$type": "MergedPatch", "Hash": "aGFzaGhhc2g=", "Size": 4, "To": "b.esp"},
+        {"$type": "FromArchive", "Hash": "aGFzaGhhc2g=", "Size": 4, "To": "c.esp"}
+    ]))?;
+    assert_eq!(
+        directives
+            .iter()
+            .map(Directive::directive_kind)
+            .collect::<Vec<_>>(),
+        [DirectiveKind::FromArchive, DirectiveKind::Unsupported, DirectiveKind::Unsupported]
+    );
+    // written back as it was read
+    assert_eq!(serde_json::to_value(&directives[1])?["$type"], "MergedPatch");
+    match &directives[2] {
+        Directive::Unsupported(entry) => assert!(entry.directive_rejection().contains("ArchiveHashPath"), "{}", entry.directive_rejection()),
+        other => panic!("expected an unsupported directive, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_new_fields_of_known_directives_are_ignored() -> anyhow::Result<()> {
+    let directive = serde_json::from_value::<Directive>(from_archive("c.esp", ARCHIVE_HASH, "c.esp").tap_mut(|directive| directive["Compression"] = json!(3)))?;
+    assert_eq!(directive.directive_kind(), DirectiveKind::FromArchive);
+    assert_eq!(
+        directive
+            .extra_fields()
+            .map(|extra| extra.keys().collect::<Vec<_>>()),
+        Some(vec!["Compression"])
+    );
+    // and written back as they were read
+    assert_eq!(serde_json::to_value(&directive)?["Compression"], 3);
+    Ok(())
+}
+
+#[test]
+fn test_unknown_download_sources_are_kept() -> anyhow::Result<()> {
+    let archive = serde_json::from_str::<Archive>(
+        r#"{
+            "Hash": "aGFzaGhhc2g=", "Meta": "", "Name": "a.7z", "Size": 4,
+            "State": {"$type": "BunkrDownloader, Wabbajack.Lib", "Url": "https://example.com"}
+        }"#,
+    )?;
+    match &archive.state {
+        State::Unsupported(entry) => assert_eq!(entry.state_rejection(), "unknown download source [BunkrDownloader, Wabbajack.Lib]"),
+        other => panic!("expected an unsupported state, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_new_fields_of_nested_states_are_kept() -> anyhow::Result<()> {
+    let raw = create_bsa("a.bsa", "bsa-1", &["textures\\a.dds"]).tap_mut(|directive| {
+        directive["FileStates"][0]["Compression"] = json!(3);
+        directive["State"]["Checksum"] = json!("abc");
+    });
+    let directive = serde_json::from_value::<Directive>(raw.clone())?;
+    // not pushed out to the unsupported ones because of a field deep inside of it
+    assert_eq!(directive.directive_kind(), DirectiveKind::CreateBSA);
+    assert_eq!(serde_json::to_value(&directive)?, raw);
+    Ok(())
+}
+
+#[test]
+fn test_new_fields_of_the_modlist_and_archives_are_reported() {
+    let report = modlist(
+        vec![nexus("a.7z", "SkyrimSpecialEdition", 2, 1).tap_mut(|archive| archive["Downloaded"] = json!(true))],
+        vec![],
+    )
+    .tap_mut(|modlist| {
+        modlist.extra.insert("Tags".to_string(), json!([]));
+    })
+    .pipe_ref(UnsupportedReport::new);
+    assert!(report.is_empty());
+    assert_eq!(
+        report.ignored_fields,
+        IgnoredFields(
+            [
+                ("Archive".to_string(), ["Downloaded".to_string()].into()),
+                ("Modlist".to_string(), ["Tags".to_string()].into()),
+            ]
+            .into()
+        )
+    );
+}
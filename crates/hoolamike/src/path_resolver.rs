@@ -0,0 +1,341 @@
+//! windows authored modlists assume case insensitive paths, so every path hoolamike reads or writes
+//! is resolved against what's already on disk, one (cached) directory listing at a time
+use {
+    crate::safe_path::sanitize_relative_path,
+    anyhow::{Context, Result},
+    lru::LruCache,
+    once_cell::sync::Lazy,
+    parking_lot::{Mutex, MutexGuard},
+    std::{
+        collections::HashMap,
+        ffi::{OsStr, OsString},
+        hash::{DefaultHasher, Hash, Hasher},
+        num::NonZeroUsize,
+        path::{Component, Path, PathBuf},
+        time::SystemTime,
+    },
+    tap::prelude::*,
+};
+
+/// listings of the least recently used directories are dropped once there are more than this
+const CACHED_DIRECTORIES: usize = 4096;
+/// directory creation is serialized per parent directory, hashed into this many locks
+const CREATION_LOCKS: usize = 64;
+
+fn case_insensitive_key(name: &OsStr) -> String {
+    name.to_string_lossy().to_lowercase()
+}
+
+/// `path` with every component lowercased, for comparing paths the way windows would
+pub fn case_insensitive_path(path: &Path) -> PathBuf {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => OsString::from(case_insensitive_key(name)),
+            other => other.as_os_str().to_os_string(),
+        })
+        .collect()
+}
+
+fn modified(directory: &Path) -> Option<SystemTime> {
+    std::fs::metadata(directory)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[derive(Debug, Default)]
+struct DirectoryListing {
+    /// lowercase name -> names on disk, sorted
+    entries: HashMap<String, Vec<OsString>>,
+    /// modification time of the directory when it was listed, a miss is only trusted while it stays the same
+    modified: Option<SystemTime>,
+}
+
+impl DirectoryListing {
+    fn read(directory: &Path) -> Option<Self> {
+        // read before listing, so that anything added in the meantime shows up as a change later
+        let modified = modified(directory);
+        std::fs::read_dir(directory).ok().map(|entries| {
+            entries.filter_map(|entry| entry.ok()).fold(
+                Self {
+                    modified,
+                    ..Default::default()
+                },
+                |listing, entry| listing.tap_mut(|listing| listing.insert(entry.file_name())),
+            )
+        })
+    }
+
+    fn insert(&mut self, name: OsString) {
+        let names = self.entries.entry(case_insensitive_key(&name)).or_default();
+        if !names.contains(&name) {
+            names.push(name);
+            names.sort();
+        }
+    }
+
+    /// an exact match wins, otherwise the first name in sort order, so the pick is the same on every run
+    fn pick(&self, name: &OsStr) -> Option<&OsStr> {
+        self.entries
+            .get(&case_insensitive_key(name))
+            .and_then(|names| {
+                names
+                    .iter()
+                    .find(|existing| existing.as_os_str() == name)
+                    .or_else(|| names.first())
+            })
+            .map(OsString::as_os_str)
+    }
+}
+
+/// directories that don't exist are not cached, so that they're picked up once something creates them.
+/// the cache lock is never held while the filesystem is accessed
+#[derive(Debug)]
+pub struct PathResolver {
+    directories: Mutex<LruCache<PathBuf, DirectoryListing>>,
+    creating: [Mutex<()>; CREATION_LOCKS],
+}
+
+impl Default for PathResolver {
+    fn default() -> Self {
+        Self::with_capacity(NonZeroUsize::new(CACHED_DIRECTORIES).expect("not zero"))
+    }
+}
+
+impl PathResolver {
+    pub fn with_capacity(cached_directories: NonZeroUsize) -> Self {
+        Self {
+            directories: Mutex::new(LruCache::new(cached_directories)),
+            creating: std::array::from_fn(|_| Mutex::new(())),
+        }
+    }
+
+    /// the on-disk name of `name` in `directory`, if there is one
+    fn lookup(&self, directory: &Path, name: &OsStr) -> Option<OsString> {
+        let cached = self
+            .directories
+            .lock()
+            .get(directory)
+            .map(|listing| (listing.pick(name).map(OsStr::to_os_string), listing.modified));
+        match cached {
+            Some((Some(existing), _)) => Some(existing),
+            Some((None, Some(listed_at))) if modified(directory) == Some(listed_at) => None,
+            // not listed yet, or something outside of the resolver changed the directory since
+            _ => DirectoryListing::read(directory).and_then(|listing| {
+                let existing = listing.pick(name).map(OsStr::to_os_string);
+                self.directories.lock().put(directory.to_owned(), listing);
+                existing
+            }),
+        }
+    }
+
+    /// for entries the resolver knows exist, the listing is refreshed instead of read again on the next miss
+    fn remember(&self, directory: &Path, name: &OsStr) {
+        let modified = modified(directory);
+        if let Some(listing) = self.directories.lock().get_mut(directory) {
+            listing.insert(name.to_os_string());
+            listing.modified = modified;
+        }
+    }
+
+    fn creation_lock(&self, parent: &Path) -> MutexGuard<'_, ()> {
+        let mut hasher = DefaultHasher::new();
+        parent.hash(&mut hasher);
+        self.creating[hasher.finish() as usize % CREATION_LOCKS].lock()
+    }
+
+    /// new directories keep the requested casing, unless a different casing of it was created in the meantime
+    fn create_directory(&self, parent: &Path, name: &OsStr) -> Result<OsString> {
+        let _creating = self.creation_lock(parent);
+        if let Some(existing) = self.lookup(parent, name) {
+            return Ok(existing);
+        }
+        let directory = parent.join(name);
+        match std::fs::create_dir(&directory) {
+            Err(error) if error.kind() != std::io::ErrorKind::AlreadyExists => Err(error).with_context(|| format!("creating [{}]", directory.display())),
+            _ => {
+                self.remember(parent, name);
+                Ok(name.to_os_string())
+            }
+        }
+    }
+
+    fn walk(&self, root: &Path, relative: &Path, create_directories: bool) -> Result<PathBuf> {
+        if create_directories {
+            std::fs::create_dir_all(root).with_context(|| format!("creating [{}]", root.display()))?;
+        }
+        let components = relative.components().collect::<Vec<_>>();
+        let mut current = root.to_path_buf();
+        for (idx, component) in components.iter().enumerate() {
+            let name = match *component {
+                Component::Normal(name) => name,
+                other => {
+                    current.push(other);
+                    continue;
+                }
+            };
+            // the file itself is only remembered once it was written, see [PathResolver::written]
+            let existing = match self.lookup(&current, name) {
+                None if create_directories && idx + 1 < components.len() => Some(self.create_directory(&current, name)?),
+                existing => existing,
+            };
+            current.push(existing.as_deref().unwrap_or(name));
+        }
+        Ok(current)
+    }
+
+    /// `root` joined with `relative`, using the on-disk casing of every component that exists.
+    /// like with [Path::join], an absolute `relative` replaces `root`
+    pub fn resolve(&self, root: &Path, relative: &Path) -> PathBuf {
+        self.walk(root, relative, false)
+            .expect("resolving without creating directories can't fail")
+    }
+
+    /// like [PathResolver::resolve], but missing parent directories are created
+    pub fn resolve_for_write(&self, root: &Path, relative: &Path) -> Result<PathBuf> {
+        self.walk(root, relative, true)
+            .with_context(|| format!("resolving [{}] in [{}] for writing", relative.display(), root.display()))
+    }
+
+    /// for files written to a path from [PathResolver::resolve_for_write], once they're actually on disk,
+    /// so that siblings written later reuse their casing
+    pub fn written(&self, path: &Path) {
+        if let Some((parent, name)) = path.parent().zip(path.file_name()) {
+            self.remember(parent, name);
+        }
+    }
+
+    /// for when something outside of the resolver renamed or removed files in `directory`
+    pub fn forget(&self, directory: &Path) {
+        let mut directories = self.directories.lock();
+        directories
+            .iter()
+            .map(|(cached, _)| cached)
+            .filter(|cached| cached.starts_with(directory))
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .for_each(|cached| {
+                directories.pop(&cached);
+            });
+    }
+}
+
+static RESOLVER: Lazy<PathResolver> = Lazy::new(PathResolver::default);
+
+pub fn resolver() -> &'static PathResolver {
+    &RESOLVER
+}
+
+/// shorthand for reading `relative` from `root` through the shared resolver
+pub fn existing_path(root: &Path, relative: &Path) -> PathBuf {
+    resolver().resolve(root, relative)
+}
+
+/// shorthand for writing `relative` into `root` through the shared resolver, `relative` can't point outside of `root`
+pub fn output_path(root: &Path, relative: &Path) -> Result<PathBuf> {
+    sanitize_relative_path(relative)
+        .with_context(|| format!("refusing to write outside of [{}]", root.display()))
+        .and_then(|relative| resolver().resolve_for_write(root, &relative))
+}
+
+/// for paths that are absolute already, like the ones with game directories substituted in
+pub fn existing_absolute_path(path: &Path) -> PathBuf {
+    existing_path(Path::new("/"), path)
+}
+
+pub fn output_absolute_path(path: &Path) -> Result<PathBuf> {
+    resolver().resolve_for_write(Path::new("/"), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_use_existing_casing() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir_all(root.path().join("Data/Textures"))?;
+        std::fs::write(root.path().join("Data/Textures/Sky.dds"), b"")?;
+        let resolver = PathResolver::default();
+        assert_eq!(
+            resolver.resolve(root.path(), Path::new("data/textures/sky.DDS")),
+            root.path().join("Data/Textures/Sky.dds")
+        );
+        assert_eq!(
+            resolver.resolve(root.path(), Path::new("data/meshes/rock.nif")),
+            root.path().join("Data/meshes/rock.nif")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_writes_do_not_create_duplicate_trees() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let resolver = PathResolver::default();
+        let first = resolver.resolve_for_write(root.path(), Path::new("Data/Textures/a.dds"))?;
+        std::fs::write(&first, b"")?;
+        resolver.written(&first);
+        let second = resolver.resolve_for_write(root.path(), Path::new("data/textures/b.dds"))?;
+        assert_eq!(second, root.path().join("Data/Textures/b.dds"));
+        assert_eq!(std::fs::read_dir(root.path())?.count(), 1);
+        assert_eq!(resolver.resolve_for_write(root.path(), Path::new("DATA/TEXTURES/A.DDS"))?, first);
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_paths_stay_inside_of_root() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        assert!(output_path(root.path(), Path::new("../outside.txt")).is_err());
+        assert!(output_path(root.path(), Path::new("/etc/passwd")).is_err());
+        assert_eq!(output_path(root.path(), Path::new("inside/../a.txt"))?, root.path().join("a.txt"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_exact_match_wins_over_other_casings() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir_all(root.path().join("textures"))?;
+        std::fs::create_dir_all(root.path().join("Textures"))?;
+        if std::fs::read_dir(root.path())?.count() != 2 {
+            // case insensitive filesystem, nothing to test
+            return Ok(());
+        }
+        let resolver = PathResolver::default();
+        assert_eq!(resolver.resolve(root.path(), Path::new("textures")), root.path().join("textures"));
+        assert_eq!(resolver.resolve(root.path(), Path::new("TEXTURES")), root.path().join("Textures"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unwritten_files_are_not_remembered() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let resolver = PathResolver::default();
+        resolver.resolve_for_write(root.path(), Path::new("Data/New.dds"))?;
+        assert_eq!(resolver.resolve(root.path(), Path::new("data/new.dds")), root.path().join("Data/new.dds"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_changes_made_outside_of_the_resolver_are_picked_up() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let resolver = PathResolver::default();
+        std::fs::create_dir_all(root.path().join("Data"))?;
+        assert_eq!(resolver.resolve(root.path(), Path::new("data/a.esp")), root.path().join("Data/a.esp"));
+        std::fs::write(root.path().join("Data/A.esp"), b"")?;
+        // modification times are coarse, so the change is made visible explicitly
+        std::fs::File::open(root.path().join("Data"))?.set_modified(SystemTime::UNIX_EPOCH)?;
+        assert_eq!(resolver.resolve(root.path(), Path::new("data/a.esp")), root.path().join("Data/A.esp"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_least_recently_used_listings_are_dropped() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir_all(root.path().join("Data/Textures"))?;
+        let resolver = PathResolver::with_capacity(NonZeroUsize::MIN);
+        assert_eq!(resolver.resolve(root.path(), Path::new("data/textures")), root.path().join("Data/Textures"));
+        assert_eq!(resolver.directories.lock().len(), 1);
+        Ok(())
+    }
+}
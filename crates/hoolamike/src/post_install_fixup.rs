@@ -8,9 +8,11 @@ use {
 };
 
 #[instrument]
-fn post_install_fixup_linux() -> Result<()> {
+fn post_install_fixup_linux(config: &HoolamikeConfig) -> Result<()> {
     info!("applying linux fixes");
     Ok(())
+        // windows would treat these as the same directory
+        .and_then(|_| case_collisions::merge_case_collisions(&config.installation.installation_path).map(|_| ()))
 }
 
 macro_rules! target_os_only {
@@ -30,6 +32,7 @@ macro_rules! target_os_only {
 
 // }
 
+pub mod case_collisions;
 pub mod diffing;
 
 #[extension_traits::extension(pub trait LinesPreservePlatform)]
@@ -172,53 +175,40 @@ pub mod common {
                     })
                 })
                 .and_then(|_| {
-                    info_span!("skyrimprefs.ini").in_scope(|| {
-                        all_files_with_name("skyrimprefs.ini").try_for_each(|file| {
-                            patch_file(&file, |contents| {
-                                contents
-                                    .lines_preserve_platform()
-                                    .pipe(|(sep, lines)| {
-                                        lines
-                                            .map(|line| {
-                                                if line.starts_with("iSize W") {
-                                                    format!("iSize W = {}", resolution.x).pipe(Cow::Owned)
-                                                } else if line.starts_with("iSize H") {
-                                                    format!("iSize H = {}", resolution.y).pipe(Cow::Owned)
-                                                } else {
-                                                    line.pipe(Cow::Borrowed)
-                                                }
-                                            })
-                                            .join(sep)
-                                    })
-                                    .pipe(Ok)
+                    // bethesda games keep the resolution in their prefs ini (as named in the game registry)
+                    let prefs_inis = crate::games::GAMES
+                        .iter()
+                        .filter_map(|game| game.prefs_ini)
+                        .map(|prefs_ini| prefs_ini.to_lowercase())
+                        .collect::<std::collections::BTreeSet<_>>();
+                    info_span!("prefs_ini", ?prefs_inis).in_scope(|| {
+                        list_all_files(root)
+                            .filter(|file| {
+                                file.file_name()
+                                    .map(|filename| prefs_inis.contains(&filename.to_string_lossy().to_lowercase()))
+                                    .unwrap_or_default()
                             })
-                            .tap_ok(|_| debug!("patched resolution to [{resolution}] at [{file:#?}]"))
-                        })
-                    })
-                })
-                .and_then(|_| {
-                    info_span!("Fallout4Prefs.ini").in_scope(|| {
-                        all_files_with_name("Fallout4Prefs.ini").try_for_each(|file| {
-                            patch_file(&file, |contents| {
-                                contents
-                                    .lines_preserve_platform()
-                                    .pipe(|(sep, lines)| {
-                                        lines
-                                            .map(|line| {
-                                                if line.starts_with("iSize W") {
-                                                    format!("iSize W = {}", resolution.x).pipe(Cow::Owned)
-                                                } else if line.starts_with("iSize H") {
-                                                    format!("iSize H = {}", resolution.y).pipe(Cow::Owned)
-                                                } else {
-                                                    line.pipe(Cow::Borrowed)
-                                                }
-                                            })
-                                            .join(sep)
-                                    })
-                                    .pipe(Ok)
+                            .try_for_each(|file| {
+                                patch_file(&file, |contents| {
+                                    contents
+                                        .lines_preserve_platform()
+                                        .pipe(|(sep, lines)| {
+                                            lines
+                                                .map(|line| {
+                                                    if line.starts_with("iSize W") {
+                                                        format!("iSize W = {}", resolution.x).pipe(Cow::Owned)
+                                                    } else if line.starts_with("iSize H") {
+                                                        format!("iSize H = {}", resolution.y).pipe(Cow::Owned)
+                                                    } else {
+                                                        line.pipe(Cow::Borrowed)
+                                                    }
+                                                })
+                                                .join(sep)
+                                        })
+                                        .pipe(Ok)
+                                })
+                                .tap_ok(|_| debug!("patched resolution to [{resolution}] at [{file:#?}]"))
                             })
-                            .tap_ok(|_| debug!("patched resolution to [{resolution}] at [{file:#?}]"))
-                        })
                     })
                 })
         }
@@ -238,6 +228,6 @@ pub(crate) fn run_post_install_fixup(config: &HoolamikeConfig) -> Result<()> {
     info!("running post install fixup");
     Ok(())
         // platform-specific fixes
-        .and_then(|_| target_os_only!("linux", post_install_fixup_linux()))
+        .and_then(|_| target_os_only!("linux", post_install_fixup_linux(config)))
         .and_then(|_| post_install_fixup_common(config))
 }
@@ -0,0 +1,192 @@
+//! merges sibling entries that only differ in case (`Meshes/` and `meshes/`), proton only ever sees one of them
+use {
+    super::*,
+    itertools::Itertools,
+    std::{
+        collections::BTreeMap,
+        ffi::OsString,
+        io::{BufReader, Read},
+    },
+    tracing::{debug, warn},
+};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CaseCollisionReport {
+    pub merged_directories: usize,
+    pub removed_duplicates: usize,
+    /// both entries were kept, the second one is where it was before
+    pub conflicts: Vec<(PathBuf, PathBuf)>,
+}
+
+fn list_directory(directory: &Path) -> Result<Vec<OsString>> {
+    std::fs::read_dir(directory)
+        .with_context(|| format!("reading [{}]", directory.display()))?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.file_name())
+                .context("reading entry")
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|entries| entries.tap_mut(|entries| entries.sort()))
+}
+
+fn same_contents(left: &Path, right: &Path) -> Result<bool> {
+    if left.metadata()?.len() != right.metadata()?.len() {
+        return Ok(false);
+    }
+    let open = |path: &Path| {
+        std::fs::File::open(path)
+            .with_context(|| format!("opening [{}]", path.display()))
+            .map(BufReader::new)
+    };
+    let (mut left, mut right) = (open(left)?, open(right)?);
+    let (mut left_buffer, mut right_buffer) = (vec![0; crate::BUFFER_SIZE], vec![0; crate::BUFFER_SIZE]);
+    loop {
+        let read = left.read(&mut left_buffer).context("reading left")?;
+        if read == 0 {
+            return Ok(true);
+        }
+        right
+            .read_exact(&mut right_buffer[..read])
+            .context("reading right")?;
+        if left_buffer[..read] != right_buffer[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// `duplicate` is removed if it's identical to `canonical`, both are kept otherwise
+fn resolve_file_collision(canonical: &Path, duplicate: &Path, report: &mut CaseCollisionReport) -> Result<()> {
+    match same_contents(canonical, duplicate)? {
+        true => std::fs::remove_file(duplicate)
+            .with_context(|| format!("removing [{}]", duplicate.display()))
+            .map(|_| report.removed_duplicates += 1),
+        false => {
+            report
+                .conflicts
+                .push((canonical.to_owned(), duplicate.to_owned()));
+            Ok(())
+        }
+    }
+}
+
+/// moves everything from `from` into `into`, merging whatever already exists there
+fn merge_into(from: &Path, into: &Path, report: &mut CaseCollisionReport) -> Result<()> {
+    let existing = list_directory(into)?
+        .into_iter()
+        .map(|name| (name.to_string_lossy().to_lowercase(), name))
+        .collect::<BTreeMap<_, _>>();
+    list_directory(from)?.into_iter().try_for_each(|name| {
+        let source = from.join(&name);
+        match existing.get(&name.to_string_lossy().to_lowercase()) {
+            None => std::fs::rename(&source, into.join(&name)).with_context(|| format!("moving [{}] into [{}]", source.display(), into.display())),
+            Some(existing) => {
+                let target = into.join(existing);
+                match (target.is_dir(), source.is_dir()) {
+                    (true, true) => merge_into(&source, &target, report),
+                    (false, false) => resolve_file_collision(&target, &source, report),
+                    _ => {
+                        report.conflicts.push((target, source));
+                        Ok(())
+                    }
+                }
+            }
+        }
+    })?;
+    // conflicting entries stay behind, so the directory is not always empty
+    if list_directory(from)?.is_empty() {
+        std::fs::remove_dir(from).with_context(|| format!("removing [{}]", from.display()))?;
+    }
+    Ok(())
+}
+
+/// the first casing in sort order is kept, the same one the path resolver picks
+fn merge_directory(directory: &Path, report: &mut CaseCollisionReport) -> Result<()> {
+    list_directory(directory)?
+        .into_iter()
+        .into_group_map_by(|name| name.to_string_lossy().to_lowercase())
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .try_for_each(|names| {
+            let mut names = names.into_iter().sorted();
+            let canonical = directory.join(names.next().expect("groups have at least two entries"));
+            names
+                .map(|name| directory.join(name))
+                .try_for_each(|duplicate| {
+                    debug!(canonical=%canonical.display(), duplicate=%duplicate.display(), "merging");
+                    match (canonical.is_dir(), duplicate.is_dir()) {
+                        (true, true) => merge_into(&duplicate, &canonical, report).map(|_| report.merged_directories += 1),
+                        (false, false) => resolve_file_collision(&canonical, &duplicate, report),
+                        _ => {
+                            report.conflicts.push((canonical.clone(), duplicate));
+                            Ok(())
+                        }
+                    }
+                })
+        })?;
+    list_directory(directory)?
+        .into_iter()
+        .map(|name| directory.join(name))
+        .filter(|entry| entry.is_dir() && !entry.is_symlink())
+        .try_for_each(|subdirectory| merge_directory(&subdirectory, report))
+}
+
+#[instrument]
+pub fn merge_case_collisions(root: &Path) -> Result<CaseCollisionReport> {
+    CaseCollisionReport::default()
+        .pipe(|mut report| merge_directory(root, &mut report).map(|_| report))
+        .tap_ok(|report| {
+            report.conflicts.iter().for_each(|(kept, conflicting)| {
+                warn!(
+                    "[{}] and [{}] only differ in case but have different contents, only one of them will be visible to the game",
+                    kept.display(),
+                    conflicting.display()
+                )
+            });
+            info!(
+                merged_directories = report.merged_directories,
+                removed_duplicates = report.removed_duplicates,
+                conflicts = report.conflicts.len(),
+                "merged case collisions"
+            );
+        })
+        .with_context(|| format!("merging case collisions in [{}]", root.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_case_collisions() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let write = |path: &str, contents: &str| {
+            root.path()
+                .join(path)
+                .pipe(|path| std::fs::create_dir_all(path.parent().unwrap()).and_then(|_| std::fs::write(path, contents)))
+        };
+        write("mods/Some Mod/Meshes/rock.nif", "rock")?;
+        write("mods/Some Mod/meshes/tree.nif", "tree")?;
+        write("mods/Some Mod/meshes/Rock.nif", "rock")?;
+        write("mods/Some Mod/MESHES/Armor/a.nif", "a")?;
+        write("mods/Some Mod/meshes/armor/a.nif", "different")?;
+        if list_directory(&root.path().join("mods/Some Mod"))?.len() != 3 {
+            // case insensitive filesystem, nothing to test
+            return Ok(());
+        }
+
+        let report = merge_case_collisions(root.path())?;
+        let mod_directory = root.path().join("mods/Some Mod");
+        assert_eq!(list_directory(&mod_directory)?, [OsString::from("MESHES"), OsString::from("meshes")]);
+        assert_eq!(
+            list_directory(&mod_directory.join("MESHES"))?,
+            ["Armor", "rock.nif", "tree.nif"].map(OsString::from)
+        );
+        assert_eq!(report.removed_duplicates, 1);
+        assert_eq!(
+            report.conflicts,
+            [(mod_directory.join("MESHES/Armor/a.nif"), mod_directory.join("meshes/armor/a.nif"))]
+        );
+        Ok(())
+    }
+}
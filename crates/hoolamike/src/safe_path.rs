@@ -0,0 +1,106 @@
+//! paths coming from modlists and archives are untrusted, so they must not be able to point outside of where they're joined onto
+use {
+    anyhow::{Context, Result},
+    std::path::{Component, Path, PathBuf},
+};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum UnsafePath {
+    #[error("[{0}] escapes the directory it's written into")]
+    ParentEscape(PathBuf),
+    #[error("[{0}] is absolute, expected a relative path")]
+    Absolute(PathBuf),
+    #[error("[{0}] starts with a drive letter, expected a relative path")]
+    DriveLetter(PathBuf),
+}
+
+fn is_drive_letter(component: &std::ffi::OsStr) -> bool {
+    component
+        .to_str()
+        .map(|component| component.as_bytes())
+        .is_some_and(|component| component.len() >= 2 && component[0].is_ascii_alphabetic() && component[1] == b':')
+}
+
+/// `..` is allowed as long as it does not leave the directory, the result has no `.` or `..` components
+pub fn sanitize_relative_path(path: &Path) -> Result<PathBuf, UnsafePath> {
+    path.components()
+        .enumerate()
+        .try_fold(PathBuf::new(), |mut sanitized, (idx, component)| match component {
+            Component::Prefix(_) => Err(UnsafePath::DriveLetter(path.to_owned())),
+            Component::RootDir => Err(UnsafePath::Absolute(path.to_owned())),
+            Component::Normal(name) if idx == 0 && is_drive_letter(name) => Err(UnsafePath::DriveLetter(path.to_owned())),
+            Component::Normal(name) => {
+                sanitized.push(name);
+                Ok(sanitized)
+            }
+            Component::CurDir => Ok(sanitized),
+            Component::ParentDir => match sanitized.pop() {
+                true => Ok(sanitized),
+                false => Err(UnsafePath::ParentEscape(path.to_owned())),
+            },
+        })
+}
+
+/// like [Path::join], but `relative` can't point outside of `root`
+pub fn sanitized_join(root: &Path, relative: &Path) -> Result<PathBuf> {
+    sanitize_relative_path(relative)
+        .map(|relative| root.join(relative))
+        .with_context(|| format!("refusing to write outside of [{}]", root.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::utils::MaybeWindowsPath};
+
+    fn windows(path: &str) -> PathBuf {
+        MaybeWindowsPath(path.to_string()).into_path()
+    }
+
+    #[test]
+    fn test_plain_paths_are_kept() {
+        assert_eq!(
+            sanitize_relative_path(&windows("Data\\Meshes\\rock.nif")),
+            Ok(PathBuf::from("Data/Meshes/rock.nif"))
+        );
+        assert_eq!(
+            sanitize_relative_path(&windows(".\\Data\\..\\Data\\rock.nif")),
+            Ok(PathBuf::from("Data/rock.nif"))
+        );
+    }
+
+    #[test]
+    fn test_parent_escapes_are_rejected() {
+        assert!(matches!(sanitize_relative_path(&windows("..\\..\\.bashrc")), Err(UnsafePath::ParentEscape(_))));
+        assert!(matches!(
+            sanitize_relative_path(&windows("Data\\..\\..\\.bashrc")),
+            Err(UnsafePath::ParentEscape(_))
+        ));
+    }
+
+    #[test]
+    fn test_absolute_paths_are_rejected() {
+        assert!(matches!(sanitize_relative_path(Path::new("/home/user/.bashrc")), Err(UnsafePath::Absolute(_))));
+        // UNC paths turn into absolute ones
+        assert!(matches!(
+            sanitize_relative_path(&windows("\\\\server\\share\\file")),
+            Err(UnsafePath::Absolute(_))
+        ));
+    }
+
+    #[test]
+    fn test_drive_letters_are_rejected() {
+        assert!(matches!(
+            sanitize_relative_path(&windows("C:\\Windows\\system32")),
+            Err(UnsafePath::DriveLetter(_))
+        ));
+        assert!(matches!(sanitize_relative_path(&windows("c:relative")), Err(UnsafePath::DriveLetter(_))));
+    }
+
+    #[test]
+    fn test_sanitized_join() -> Result<()> {
+        let root = Path::new("/install");
+        assert_eq!(sanitized_join(root, &windows("mods\\a.esp"))?, PathBuf::from("/install/mods/a.esp"));
+        assert!(sanitized_join(root, &windows("..\\outside")).is_err());
+        Ok(())
+    }
+}
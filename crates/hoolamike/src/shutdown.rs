@@ -0,0 +1,65 @@
+//! ctrl-c and SIGTERM stop the installation from scheduling new directives, whatever is already running gets to finish.
+//! outputs are only moved into place once complete, so running the same command again picks up where it stopped
+use {
+    std::sync::atomic::{AtomicBool, Ordering},
+    tracing::warn,
+};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, thiserror::Error)]
+#[error("installation was interrupted, run the same command again to resume")]
+pub struct Interrupted;
+
+/// true once the user asked to stop
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+/// errors out if a shutdown was requested, for long running work that checks in between steps
+pub fn check() -> Result<(), Interrupted> {
+    match requested() {
+        true => Err(Interrupted),
+        false => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+async fn signals() -> std::io::Result<impl futures::Stream<Item = ()>> {
+    use {
+        futures::StreamExt,
+        tokio::signal::unix::{signal, SignalKind},
+    };
+    // both are polled so either one wakes the listener up
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    Ok(futures::stream::poll_fn(move |cx| match interrupt.poll_recv(cx) {
+        std::task::Poll::Pending => terminate.poll_recv(cx),
+        ready => ready,
+    })
+    .fuse())
+}
+
+#[cfg(not(unix))]
+async fn signals() -> std::io::Result<impl futures::Stream<Item = ()>> {
+    Ok(futures::stream::unfold((), |_| async { tokio::signal::ctrl_c().await.ok().map(|_| ((), ())) }))
+}
+
+/// the first signal stops scheduling new work, the second one exits right away.
+/// partial files left behind by the latter are cleaned up by the next run
+pub async fn listen() -> std::io::Result<()> {
+    use futures::StreamExt;
+    let mut signals = signals().await?.boxed();
+    tokio::spawn(async move {
+        while signals.next().await.is_some() {
+            match REQUESTED.swap(true, Ordering::Relaxed) {
+                false => warn!("stopping: waiting for directives that are already running to finish, press ctrl-c again to abort right away"),
+                true => {
+                    warn!("aborting, run the same command again to resume");
+                    std::process::exit(130)
+                }
+            }
+        }
+    });
+    Ok(())
+}
@@ -4,7 +4,12 @@ use {
     futures::FutureExt,
     itertools::Itertools,
     serde::{Deserialize, Serialize},
-    std::{convert::identity, future::Future, path::PathBuf, sync::Arc},
+    std::{
+        convert::identity,
+        future::Future,
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
     tap::prelude::*,
     tempfile::{NamedTempFile, TempPath},
     tracing::{debug_span, info_span},
@@ -60,6 +65,15 @@ impl MaybeWindowsPath {
         };
         PathBuf::from(s)
     }
+
+    /// modlist paths are windows paths - this is the form they can be compared in (lowercase, forward slashes)
+    pub fn normalized(&self) -> String {
+        self.0
+            .split(['\\', '/'])
+            .filter(|segment| !segment.is_empty())
+            .join("/")
+            .to_lowercase()
+    }
 }
 
 pub fn boxed_iter<'a, T: 'a>(iter: impl Iterator<Item = T> + 'a) -> Box<dyn Iterator<Item = T> + 'a> {
@@ -103,6 +117,130 @@ impl<T: AsRef<std::path::Path>> T {
     }
 }
 
+/// prefix of the hidden sibling files outputs are written into before they're renamed into place
+pub const PARTIAL_FILE_PREFIX: &str = ".hoolamike-partial-";
+
+/// where [AtomicFile::keep_abandoned] keeps what was written
+pub fn abandoned_output_path(path: &Path) -> PathBuf {
+    path.with_added_extension("failed")
+}
+
+/// writes go into a hidden file next to `path`, which only replaces `path` once [AtomicFile::commit] is called.
+/// dropping it without committing removes the partial file, so an interrupted write never leaves a truncated output behind
+#[derive(Debug)]
+pub struct AtomicFile {
+    path: PathBuf,
+    /// only taken when committing
+    temp: Option<NamedTempFile>,
+    keep_abandoned: bool,
+}
+
+impl AtomicFile {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let parent = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        std::fs::create_dir_all(parent).with_context(|| format!("creating directory for [{}]", path.display()))?;
+        tempfile::Builder::new()
+            .prefix(PARTIAL_FILE_PREFIX)
+            .tempfile_in(parent)
+            .with_context(|| format!("creating partial file for [{}]", path.display()))
+            .and_then(|temp| {
+                // temp files are only readable by the owner by default, outputs should look like any other file
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    temp.as_file()
+                        .set_permissions(std::fs::Permissions::from_mode(0o644))
+                        .context("setting permissions of partial file")?;
+                }
+                Ok(temp)
+            })
+            .map(|temp| Self {
+                path: path.to_owned(),
+                temp: Some(temp),
+                keep_abandoned: false,
+            })
+    }
+
+    /// when it's never committed (eg. it failed validation), what was written is kept at [abandoned_output_path]
+    /// instead of being removed, so that it can be inspected
+    pub fn keep_abandoned(self, keep_abandoned: bool) -> Self {
+        self.tap_mut(|file| file.keep_abandoned = keep_abandoned)
+    }
+
+    fn temp(&mut self) -> &mut NamedTempFile {
+        self.temp.as_mut().expect("only taken when committing")
+    }
+
+    /// atomically replaces the target with everything written so far
+    pub fn commit(mut self) -> anyhow::Result<PathBuf> {
+        // otherwise the rename can reach the disk before the contents do, and a crash leaves an empty output behind
+        self.temp()
+            .as_file()
+            .sync_all()
+            .with_context(|| format!("flushing finished file for [{}] to disk", self.path.display()))?;
+        let path = std::mem::take(&mut self.path);
+        self.temp
+            .take()
+            .expect("only taken when committing")
+            .persist(&path)
+            .map_err(|e| e.error)
+            .with_context(|| format!("moving finished file into [{}]", path.display()))
+            .map(|_| path)
+            .tap_ok(|path| crate::path_resolver::resolver().written(path))
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if let Some(temp) = self.temp.take().filter(|_| self.keep_abandoned) {
+            let abandoned = abandoned_output_path(&self.path);
+            match temp.persist(&abandoned) {
+                Ok(_) => tracing::warn!("output was not finished, kept what was written at [{}]", abandoned.display()),
+                Err(e) => tracing::warn!(error = ?e.error, "could not keep unfinished output at [{}]", abandoned.display()),
+            }
+        }
+    }
+}
+
+impl std::io::Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        std::io::Write::write(self.temp(), buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::Write::flush(self.temp())
+    }
+}
+
+/// partial files are normally removed when a write is abandoned, but not when the process is killed
+pub fn remove_partial_files(root: &Path) -> anyhow::Result<usize> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            // the directory might not exist yet
+            Err(e) => {
+                tracing::debug!(?e, "skipping entry");
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with(PARTIAL_FILE_PREFIX))
+        })
+        .try_fold(0, |removed, entry| {
+            std::fs::remove_file(entry.path())
+                .with_context(|| format!("removing partial file [{}]", entry.path().display()))
+                .map(|_| removed + 1)
+        })
+}
+
 #[derive(derive_more::Display, Debug, Clone)]
 pub struct ArcError(Arc<anyhow::Error>);
 
@@ -161,6 +299,27 @@ pub fn chunk_while<T>(input: Vec<T>, mut chunk_while: impl FnMut(&[T]) -> bool)
     buf
 }
 
+#[test]
+fn test_atomic_file() -> anyhow::Result<()> {
+    use std::io::Write;
+    let directory = tempfile::tempdir()?;
+    let path = directory.path().join("nested/file.esp");
+    AtomicFile::create(&path)?.pipe(|mut file| file.write_all(b"abandoned"))?;
+    assert!(!path.exists());
+    assert_eq!(std::fs::read_dir(path.parent().unwrap())?.count(), 0);
+    AtomicFile::create(&path)?
+        .tap_mut(|file| file.write_all(b"finished").unwrap())
+        .commit()?;
+    assert_eq!(std::fs::read(&path)?, b"finished");
+    AtomicFile::create(&path)?
+        .keep_abandoned(true)
+        .pipe(|mut file| file.write_all(b"failed"))?;
+    assert_eq!(std::fs::read(abandoned_output_path(&path))?, b"failed");
+    assert_eq!(std::fs::read(&path)?, b"finished");
+    assert_eq!(remove_partial_files(directory.path())?, 0);
+    Ok(())
+}
+
 #[test]
 fn test_chunk_while() {
     use std::iter::repeat;
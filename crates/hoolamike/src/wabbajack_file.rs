@@ -1,11 +1,12 @@
 use {
-    crate::{compression::ProcessArchive, install_modlist::directives::wabbajack_file_handle::WabbajackFileHandle, utils::PathReadWrite},
-    anyhow::{Context, Result},
-    std::{
-        io::Read,
-        path::{Path, PathBuf},
+    crate::{
+        compression::ProcessArchive,
+        install_modlist::directives::wabbajack_file_handle::WabbajackFileHandle,
+        modlist_json::parsing_helpers,
+        utils::PathReadWrite,
     },
-    tap::prelude::*,
+    anyhow::{Context, Result},
+    std::path::{Path, PathBuf},
 };
 
 #[derive(Debug)]
@@ -15,11 +16,12 @@ pub struct WabbajackFile {
     pub modlist: super::modlist_json::Modlist,
 }
 
-const MODLIST_JSON_FILENAME: &str = "modlist";
+pub(crate) const MODLIST_JSON_FILENAME: &str = "modlist";
 
 impl WabbajackFile {
+    /// only the listing and the modlist itself are read, nothing is extracted
     #[tracing::instrument]
-    pub fn load_wabbajack_file(at_path: PathBuf) -> Result<(WabbajackFileHandle, Self)> {
+    pub fn read_modlist(at_path: &Path) -> Result<(Vec<PathBuf>, super::modlist_json::Modlist)> {
         at_path
             .open_file_read()
             .and_then(|(_, file)| crate::compression::compress_tools::ArchiveHandle::new(file))
@@ -29,25 +31,21 @@ impl WabbajackFile {
                     archive
                         .get_handle(Path::new(MODLIST_JSON_FILENAME))
                         .context("looking up file by name")
-                        .and_then(|mut handle| {
-                            String::new()
-                                .pipe(|mut out| handle.read_to_string(&mut out).map(|_| out))
-                                .context("reading modlist json to string")
-                        })
-                        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).context("reading archive json contents"))
-                        .and_then(|json| {
-                            serde_json::to_string_pretty(&json)
-                                .context("serializing json")
-                                .and_then(|output| serde_json::from_str(&output).context("output is a valid json but not a valid modlist file"))
-                        })
+                        .and_then(parsing_helpers::read_modlist)
                         .with_context(|| format!("reading [{MODLIST_JSON_FILENAME}]"))
-                        .map(|modlist| Self {
-                            wabbajack_file_path: at_path.clone(),
-                            wabbajack_entries: entries,
-                            modlist,
-                        })
-                        .and_then(|data| WabbajackFileHandle::from_archive(at_path).map(|archive| (archive, data)))
+                        .map(|modlist| (entries, modlist))
                 })
             })
     }
+
+    #[tracing::instrument]
+    pub fn load_wabbajack_file(at_path: PathBuf) -> Result<(WabbajackFileHandle, Self)> {
+        Self::read_modlist(&at_path)
+            .map(|(wabbajack_entries, modlist)| Self {
+                wabbajack_file_path: at_path.clone(),
+                wabbajack_entries,
+                modlist,
+            })
+            .and_then(|data| WabbajackFileHandle::from_archive(at_path).map(|archive| (archive, data)))
+    }
 }